@@ -0,0 +1,45 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use anyhow::Result;
+use crate::console::{on_attach, on_detach, HookId};
+use crate::platform::config_dir;
+
+/// File name of the audit log inside the config directory
+const AUDIT_FILE: &str = "audit.log";
+
+/// Location of the audit log
+pub fn audit_path() -> PathBuf {
+    config_dir().join(AUDIT_FILE)
+}
+
+/// Append a timestamped entry for an action taken on a target on the user's behalf
+pub fn log(pid: Option<u32>, action: &str) -> Result<()> {
+    let path = audit_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let target = pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string());
+    writeln!(
+        file,
+        "{}\tpid={}\t{}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        target,
+        action.replace(['\r', '\n'], " ")
+    )?;
+    Ok(())
+}
+
+/// Log every session start and end from the attach hooks, until the
+/// returned hooks are unsubscribed
+pub fn watch_sessions() -> Vec<HookId> {
+    vec![
+        on_attach(|pid| {
+            let _ = log(Some(pid), "session attached");
+        }),
+        on_detach(|pid| {
+            let _ = log(Some(pid), "session ended");
+        }),
+    ]
+}
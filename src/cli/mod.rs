@@ -0,0 +1,44 @@
+use anyhow::{Result, anyhow};
+
+/// What the GUI should do right after it starts, from command line flags
+#[derive(Debug, Clone, Default)]
+pub struct StartupIntent {
+    /// `--attach-pid <pid>`
+    pub attach_pid: Option<u32>,
+    /// `--attach-title <pattern>`, a wildcard pattern matched against window titles
+    pub attach_title: Option<String>,
+    /// Problem with the command line, shown in the GUI since there is no console
+    pub error: Option<String>,
+}
+
+impl StartupIntent {
+    /// Build the intent from process arguments (without the program name)
+    pub fn from_args(args: &[String]) -> Self {
+        Self::parse(args).unwrap_or_else(|e| StartupIntent {
+            error: Some(e.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut intent = StartupIntent::default();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            let mut value = || iter.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+            match arg.as_str() {
+                "--attach-pid" => {
+                    let v = value()?;
+                    intent.attach_pid = Some(v.parse().map_err(|_| anyhow!("Invalid PID \"{}\"", v))?);
+                }
+                "--attach-title" => intent.attach_title = Some(value()?.clone()),
+                _ => return Err(anyhow!("Unknown argument \"{}\"", arg)),
+            }
+        }
+
+        if intent.attach_pid.is_some() && intent.attach_title.is_some() {
+            return Err(anyhow!("--attach-pid and --attach-title cannot be combined"));
+        }
+        Ok(intent)
+    }
+}
@@ -1,22 +1,167 @@
-use windows::Win32::System::Console::{AttachConsole, FreeConsole, GetConsoleWindow};
+use std::sync::Mutex;
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::System::Console::{AttachConsole, FreeConsole, GetConsoleWindow, SetConsoleCtrlHandler};
+#[cfg(feature = "gui")]
+use windows::Win32::System::Console::{GetConsoleProcessList, GetConsoleTitleW};
 use windows::core::Error as WinError;
 use anyhow::{Result, anyhow};
+use super::handles::close_cached;
+use crate::repro;
+#[cfg(feature = "gui")]
+use crate::process::console_attach_candidates;
 
 /// Attach to a process's console
 pub fn attach_to_console(pid: u32) -> Result<()> {
-    unsafe {
+    let result = unsafe {
         // Free any current console attachment first
+        close_cached();
         let _ = FreeConsole();
 
         // Attach to the target process's console
         AttachConsole(pid)
-            .map_err(|e| anyhow!("Failed to attach to console PID {}: {}", pid, e.to_string()))?;
+            .map_err(|e| anyhow!("Failed to attach to console PID {}: {}", pid, e.to_string()))
+    };
+    repro::record_call("attach_to_console", &result);
+    result
+}
+
+/// Live [`CtrlGuard`]s; our ignore-everything control handler is installed while nonzero
+static CTRL_GUARDS: Mutex<usize> = Mutex::new(0);
+
+/// Swallow every console control event delivered to this process
+unsafe extern "system" fn ignore_ctrl_event(_ctrl_type: u32) -> BOOL {
+    BOOL(1)
+}
+
+/// Keeps console control events from killing us while held. The handler is
+/// installed with the first guard and removed when the last one is dropped.
+///
+/// A Ctrl+C written into the target's input is raised in every process
+/// attached to that console, including ours. Each session holds a guard for
+/// its whole life (not just each attach) because the event arrives on its
+/// own thread, possibly after the poll has already detached, and another
+/// session may still be attached when one ends.
+pub struct CtrlGuard(());
+
+impl CtrlGuard {
+    pub fn acquire() -> Self {
+        let mut guards = CTRL_GUARDS.lock().unwrap_or_else(|e| e.into_inner());
+        if *guards == 0 {
+            unsafe {
+                let _ = SetConsoleCtrlHandler(Some(Some(ignore_ctrl_event)), true);
+            }
+        }
+        *guards += 1;
+        Self(())
     }
-    Ok(())
+}
+
+impl Drop for CtrlGuard {
+    fn drop(&mut self) {
+        let mut guards = CTRL_GUARDS.lock().unwrap_or_else(|e| e.into_inner());
+        *guards -= 1;
+        if *guards == 0 {
+            unsafe {
+                let _ = SetConsoleCtrlHandler(Some(Some(ignore_ctrl_event)), false);
+            }
+        }
+    }
+}
+
+/// Whether some session holds a [`CtrlGuard`]
+pub fn ctrl_guarded() -> bool {
+    *CTRL_GUARDS.lock().unwrap_or_else(|e| e.into_inner()) > 0
+}
+
+#[cfg(feature = "gui")]
+/// How an attachment to a target's console was achieved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachRoute {
+    /// AttachConsole on the target PID itself
+    Direct,
+    /// AttachConsole on another process that shares the target's console
+    ViaProcess { pid: u32, name: String },
+}
+
+#[cfg(feature = "gui")]
+impl AttachRoute {
+    /// The PID to pass to AttachConsole when re-attaching through this route
+    pub fn attach_pid(&self, target: u32) -> u32 {
+        match self {
+            AttachRoute::Direct => target,
+            AttachRoute::ViaProcess { pid, .. } => *pid,
+        }
+    }
+
+    /// Human-readable description for status messages
+    pub fn describe(&self) -> String {
+        match self {
+            AttachRoute::Direct => "direct".to_string(),
+            AttachRoute::ViaProcess { pid, name } => format!("via {} (PID {})", name, pid),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Attach to a process's console, falling back to processes sharing it.
+///
+/// If AttachConsole(pid) fails, the console host and related processes are
+/// tried in turn. A fallback only counts if the console we land on lists the
+/// target PID in GetConsoleProcessList, so we never read the wrong console.
+pub fn attach_with_fallback(pid: u32) -> Result<AttachRoute> {
+    let direct_err = match attach_to_console(pid) {
+        Ok(()) => return Ok(AttachRoute::Direct),
+        Err(e) => e,
+    };
+
+    for (candidate, name) in console_attach_candidates(pid) {
+        if attach_to_console(candidate).is_err() {
+            continue;
+        }
+        if console_process_list().contains(&pid) {
+            return Ok(AttachRoute::ViaProcess { pid: candidate, name });
+        }
+        let _ = detach_from_console();
+    }
+
+    Err(anyhow!("{} (no fallback route shares its console)", direct_err))
+}
+
+#[cfg(feature = "gui")]
+/// List the PIDs attached to the current console
+pub fn console_process_list() -> Vec<u32> {
+    let mut pids = vec![0u32; 64];
+    loop {
+        let count = unsafe { GetConsoleProcessList(&mut pids) } as usize;
+        if count == 0 {
+            return Vec::new();
+        }
+        if count <= pids.len() {
+            pids.truncate(count);
+            return pids;
+        }
+        // Buffer too small; the return value is the required size
+        pids = vec![0u32; count];
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Identity of the current console (its window handle), stable while the console lives
+pub fn console_window_id() -> isize {
+    unsafe { GetConsoleWindow().0 as isize }
+}
+
+#[cfg(feature = "gui")]
+/// Title of the current console window; empty if it has none
+pub fn console_title() -> String {
+    let mut buf = vec![0u16; 1024];
+    let len = unsafe { GetConsoleTitleW(&mut buf) } as usize;
+    String::from_utf16_lossy(&buf[..len.min(buf.len())])
 }
 
 /// Detach from the current console
 pub fn detach_from_console() -> Result<()> {
+    close_cached();
     unsafe {
         FreeConsole()
             .map_err(|e| anyhow!("Failed to detach from console: {}", e.to_string()))?;
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use super::{attach_to_console, attach_with_fallback, detach_from_console, send_command, AttachRoute, ConsoleStream, LineEvent, ReadWindow};
+
+/// The console calls a worker session makes on its target, so a recorded
+/// trace can be replayed in place of the real console
+pub trait ConsoleBackend {
+    /// Attach this process to the console of `pid`
+    fn attach(&mut self, pid: u32) -> Result<()>;
+
+    /// Attach to the console of `pid` through whichever route reaches it
+    fn attach_route(&mut self, pid: u32) -> Result<AttachRoute>;
+
+    /// Detach from the current console, if any
+    fn detach(&mut self);
+
+    /// Read `window` of the attached console into `stream`
+    fn poll(&mut self, stream: &mut ConsoleStream, window: ReadWindow) -> Result<Vec<LineEvent>>;
+
+    /// Type a command into the console followed by Enter
+    fn send_command(&mut self, command: &str) -> Result<()>;
+}
+
+/// The attached Win32 console
+pub struct Win32Console;
+
+impl ConsoleBackend for Win32Console {
+    fn attach(&mut self, pid: u32) -> Result<()> {
+        attach_to_console(pid)
+    }
+
+    fn attach_route(&mut self, pid: u32) -> Result<AttachRoute> {
+        attach_with_fallback(pid)
+    }
+
+    fn detach(&mut self) {
+        let _ = detach_from_console();
+    }
+
+    fn poll(&mut self, stream: &mut ConsoleStream, window: ReadWindow) -> Result<Vec<LineEvent>> {
+        stream.poll(window)
+    }
+
+    fn send_command(&mut self, command: &str) -> Result<()> {
+        send_command(command)
+    }
+}
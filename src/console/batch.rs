@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use super::read::{buffer_info, open_conout, read_rows};
+use super::write::send_command;
+
+/// How often the prompt check looks at the cursor line
+const PROMPT_POLL: Duration = Duration::from_millis(50);
+
+/// How a [`send_commands`] batch behaves between and after commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchOptions {
+    /// Before each next command, wait up to this long for the shell's prompt
+    /// to come back; `None` sends back to back
+    pub wait_for_prompt: Option<Duration>,
+    /// Skip the rest of the batch after the first failure
+    pub stop_on_error: bool,
+}
+
+/// What happened to one command of a batch
+#[derive(Debug)]
+pub struct CommandOutcome {
+    pub command: String,
+    /// `None` when the command was skipped after an earlier failure
+    pub result: Option<Result<()>>,
+}
+
+/// Type each command with Enter on the attached console, in order, and
+/// report each command's outcome.
+///
+/// The caller holds the console turn for the whole batch, so polls cannot
+/// interleave.
+pub fn send_commands(commands: &[String], options: BatchOptions) -> Vec<CommandOutcome> {
+    let mut outcomes = Vec::with_capacity(commands.len());
+    let mut failed = false;
+    for (i, command) in commands.iter().enumerate() {
+        if failed && options.stop_on_error {
+            outcomes.push(CommandOutcome { command: command.to_string(), result: None });
+            continue;
+        }
+        let before = cursor_position().ok();
+        let mut result = send_command(command);
+        let more = i + 1 < commands.len();
+        if let (Ok(()), Some(timeout), true) = (&result, options.wait_for_prompt, more) {
+            result = wait_for_prompt(before, timeout);
+        }
+        failed |= result.is_err();
+        outcomes.push(CommandOutcome { command: command.to_string(), result: Some(result) });
+    }
+
+    outcomes
+}
+
+/// Wait until the cursor has left `before` and sits after a `>` prompt
+fn wait_for_prompt(before: Option<(usize, usize)>, timeout: Duration) -> Result<()> {
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+        std::thread::sleep(PROMPT_POLL);
+        let Ok(cursor) = cursor_position() else {
+            continue;
+        };
+        if Some(cursor) != before && prompt_at(cursor).unwrap_or(false) {
+            return Ok(());
+        }
+    }
+    Err(anyhow!("Command sent, but no prompt came back within {}s", timeout.as_secs()))
+}
+
+/// Buffer row and column of the cursor
+fn cursor_position() -> Result<(usize, usize)> {
+    let csbi = buffer_info(open_conout()?)?;
+    Ok((csbi.dwCursorPosition.Y.max(0) as usize, csbi.dwCursorPosition.X.max(0) as usize))
+}
+
+/// Whether the text left of the cursor ends in `>`, like cmd.exe's prompt
+fn prompt_at((row, col): (usize, usize)) -> Result<bool> {
+    if col == 0 {
+        return Ok(false);
+    }
+    let line = read_rows(open_conout()?, row, row + 1, 0..col, false)?;
+    Ok(line.first().is_some_and(|l| l.text.trim_end().ends_with('>')))
+}
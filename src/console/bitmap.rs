@@ -0,0 +1,170 @@
+use windows::Win32::Graphics::Gdi::{
+    CreateCompatibleDC, CreateDIBSection, CreateFontW, DeleteDC, DeleteObject, ExtTextOutW,
+    GdiFlush, SelectObject, SetBkColor, SetBkMode, SetTextColor, BITMAPINFO, BITMAPINFOHEADER,
+    BI_RGB, CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DIB_RGB_COLORS, ETO_CLIPPED, ETO_OPAQUE,
+    FIXED_PITCH, HGDIOBJ, NONANTIALIASED_QUALITY, OPAQUE, OUT_DEFAULT_PRECIS,
+};
+use windows::Win32::Foundation::{COLORREF, RECT};
+use windows::Win32::System::Console::{
+    GetConsoleScreenBufferInfoEx, GetCurrentConsoleFontEx, CONSOLE_FONT_INFOEX,
+    CONSOLE_SCREEN_BUFFER_INFOEX,
+};
+use windows::core::PCWSTR;
+use anyhow::{Result, anyhow};
+use super::read::{open_conout, read_console_cells, Cell, Grid, ReadWindow};
+
+/// Attribute bit asking the console to swap foreground and background
+const REVERSE_VIDEO: u16 = 0x4000;
+
+/// Render the visible window of the attached console to a PNG, drawn with
+/// the console's own font and color table.
+///
+/// Returns the encoded file. Fonts that report no cell width (some TrueType
+/// faces) are drawn at half their height per column.
+pub fn render_console_bitmap() -> Result<Vec<u8>> {
+    let conout = open_conout()?;
+    let mut info = CONSOLE_SCREEN_BUFFER_INFOEX {
+        cbSize: std::mem::size_of::<CONSOLE_SCREEN_BUFFER_INFOEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetConsoleScreenBufferInfoEx(conout, &mut info) }
+        .map_err(|e| anyhow!("Failed to get console buffer info: {}", e))?;
+    let mut font = CONSOLE_FONT_INFOEX {
+        cbSize: std::mem::size_of::<CONSOLE_FONT_INFOEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetCurrentConsoleFontEx(conout, false, &mut font) }
+        .map_err(|e| anyhow!("Failed to get console font: {}", e))?;
+    let grid = read_console_cells(ReadWindow::Viewport)?;
+
+    let cell_h = (font.dwFontSize.Y as i32).max(1);
+    let cell_w = if font.dwFontSize.X > 0 { font.dwFontSize.X as i32 } else { (cell_h / 2).max(1) };
+    let rgb = draw_grid(&grid, &info.ColorTable, &font, cell_w, cell_h)?;
+
+    let width = (grid.width as i32 * cell_w) as u32;
+    let height = (grid.height() as i32 * cell_h) as u32;
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()
+        .map_err(|e| anyhow!("Failed to encode PNG: {}", e))?;
+    writer.write_image_data(&rgb)
+        .map_err(|e| anyhow!("Failed to encode PNG: {}", e))?;
+    writer.finish()
+        .map_err(|e| anyhow!("Failed to encode PNG: {}", e))?;
+    Ok(png_bytes)
+}
+
+/// Draw every run of the grid into an off-screen DIB and return its pixels
+/// as packed RGB rows, top to bottom
+fn draw_grid(grid: &Grid<Cell>, colors: &[COLORREF; 16], font: &CONSOLE_FONT_INFOEX, cell_w: i32, cell_h: i32) -> Result<Vec<u8>> {
+    let width = grid.width as i32 * cell_w;
+    let height = grid.height() as i32 * cell_h;
+    if width == 0 || height == 0 {
+        return Err(anyhow!("Console window is empty"));
+    }
+
+    let bmi = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative height makes the DIB top-down
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    unsafe {
+        let dc = CreateCompatibleDC(None);
+        if dc.is_invalid() {
+            return Err(anyhow!("Failed to create a drawing context"));
+        }
+        let mut bits: *mut core::ffi::c_void = std::ptr::null_mut();
+        let bitmap = match CreateDIBSection(Some(dc), &bmi, DIB_RGB_COLORS, &mut bits, None, 0) {
+            Ok(bitmap) => bitmap,
+            Err(e) => {
+                let _ = DeleteDC(dc);
+                return Err(anyhow!("Failed to create bitmap: {}", e));
+            }
+        };
+        let hfont = CreateFontW(
+            cell_h, cell_w, 0, 0, font.FontWeight as i32, 0, 0, 0,
+            DEFAULT_CHARSET, OUT_DEFAULT_PRECIS, CLIP_DEFAULT_PRECIS, NONANTIALIASED_QUALITY,
+            FIXED_PITCH.0 as u32, PCWSTR(font.FaceName.as_ptr()),
+        );
+        let old_bitmap = SelectObject(dc, HGDIOBJ(bitmap.0));
+        let old_font = SelectObject(dc, HGDIOBJ(hfont.0));
+        SetBkMode(dc, OPAQUE);
+
+        for (y, row) in grid.rows().enumerate() {
+            for (start, len, attr) in row_runs(row) {
+                let (fg, bg) = if attr & REVERSE_VIDEO != 0 {
+                    (attr >> 4 & 0x0F, attr & 0x0F)
+                } else {
+                    (attr & 0x0F, attr >> 4 & 0x0F)
+                };
+                SetTextColor(dc, colors[fg as usize]);
+                SetBkColor(dc, colors[bg as usize]);
+
+                // Each character advances by the cells it covers, so wide
+                // characters and their continuation cells stay aligned
+                let mut text: Vec<u16> = Vec::with_capacity(len);
+                let mut dx: Vec<i32> = Vec::with_capacity(len);
+                for cell in &row[start..start + len] {
+                    if cell.ch == '\0' {
+                        if let Some(last) = dx.last_mut() {
+                            *last += cell_w;
+                        }
+                        continue;
+                    }
+                    let mut units = [0u16; 2];
+                    for unit in cell.ch.encode_utf16(&mut units) {
+                        text.push(*unit);
+                        dx.push(0);
+                    }
+                    // Surrogate pairs take the advance on their second unit
+                    if let Some(last) = dx.last_mut() {
+                        *last = cell_w;
+                    }
+                }
+
+                let left = start as i32 * cell_w;
+                let top = y as i32 * cell_h;
+                let rect = RECT { left, top, right: left + len as i32 * cell_w, bottom: top + cell_h };
+                let _ = ExtTextOutW(dc, left, top, ETO_OPAQUE | ETO_CLIPPED, Some(&rect), PCWSTR(text.as_ptr()), text.len() as u32, Some(dx.as_ptr()));
+            }
+        }
+        let _ = GdiFlush();
+
+        // BGRX pixels to packed RGB
+        let pixels = std::slice::from_raw_parts(bits as *const u8, (width * height * 4) as usize);
+        let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+        for px in pixels.chunks_exact(4) {
+            rgb.extend_from_slice(&[px[2], px[1], px[0]]);
+        }
+
+        SelectObject(dc, old_font);
+        SelectObject(dc, old_bitmap);
+        let _ = DeleteObject(HGDIOBJ(hfont.0));
+        let _ = DeleteObject(HGDIOBJ(bitmap.0));
+        let _ = DeleteDC(dc);
+        Ok(rgb)
+    }
+}
+
+/// `(first column, cell count, attribute)` for each run of equal attributes
+fn row_runs(row: &[Cell]) -> Vec<(usize, usize, u16)> {
+    let mut runs: Vec<(usize, usize, u16)> = Vec::new();
+    for (col, cell) in row.iter().enumerate() {
+        match runs.last_mut() {
+            Some((_, len, attr)) if *attr == cell.attr => *len += 1,
+            _ => runs.push((col, 1, cell.attr)),
+        }
+    }
+    runs
+}
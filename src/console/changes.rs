@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::thread;
+use std::time::Duration;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetClassNameW, GetMessageW, PostThreadMessageW, EVENT_CONSOLE_LAYOUT, EVENT_CONSOLE_UPDATE_REGION, MSG,
+    WINEVENT_OUTOFCONTEXT, WM_QUIT,
+};
+use anyhow::{Result, anyhow};
+
+/// Window class of a classic conhost window; ConPTY hosts such as Windows
+/// Terminal give the console a hidden pseudo window that raises no events
+const CONHOST_CLASS: &str = "ConsoleWindowClass";
+
+thread_local! {
+    /// Window watched by this hook thread and where to report its changes
+    static TARGET: RefCell<Option<(isize, Sender<()>)>> = const { RefCell::new(None) };
+}
+
+/// Wake-ups for output drawn in one conhost window, from the console
+/// WinEvents (region, simple and scroll updates, layout changes) that conhost
+/// raises for accessibility tools. The hook lives on its own thread, which
+/// pumps messages until the watch is dropped.
+pub struct ChangeWatch {
+    changes: Receiver<()>,
+    thread_id: u32,
+}
+
+impl ChangeWatch {
+    /// Watch console window `window` (see [`super::console_window_id`]).
+    ///
+    /// Fails when the window is not a classic conhost window or the hook
+    /// cannot be set; callers then keep polling.
+    pub fn start(window: isize) -> Result<Self> {
+        let hwnd = HWND(window as *mut _);
+        let mut class = [0u16; 64];
+        let len = unsafe { GetClassNameW(hwnd, &mut class) } as usize;
+        if String::from_utf16_lossy(&class[..len]) != CONHOST_CLASS {
+            return Err(anyhow!("Console window does not raise change events"));
+        }
+
+        let (changes_tx, changes) = bounded(1);
+        let (ready_tx, ready) = bounded(1);
+        thread::Builder::new()
+            .name("console-change-watch".to_string())
+            .spawn(move || hook_thread(window, changes_tx, ready_tx))
+            .map_err(|e| anyhow!("Failed to start change watch thread: {}", e))?;
+        let thread_id = ready.recv()
+            .map_err(|_| anyhow!("Change watch thread exited"))??;
+        Ok(Self { changes, thread_id })
+    }
+
+    /// Receives once per burst of changes, for use in `select!`
+    pub fn changes(&self) -> &Receiver<()> {
+        &self.changes
+    }
+
+    /// Let the rest of a burst arrive for `settle`, then forget it, so the
+    /// next wake-up is for output drawn after the read that follows
+    pub fn settle(&self, settle: Duration) {
+        thread::sleep(settle);
+        while self.changes.try_recv().is_ok() {}
+    }
+}
+
+impl Drop for ChangeWatch {
+    fn drop(&mut self) {
+        let _ = unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+    }
+}
+
+/// Set the hook, report this thread's id (or the failure) on `ready`, then
+/// pump messages so the hook callback runs until WM_QUIT
+fn hook_thread(window: isize, changes: Sender<()>, ready: Sender<Result<u32>>) {
+    TARGET.with(|t| *t.borrow_mut() = Some((window, changes)));
+    let hook = unsafe {
+        SetWinEventHook(EVENT_CONSOLE_UPDATE_REGION, EVENT_CONSOLE_LAYOUT, None, Some(on_console_event), 0, 0, WINEVENT_OUTOFCONTEXT)
+    };
+    if hook.is_invalid() {
+        let _ = ready.send(Err(anyhow!("Failed to hook console events")));
+        return;
+    }
+    let _ = ready.send(Ok(unsafe { GetCurrentThreadId() }));
+
+    let mut msg = MSG::default();
+    while unsafe { GetMessageW(&mut msg, None, 0, 0) }.as_bool() {}
+    let _ = unsafe { UnhookWinEvent(hook) };
+}
+
+/// Console event callback; runs on the hook thread while it waits for messages
+unsafe extern "system" fn on_console_event(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: HWND,
+    _object: i32,
+    _child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    TARGET.with(|t| {
+        if let Some((window, changes)) = t.borrow().as_ref()
+            && hwnd.0 as isize == *window
+        {
+            let _ = changes.try_send(());
+        }
+    });
+}
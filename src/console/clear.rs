@@ -0,0 +1,32 @@
+use windows::Win32::System::Console::{
+    FillConsoleOutputAttribute, FillConsoleOutputCharacterW, SetConsoleCursorPosition,
+    SetConsoleWindowInfo, COORD, SMALL_RECT,
+};
+use anyhow::{Result, anyhow};
+use super::read::{buffer_info, open_conout};
+
+/// Blank the attached console's active screen buffer and home the cursor,
+/// like `cls` but without going through the shell.
+///
+/// Works while a program is running in the foreground. Cells take the
+/// buffer's current attributes, and the window scrolls back to the top.
+pub fn clear_console() -> Result<()> {
+    let conout = open_conout()?;
+    let info = buffer_info(conout)?;
+    let cells = info.dwSize.X as u32 * info.dwSize.Y as u32;
+    let origin = COORD { X: 0, Y: 0 };
+
+    let mut written = 0;
+    unsafe { FillConsoleOutputCharacterW(conout, ' ' as u16, cells, origin, &mut written) }
+        .map_err(|e| anyhow!("Failed to blank console: {}", e))?;
+    unsafe { FillConsoleOutputAttribute(conout, info.wAttributes.0, cells, origin, &mut written) }
+        .map_err(|e| anyhow!("Failed to reset console colors: {}", e))?;
+    unsafe { SetConsoleCursorPosition(conout, origin) }
+        .map_err(|e| anyhow!("Failed to move cursor: {}", e))?;
+
+    // Keep the window size, moved to the top of the buffer
+    let w = info.srWindow;
+    let top = SMALL_RECT { Left: 0, Top: 0, Right: w.Right - w.Left, Bottom: w.Bottom - w.Top };
+    let _ = unsafe { SetConsoleWindowInfo(conout, true, &top) };
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::Globalization::{
+    MultiByteToWideChar, WideCharToMultiByte, CP_UTF8, MB_ERR_INVALID_CHARS, WC_NO_BEST_FIT_CHARS,
+};
+use windows::Win32::System::Console::{GetConsoleCP, GetConsoleOutputCP};
+use windows::core::PCSTR;
+use super::read::{styled_line, StyledLine};
+
+/// Input and output code pages of the attached console
+pub fn console_code_pages() -> (u32, u32) {
+    unsafe { (GetConsoleCP(), GetConsoleOutputCP()) }
+}
+
+/// How the bytes a program wrote should be read back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDecoding {
+    /// Trust the console's translation
+    AsShown,
+    /// The program wrote bytes in this code page (65001 for UTF-8) while the
+    /// console translated them with its own output code page
+    CodePage(u32),
+}
+
+impl TextDecoding {
+    /// Choices offered in the UI
+    pub const ALL: [TextDecoding; 7] = [
+        TextDecoding::AsShown,
+        TextDecoding::CodePage(CP_UTF8),
+        TextDecoding::CodePage(437),
+        TextDecoding::CodePage(850),
+        TextDecoding::CodePage(1252),
+        TextDecoding::CodePage(936),
+        TextDecoding::CodePage(932),
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            TextDecoding::AsShown => "As shown".to_string(),
+            TextDecoding::CodePage(CP_UTF8) => "UTF-8".to_string(),
+            TextDecoding::CodePage(cp) => format!("CP {}", cp),
+        }
+    }
+}
+
+/// Undo mojibake: turn each line back into the bytes the console decoded
+/// with `shown_cp` and decode those bytes with `actual_cp` instead.
+///
+/// Lines that are plain ASCII, hold characters `shown_cp` cannot encode, or
+/// are not valid in `actual_cp` come back unchanged, so text that was fine
+/// to begin with is left alone. Bytes the console already replaced with
+/// U+FFFD cannot be recovered.
+pub fn redecode(lines: &[StyledLine], shown_cp: u32, actual_cp: u32) -> Vec<StyledLine> {
+    if shown_cp == actual_cp {
+        return lines.to_vec();
+    }
+    lines.iter()
+        .map(|line| redecode_line(line, shown_cp, actual_cp).unwrap_or_else(|| line.clone()))
+        .collect()
+}
+
+fn redecode_line(line: &StyledLine, shown_cp: u32, actual_cp: u32) -> Option<StyledLine> {
+    if line.text.is_ascii() {
+        return None;
+    }
+
+    // Bytes the console saw, each tagged with the attribute of its character
+    let mut bytes: Vec<(u8, u16)> = Vec::with_capacity(line.text.len());
+    for (i, ch) in line.text.char_indices() {
+        let attr = line.runs.iter().find(|r| r.range.contains(&i)).map_or(0x07, |r| r.attr);
+        for b in encode_char(ch, shown_cp)? {
+            bytes.push((b, attr));
+        }
+    }
+
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut attrs = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let (decoded, used) = decode_next(&bytes[i..], actual_cp)?;
+        for unit in decoded {
+            units.push(unit);
+            attrs.push(bytes[i].1);
+        }
+        i += used;
+    }
+    Some(styled_line(&units, &attrs, true))
+}
+
+/// Encode one character in `cp`, failing if the code page has no byte sequence for it
+fn encode_char(ch: char, cp: u32) -> Option<Vec<u8>> {
+    let mut wide = [0u16; 2];
+    let wide = ch.encode_utf16(&mut wide);
+    let mut buf = [0u8; 8];
+    let mut used_default = BOOL(0);
+    // UTF-8 rejects the best-fit flag and the used-default pointer
+    let len = unsafe {
+        if cp == CP_UTF8 {
+            WideCharToMultiByte(cp, 0, wide, Some(&mut buf), PCSTR::null(), None)
+        } else {
+            WideCharToMultiByte(cp, WC_NO_BEST_FIT_CHARS, wide, Some(&mut buf), PCSTR::null(), Some(&mut used_default))
+        }
+    };
+    if len <= 0 || used_default.as_bool() {
+        return None;
+    }
+    Some(buf[..len as usize].to_vec())
+}
+
+/// Decode the shortest valid character at the start of `bytes`, returning
+/// its UTF-16 units and how many bytes it took
+fn decode_next(bytes: &[(u8, u16)], cp: u32) -> Option<(Vec<u16>, usize)> {
+    if bytes[0].0 < 0x80 {
+        return Some((vec![bytes[0].0 as u16], 1));
+    }
+    let mut raw = [0u8; 4];
+    for n in 1..=bytes.len().min(4) {
+        raw[n - 1] = bytes[n - 1].0;
+        let mut wide = [0u16; 2];
+        let len = unsafe { MultiByteToWideChar(cp, MB_ERR_INVALID_CHARS, &raw[..n], Some(&mut wide)) };
+        if len > 0 {
+            return Some((wide[..len as usize].to_vec(), n));
+        }
+    }
+    None
+}
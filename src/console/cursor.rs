@@ -0,0 +1,22 @@
+use windows::Win32::System::Console::{GetConsoleCursorInfo, CONSOLE_CURSOR_INFO};
+use anyhow::{Result, anyhow};
+use super::read::open_conout;
+
+/// Visibility and height of the attached console's cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorShape {
+    /// Shells show the cursor while prompting; TUIs and some long-running
+    /// commands hide it
+    pub visible: bool,
+    /// Percentage of the cell the cursor fills, 1 to 100
+    pub size: u32,
+}
+
+/// Read the cursor's visibility and size from the active screen buffer
+pub fn cursor_shape() -> Result<CursorShape> {
+    let conout = open_conout()?;
+    let mut info = CONSOLE_CURSOR_INFO::default();
+    unsafe { GetConsoleCursorInfo(conout, &mut info) }
+        .map_err(|e| anyhow!("Failed to get cursor info: {}", e))?;
+    Ok(CursorShape { visible: info.bVisible.as_bool(), size: info.dwSize })
+}
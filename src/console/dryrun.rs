@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+use windows::Win32::System::Console::{INPUT_RECORD, KEY_EVENT, MOUSE_EVENT};
+use super::write::{input_settings, update_input_settings};
+
+/// Descriptions of the input that would have been written, by any thread
+/// writing with dry run on, until taken
+static CAPTURED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// dwControlKeyState bits and their names, in the order they are printed
+const KEY_STATES: [(u32, &str); 9] = [
+    (0x0008, "LEFT_CTRL"),
+    (0x0004, "RIGHT_CTRL"),
+    (0x0002, "LEFT_ALT"),
+    (0x0001, "RIGHT_ALT"),
+    (0x0010, "SHIFT"),
+    (0x0100, "ENHANCED"),
+    (0x0020, "NUMLOCK"),
+    (0x0040, "SCROLLLOCK"),
+    (0x0080, "CAPSLOCK"),
+];
+
+/// Turn dry run on or off for input written on this thread. While on,
+/// nothing reaches CONIN$; each record is described and kept for
+/// [`take_dry_run`] instead.
+pub fn set_dry_run(on: bool) {
+    update_input_settings(|s| s.dry_run = on);
+}
+
+pub fn is_dry_run() -> bool {
+    input_settings().dry_run
+}
+
+/// Descriptions captured since the last call
+pub fn take_dry_run() -> Vec<String> {
+    CAPTURED.lock().map(|mut c| std::mem::take(&mut *c)).unwrap_or_default()
+}
+
+/// Describe `records` instead of writing them; false when dry run is off
+pub(super) fn capture(records: &[INPUT_RECORD]) -> bool {
+    capture_with(|| records.iter().map(describe).collect())
+}
+
+/// Note an action that is not an input record, such as a raised Ctrl+C
+pub(super) fn capture_note(note: &str) -> bool {
+    capture_with(|| vec![note.to_string()])
+}
+
+fn capture_with(lines: impl FnOnce() -> Vec<String>) -> bool {
+    if !is_dry_run() {
+        return false;
+    }
+    if let Ok(mut captured) = CAPTURED.lock() {
+        captured.extend(lines());
+    }
+    true
+}
+
+/// One line per record, e.g. `KEY down vk=0x41 scan=0x1E char='a' U+0061 state=SHIFT`
+fn describe(record: &INPUT_RECORD) -> String {
+    match record.EventType as u32 {
+        KEY_EVENT => {
+            let key = unsafe { record.Event.KeyEvent };
+            let unit = unsafe { key.uChar.UnicodeChar };
+            let ch = match char::from_u32(unit as u32) {
+                Some(c) if !c.is_control() => format!("'{}'", c),
+                _ => "-".to_string(),
+            };
+            format!(
+                "KEY {} vk=0x{:02X} scan=0x{:02X} char={} U+{:04X} repeat={} state={}",
+                if key.bKeyDown.as_bool() { "down" } else { "up  " },
+                key.wVirtualKeyCode, key.wVirtualScanCode, ch, unit, key.wRepeatCount,
+                key_state(key.dwControlKeyState),
+            )
+        }
+        MOUSE_EVENT => {
+            let mouse = unsafe { record.Event.MouseEvent };
+            format!(
+                "MOUSE at {},{} buttons=0x{:X} flags=0x{:X} state={}",
+                mouse.dwMousePosition.X, mouse.dwMousePosition.Y, mouse.dwButtonState,
+                mouse.dwEventFlags, key_state(mouse.dwControlKeyState),
+            )
+        }
+        other => format!("EVENT type={}", other),
+    }
+}
+
+fn key_state(state: u32) -> String {
+    let names: Vec<&str> = KEY_STATES.iter()
+        .filter(|(bit, _)| state & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if names.is_empty() { "0".to_string() } else { names.join("|") }
+}
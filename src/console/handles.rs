@@ -0,0 +1,100 @@
+use std::sync::Mutex;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_SHARE_READ,
+    FILE_SHARE_WRITE, OPEN_EXISTING,
+};
+use windows::core::PCWSTR;
+use anyhow::{Result, anyhow};
+
+/// A console device handle that is closed when dropped
+#[derive(Debug)]
+pub struct ConsoleHandle(HANDLE);
+
+// A console handle is a plain kernel handle; any thread may use or close it
+unsafe impl Send for ConsoleHandle {}
+
+impl ConsoleHandle {
+    /// Open `CONIN$` or `CONOUT$` of the attached console for reading and writing
+    pub fn open(device: &str) -> Result<Self> {
+        let name: Vec<u16> = device.encode_utf16().chain(Some(0)).collect();
+        let handle = unsafe {
+            CreateFileW(
+                PCWSTR::from_raw(name.as_ptr()),
+                FILE_GENERIC_READ.0 | FILE_GENERIC_WRITE.0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                FILE_ATTRIBUTE_NORMAL,
+                None,
+            )
+        }
+        .map_err(|e| anyhow!("Failed to open {}: {}", device, e))?;
+        if handle.is_invalid() {
+            return Err(anyhow!("Failed to open {}", device));
+        }
+        Ok(Self(handle))
+    }
+
+    pub fn raw(&self) -> HANDLE {
+        self.0
+    }
+}
+
+impl Drop for ConsoleHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Device handles of the current attachment, opened on first use
+struct HandleCache {
+    conin: Option<ConsoleHandle>,
+    conout: Option<ConsoleHandle>,
+}
+
+static CACHE: Mutex<HandleCache> = Mutex::new(HandleCache { conin: None, conout: None });
+
+/// `CONIN$` of the attached console, opened once per attachment.
+///
+/// The handle stays owned by the cache and is valid until the next attach or
+/// detach; callers must not close it.
+pub fn conin() -> Result<HANDLE> {
+    let mut cache = CACHE.lock().map_err(|_| anyhow!("Console handle cache poisoned"))?;
+    if cache.conin.is_none() {
+        cache.conin = Some(ConsoleHandle::open("CONIN$")?);
+    }
+    Ok(cache.conin.as_ref().unwrap().raw())
+}
+
+/// `CONOUT$` of the attached console, opened once per attachment.
+///
+/// It refers to the screen buffer that was active when it was opened. Like
+/// [`conin`], it is valid until the next attach or detach.
+pub fn conout() -> Result<HANDLE> {
+    let mut cache = CACHE.lock().map_err(|_| anyhow!("Console handle cache poisoned"))?;
+    if cache.conout.is_none() {
+        cache.conout = Some(ConsoleHandle::open("CONOUT$")?);
+    }
+    Ok(cache.conout.as_ref().unwrap().raw())
+}
+
+/// Drop the cached `CONOUT$` so the next use opens whichever screen buffer
+/// is active now; for attachments kept across polls, where a full-screen
+/// app may have switched buffers since the handle was opened
+#[cfg(feature = "gui")]
+pub fn refresh_conout() {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.conout = None;
+    }
+}
+
+/// Close the cached handles; called whenever the attachment changes
+pub fn close_cached() {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.conin = None;
+        cache.conout = None;
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Attachment lifecycle event delivered to registered hooks.
+///
+/// These describe sessions, not the short attach/detach cycles used for each
+/// poll, so observers see one `Attached` per target and one `Detached` when
+/// the session ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachEvent {
+    /// A session attached to the target PID
+    Attached { pid: u32 },
+    /// The session for the target PID ended (user detach or lost target)
+    Detached { pid: u32 },
+    /// An attach, read or write operation failed
+    Error { pid: Option<u32>, message: String },
+}
+
+/// Handle returned by registration, used to remove a hook again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+type Hook = Arc<dyn Fn(&AttachEvent) + Send + Sync>;
+
+static HOOKS: Mutex<Vec<(HookId, Hook)>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Register a hook that sees every lifecycle event
+pub fn subscribe(hook: impl Fn(&AttachEvent) + Send + Sync + 'static) -> HookId {
+    let id = HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    if let Ok(mut hooks) = HOOKS.lock() {
+        hooks.push((id, Arc::new(hook)));
+    }
+    id
+}
+
+/// Register a hook called with the PID whenever a session attaches
+pub fn on_attach(hook: impl Fn(u32) + Send + Sync + 'static) -> HookId {
+    subscribe(move |event| {
+        if let AttachEvent::Attached { pid } = event {
+            hook(*pid);
+        }
+    })
+}
+
+/// Register a hook called with the PID whenever a session detaches
+pub fn on_detach(hook: impl Fn(u32) + Send + Sync + 'static) -> HookId {
+    subscribe(move |event| {
+        if let AttachEvent::Detached { pid } = event {
+            hook(*pid);
+        }
+    })
+}
+
+/// Register a hook called with the PID (if known) and message on errors
+// Re-exported for code built on the crate; nothing in the app listens for errors
+#[allow(dead_code)]
+pub fn on_error(hook: impl Fn(Option<u32>, &str) + Send + Sync + 'static) -> HookId {
+    subscribe(move |event| {
+        if let AttachEvent::Error { pid, message } = event {
+            hook(*pid, message);
+        }
+    })
+}
+
+/// Remove a previously registered hook
+pub fn unsubscribe(id: HookId) {
+    if let Ok(mut hooks) = HOOKS.lock() {
+        hooks.retain(|(hook_id, _)| *hook_id != id);
+    }
+}
+
+/// Deliver an event to all registered hooks. They run without the registry
+/// locked, so a hook may subscribe or unsubscribe.
+pub fn notify(event: AttachEvent) {
+    let hooks: Vec<Hook> = match HOOKS.lock() {
+        Ok(hooks) => hooks.iter().map(|(_, hook)| hook.clone()).collect(),
+        Err(_) => return,
+    };
+    for hook in hooks {
+        hook(&event);
+    }
+}
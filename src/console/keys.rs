@@ -0,0 +1,103 @@
+/// Keys with no character of their own that interactive programs listen for
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VirtualKey {
+    Escape,
+    Tab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// F1 to F12
+    F(u8),
+}
+
+#[cfg(feature = "gui")]
+impl VirtualKey {
+    /// Keys offered in the UI palette, in display order
+    pub const PALETTE: [VirtualKey; 24] = [
+        VirtualKey::Escape,
+        VirtualKey::Tab,
+        VirtualKey::Up,
+        VirtualKey::Down,
+        VirtualKey::Left,
+        VirtualKey::Right,
+        VirtualKey::Home,
+        VirtualKey::End,
+        VirtualKey::PageUp,
+        VirtualKey::PageDown,
+        VirtualKey::Insert,
+        VirtualKey::Delete,
+        VirtualKey::F(1),
+        VirtualKey::F(2),
+        VirtualKey::F(3),
+        VirtualKey::F(4),
+        VirtualKey::F(5),
+        VirtualKey::F(6),
+        VirtualKey::F(7),
+        VirtualKey::F(8),
+        VirtualKey::F(9),
+        VirtualKey::F(10),
+        VirtualKey::F(11),
+        VirtualKey::F(12),
+    ];
+
+    /// Windows virtual key code
+    pub fn code(&self) -> u16 {
+        match self {
+            VirtualKey::Escape => 0x1B,
+            VirtualKey::Tab => 0x09,
+            VirtualKey::PageUp => 0x21,
+            VirtualKey::PageDown => 0x22,
+            VirtualKey::End => 0x23,
+            VirtualKey::Home => 0x24,
+            VirtualKey::Left => 0x25,
+            VirtualKey::Up => 0x26,
+            VirtualKey::Right => 0x27,
+            VirtualKey::Down => 0x28,
+            VirtualKey::Insert => 0x2D,
+            VirtualKey::Delete => 0x2E,
+            // VK_F1 is 0x70 and the rest follow in order
+            VirtualKey::F(n) => 0x70 + (*n).clamp(1, 12) as u16 - 1,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            VirtualKey::Escape => "Esc".to_string(),
+            VirtualKey::Tab => "Tab".to_string(),
+            VirtualKey::Up => "↑".to_string(),
+            VirtualKey::Down => "↓".to_string(),
+            VirtualKey::Left => "←".to_string(),
+            VirtualKey::Right => "→".to_string(),
+            VirtualKey::Home => "Home".to_string(),
+            VirtualKey::End => "End".to_string(),
+            VirtualKey::PageUp => "PgUp".to_string(),
+            VirtualKey::PageDown => "PgDn".to_string(),
+            VirtualKey::Insert => "Ins".to_string(),
+            VirtualKey::Delete => "Del".to_string(),
+            VirtualKey::F(n) => format!("F{}", n),
+        }
+    }
+}
+
+/// Whether a virtual key sits in the navigation block or arrow keys, which
+/// the console reports with ENHANCED_KEY so programs can tell them from
+/// the same keys on the numeric keypad
+pub fn is_enhanced(vk: u16) -> bool {
+    matches!(vk, 0x21..=0x28 | 0x2D | 0x2E)
+}
+
+/// Character a real keypress of this virtual key carries, 0 for none
+pub fn key_char(vk: u16) -> u16 {
+    match vk {
+        0x08 | 0x09 | 0x0D | 0x1B => vk,
+        _ => 0,
+    }
+}
@@ -0,0 +1,22 @@
+use windows::Win32::System::Console::GetConsoleWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyboardLayout, HKL};
+use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+/// Keyboard layout of the attached console's window, so keys are picked the
+/// way the target would read them on an AZERTY or German layout rather than
+/// ours. Falls back to our own thread's layout when the console has no
+/// window to ask.
+pub fn target_layout() -> HKL {
+    let hwnd = unsafe { GetConsoleWindow() };
+    if !hwnd.is_invalid() {
+        let thread = unsafe { GetWindowThreadProcessId(hwnd, None) };
+        if thread != 0 {
+            let layout = unsafe { GetKeyboardLayout(thread) };
+            if !layout.is_invalid() {
+                return layout;
+            }
+        }
+    }
+    unsafe { GetKeyboardLayout(0) }
+}
+
@@ -0,0 +1,47 @@
+use windows::Win32::System::Console::{
+    GetCurrentConsoleFontEx, GetLargestConsoleWindowSize, CONSOLE_FONT_INFOEX,
+};
+use anyhow::{Result, anyhow};
+use super::read::{buffer_info, open_conout};
+
+/// Font and geometry of the attached console's window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleMetrics {
+    /// Face name of the current console font, e.g. "Consolas"
+    pub font_face: String,
+    /// Size of one character cell in pixels (width, height)
+    pub font_size: (u16, u16),
+    pub font_weight: u32,
+    /// Screen buffer size in cells (columns, rows)
+    pub buffer: (usize, usize),
+    /// Visible window size in cells (columns, rows)
+    pub window: (usize, usize),
+    /// Largest window that fits the screen with this font, in cells
+    pub max_window: (usize, usize),
+}
+
+/// Read the current font and the buffer and window sizes of the attached console
+pub fn console_metrics() -> Result<ConsoleMetrics> {
+    let conout = open_conout()?;
+
+    let mut font = CONSOLE_FONT_INFOEX {
+        cbSize: std::mem::size_of::<CONSOLE_FONT_INFOEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetCurrentConsoleFontEx(conout, false, &mut font) }
+        .map_err(|e| anyhow!("Failed to get console font: {}", e))?;
+
+    let csbi = buffer_info(conout)?;
+    let largest = unsafe { GetLargestConsoleWindowSize(conout) };
+
+    let face_len = font.FaceName.iter().position(|&c| c == 0).unwrap_or(font.FaceName.len());
+    let w = csbi.srWindow;
+    Ok(ConsoleMetrics {
+        font_face: String::from_utf16_lossy(&font.FaceName[..face_len]),
+        font_size: (font.dwFontSize.X.max(0) as u16, font.dwFontSize.Y.max(0) as u16),
+        font_weight: font.FontWeight,
+        buffer: (csbi.dwSize.X.max(0) as usize, csbi.dwSize.Y.max(0) as usize),
+        window: ((w.Right - w.Left + 1).max(0) as usize, (w.Bottom - w.Top + 1).max(0) as usize),
+        max_window: (largest.X.max(0) as usize, largest.Y.max(0) as usize),
+    })
+}
@@ -1,7 +1,96 @@
 pub mod attach;
+#[cfg(feature = "gui")]
+pub mod backend;
+#[cfg(feature = "automation")]
+pub mod batch;
+#[cfg(feature = "gui")]
+pub mod changes;
+#[cfg(feature = "gui")]
+pub mod bitmap;
+pub mod clear;
+#[cfg(feature = "gui")]
+pub mod codepage;
+pub mod handles;
+#[cfg(feature = "gui")]
+pub mod cursor;
+pub mod dryrun;
+#[cfg(feature = "gui")]
+pub mod hooks;
+pub mod keys;
+pub mod layout;
+#[cfg(feature = "gui")]
+pub mod metrics;
+pub mod modes;
+#[cfg(feature = "gui")]
+pub mod queue;
 pub mod read;
+#[cfg(feature = "gui")]
+pub mod selection;
+pub mod sendinput;
+#[cfg(feature = "gui")]
+pub mod severity;
+#[cfg(feature = "gui")]
+pub mod snapshot;
+#[cfg(feature = "gui")]
+pub mod stitch;
+#[cfg(feature = "gui")]
+pub mod text;
+#[cfg(feature = "gui")]
+pub mod vt;
 pub mod write;
 
-pub use attach::{attach_to_console, detach_from_console, is_attached, ConsoleAttachment};
-pub use read::{read_console_lines, read_all_console};
-pub use write::{send_command, send_ctrl_c, send_control_char};
+pub use attach::{attach_to_console, detach_from_console, is_attached, ConsoleAttachment, CtrlGuard};
+#[cfg(feature = "gui")]
+pub use attach::{attach_with_fallback, console_process_list, console_title, console_window_id, AttachRoute};
+#[cfg(feature = "gui")]
+pub use backend::{ConsoleBackend, Win32Console};
+#[cfg(feature = "automation")]
+pub use batch::{send_commands, BatchOptions, CommandOutcome};
+#[cfg(feature = "gui")]
+pub use changes::ChangeWatch;
+#[cfg(feature = "gui")]
+pub use bitmap::render_console_bitmap;
+pub use clear::clear_console;
+#[cfg(feature = "gui")]
+pub use codepage::{console_code_pages, redecode, TextDecoding};
+#[cfg(feature = "gui")]
+pub use cursor::{cursor_shape, CursorShape};
+pub use dryrun::{set_dry_run, take_dry_run};
+#[cfg(feature = "gui")]
+pub use hooks::{notify, on_attach, on_detach, unsubscribe, AttachEvent, HookId};
+// Hook API for code built on the crate; the app itself only audits sessions
+#[cfg(feature = "gui")]
+#[allow(unused_imports)]
+pub use hooks::{on_error, subscribe};
+#[cfg(feature = "gui")]
+pub use keys::VirtualKey;
+#[cfg(feature = "gui")]
+pub use metrics::{console_metrics, ConsoleMetrics};
+pub use modes::set_quick_edit;
+#[cfg(feature = "gui")]
+pub use modes::{console_modes, ConsoleModes};
+#[cfg(feature = "gui")]
+pub use queue::{wait_turn, ConsoleTurn};
+pub use read::read_console_lines;
+#[cfg(feature = "gui")]
+pub use read::{set_read_chunk_cells, DEFAULT_CHUNK_CELLS, read_console_region, ChangeTracker, ConsoleStream, LineEvent, LineTransform, ColorRun, ReadTimings, ReadWindow, SmallRect, StyledLine, take_read_timings};
+#[cfg(feature = "gui")]
+pub use selection::{console_selection, ConsoleSelection};
+#[cfg(feature = "gui")]
+pub use sendinput::input_degraded;
+#[cfg(feature = "gui")]
+pub use severity::{classify as classify_severity, Severity};
+#[cfg(feature = "gui")]
+pub use snapshot::snapshot_json;
+#[cfg(feature = "gui")]
+#[allow(unused_imports)]
+pub use snapshot::snapshot_console;
+#[cfg(feature = "gui")]
+pub use stitch::{read_new_lines_since, CursorMarker};
+#[cfg(feature = "gui")]
+pub use text::{LineEnding, TextOptions};
+#[cfg(feature = "gui")]
+pub use vt::interpret as interpret_vt;
+pub use write::{send_alt_code, send_alt_key, send_command, send_ctrl_c, send_ctrl_key, send_ctrl_z, send_control_char, send_keys, send_text, set_enter_sends_lf, set_typing_delay, send_mouse_click, KeyStroke};
+#[cfg(feature = "gui")]
+pub use write::{send_key, with_input_settings, InputSettings, MOUSE_LEFT, MOUSE_MIDDLE, MOUSE_RIGHT};
@@ -0,0 +1,92 @@
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::System::Console::{
+    GetConsoleMode, SetConsoleMode, CONSOLE_MODE, ENABLE_EXTENDED_FLAGS, ENABLE_QUICK_EDIT_MODE,
+};
+#[cfg(feature = "gui")]
+use windows::Win32::System::Console::{
+    ENABLE_ECHO_INPUT, ENABLE_INSERT_MODE, ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT,
+    ENABLE_PROCESSED_INPUT, ENABLE_PROCESSED_OUTPUT, ENABLE_VIRTUAL_TERMINAL_INPUT,
+    ENABLE_VIRTUAL_TERMINAL_PROCESSING, ENABLE_WINDOW_INPUT, ENABLE_WRAP_AT_EOL_OUTPUT,
+};
+use anyhow::{Result, anyhow};
+use super::handles;
+
+/// Input mode flags worth showing, with short names
+#[cfg(feature = "gui")]
+const INPUT_FLAGS: [(CONSOLE_MODE, &str); 8] = [
+    (ENABLE_LINE_INPUT, "line"),
+    (ENABLE_ECHO_INPUT, "echo"),
+    (ENABLE_PROCESSED_INPUT, "processed"),
+    (ENABLE_QUICK_EDIT_MODE, "quick-edit"),
+    (ENABLE_INSERT_MODE, "insert"),
+    (ENABLE_MOUSE_INPUT, "mouse"),
+    (ENABLE_WINDOW_INPUT, "window"),
+    (ENABLE_VIRTUAL_TERMINAL_INPUT, "VT"),
+];
+
+/// Output mode flags worth showing, with short names
+#[cfg(feature = "gui")]
+const OUTPUT_FLAGS: [(CONSOLE_MODE, &str); 3] = [
+    (ENABLE_PROCESSED_OUTPUT, "processed"),
+    (ENABLE_WRAP_AT_EOL_OUTPUT, "wrap"),
+    (ENABLE_VIRTUAL_TERMINAL_PROCESSING, "VT"),
+];
+
+/// GetConsoleMode of the attached console's input and active output buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleModes {
+    pub input: u32,
+    pub output: u32,
+}
+
+#[cfg(feature = "gui")]
+impl ConsoleModes {
+    /// Named input flags and whether each is set
+    pub fn input_flags(&self) -> Vec<(&'static str, bool)> {
+        INPUT_FLAGS.iter().map(|(f, name)| (*name, self.input & f.0 != 0)).collect()
+    }
+
+    /// Whether the program reading input asked for mouse events
+    pub fn mouse_input(&self) -> bool {
+        self.input & ENABLE_MOUSE_INPUT.0 != 0
+    }
+
+    /// Whether clicking the target's window starts a selection, which pauses its output
+    pub fn quick_edit(&self) -> bool {
+        self.input & ENABLE_QUICK_EDIT_MODE.0 != 0
+    }
+
+    /// Named output flags and whether each is set
+    pub fn output_flags(&self) -> Vec<(&'static str, bool)> {
+        OUTPUT_FLAGS.iter().map(|(f, name)| (*name, self.output & f.0 != 0)).collect()
+    }
+}
+
+/// Read the input and output modes of the attached console
+pub fn console_modes() -> Result<ConsoleModes> {
+    let input = mode_of(handles::conin()?, "CONIN$")?;
+    let output = mode_of(handles::conout()?, "CONOUT$")?;
+    Ok(ConsoleModes { input, output })
+}
+
+/// Turn Quick-Edit on the attached console's input on or off, keeping the
+/// other input flags. Conhost only honors the change with ENABLE_EXTENDED_FLAGS.
+pub fn set_quick_edit(enable: bool) -> Result<()> {
+    let conin = handles::conin()?;
+    let mut mode = mode_of(conin, "CONIN$")? | ENABLE_EXTENDED_FLAGS.0;
+    if enable {
+        mode |= ENABLE_QUICK_EDIT_MODE.0;
+    } else {
+        mode &= !ENABLE_QUICK_EDIT_MODE.0;
+    }
+    unsafe { SetConsoleMode(conin, CONSOLE_MODE(mode)) }
+        .map_err(|e| anyhow!("Failed to set CONIN$ mode: {}", e))
+}
+
+/// Query the mode of a console device handle
+fn mode_of(handle: HANDLE, device: &str) -> Result<u32> {
+    let mut mode = CONSOLE_MODE::default();
+    unsafe { GetConsoleMode(handle, &mut mode) }
+        .map_err(|e| anyhow!("Failed to get {} mode: {}", device, e))?;
+    Ok(mode.0)
+}
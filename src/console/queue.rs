@@ -0,0 +1,46 @@
+use std::sync::{Condvar, Mutex};
+
+/// A process can only be attached to one console at a time, so every
+/// attach → operate → detach sequence has to run alone. Callers take a
+/// ticket and are served strictly in arrival order.
+struct Tickets {
+    next: u64,
+    serving: u64,
+}
+
+static TICKETS: Mutex<Tickets> = Mutex::new(Tickets { next: 0, serving: 0 });
+static TURN_CHANGED: Condvar = Condvar::new();
+
+/// Exclusive right to use the console until dropped
+pub struct ConsoleTurn {
+    ticket: u64,
+}
+
+impl ConsoleTurn {
+    /// Place of this turn in the queue; when two turns have consecutive
+    /// tickets, no one else used the console between them
+    pub fn ticket(&self) -> u64 {
+        self.ticket
+    }
+}
+
+/// Queue behind any console operation in progress and wait for our turn.
+///
+/// Not reentrant: holding a turn and asking for another on the same thread deadlocks.
+pub fn wait_turn() -> ConsoleTurn {
+    let mut tickets = TICKETS.lock().unwrap_or_else(|e| e.into_inner());
+    let ticket = tickets.next;
+    tickets.next += 1;
+    while tickets.serving != ticket {
+        tickets = TURN_CHANGED.wait(tickets).unwrap_or_else(|e| e.into_inner());
+    }
+    ConsoleTurn { ticket }
+}
+
+impl Drop for ConsoleTurn {
+    fn drop(&mut self) {
+        let mut tickets = TICKETS.lock().unwrap_or_else(|e| e.into_inner());
+        tickets.serving += 1;
+        TURN_CHANGED.notify_all();
+    }
+}
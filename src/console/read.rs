@@ -1,99 +1,850 @@
+use std::cell::Cell as StdCell;
+#[cfg(feature = "gui")]
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+#[cfg(feature = "gui")]
+use std::hash::Hasher;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use windows::Win32::System::Console::{
-    GetConsoleScreenBufferInfo, ReadConsoleOutputCharacterW,
-    CONSOLE_SCREEN_BUFFER_INFO,
-};
-use windows::Win32::Storage::FileSystem::{
-    CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING, FILE_GENERIC_READ,
-    FILE_ATTRIBUTE_NORMAL,
+    GetConsoleScreenBufferInfo, ReadConsoleOutputW, CHAR_INFO, COMMON_LVB_LEADING_BYTE,
+    COMMON_LVB_TRAILING_BYTE, CONSOLE_SCREEN_BUFFER_INFO, COORD, SMALL_RECT,
 };
 use windows::Win32::Foundation::HANDLE;
-use windows::core::PCWSTR;
 use anyhow::{Result, anyhow};
+use super::handles;
+use crate::repro;
+
+/// Default cells per ReadConsoleOutputW call; a call fails once its buffer
+/// approaches 64 KB (16K cells), and less when the heap is fragmented
+pub const DEFAULT_CHUNK_CELLS: usize = 8192;
+
+/// Cells per ReadConsoleOutputW call, see [`set_read_chunk_cells`]
+static CHUNK_CELLS: AtomicUsize = AtomicUsize::new(DEFAULT_CHUNK_CELLS);
+
+/// Limit how many cells one ReadConsoleOutputW call covers; larger reads
+/// are split into rectangles of at most this many cells
+#[cfg(feature = "gui")]
+pub fn set_read_chunk_cells(cells: usize) {
+    CHUNK_CELLS.store(cells.max(1), Ordering::Relaxed);
+}
+
+/// Current cells-per-call limit
+pub fn read_chunk_cells() -> usize {
+    CHUNK_CELLS.load(Ordering::Relaxed)
+}
+
+/// Time spent in the read layer on the calling thread since the last
+/// [`take_read_timings`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadTimings {
+    /// GetConsoleScreenBufferInfo calls
+    pub buffer_info: Duration,
+    /// ReadConsoleOutputW calls
+    pub read: Duration,
+    /// Turning cells into text and color runs
+    pub convert: Duration,
+    /// Number of ReadConsoleOutputW calls, more than one per read when chunked
+    pub read_calls: usize,
+}
+
+thread_local! {
+    static TIMINGS: StdCell<ReadTimings> = StdCell::new(ReadTimings::default());
+}
+
+/// Return the timings gathered on this thread and start over
+#[cfg(feature = "gui")]
+pub fn take_read_timings() -> ReadTimings {
+    TIMINGS.with(|t| t.take())
+}
+
+/// Add the time since `started` to one of this thread's timings
+fn add_timing(started: Instant, field: impl FnOnce(&mut ReadTimings) -> &mut Duration) {
+    let elapsed = started.elapsed();
+    TIMINGS.with(|t| {
+        let mut timings = t.get();
+        *field(&mut timings) += elapsed;
+        t.set(timings);
+    });
+}
+
+/// A run of text sharing one console attribute word
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColorRun {
+    /// Byte range within the line's text
+    pub range: Range<usize>,
+    /// Win32 character attributes (low nibble foreground, high nibble background)
+    pub attr: u16,
+}
+
+/// One console line with its color runs
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StyledLine {
+    pub text: String,
+    pub runs: Vec<ColorRun>,
+}
+
+/// Which rows of the screen buffer to read
+// The helper-only build reads nothing but `ToCursor` windows
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadWindow {
+    /// The last `n` rows ending at the cursor row
+    ToCursor(usize),
+    /// The whole buffer up to the last row holding text, wherever the cursor is
+    All,
+    /// Exactly the rectangle visible in the target's window (`srWindow`)
+    Viewport,
+}
+
+/// One screen buffer cell
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    /// Character in the cell; `'\0'` for the right half of a wide character
+    pub ch: char,
+    /// Win32 character attributes without the leading/trailing byte flags
+    pub attr: u16,
+}
+
+/// Row-major rectangle of cells read from the screen buffer
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone)]
+pub struct Grid<T> {
+    pub width: usize,
+    pub cells: Vec<T>,
+    /// Cursor position in buffer coordinates
+    pub cursor_row: usize,
+    pub cursor_col: usize,
+}
+
+#[cfg(feature = "gui")]
+impl<T> Grid<T> {
+    /// Number of rows
+    pub fn height(&self) -> usize {
+        self.cells.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    /// Iterate over the rows
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+}
+
+/// Read a window of the screen buffer as a grid of cells, untrimmed, one
+/// entry per cell so columns line up exactly as on the target's screen
+#[cfg(feature = "gui")]
+pub fn read_console_cells(window: ReadWindow) -> Result<Grid<Cell>> {
+    let result = read_cells_from_conout(window);
+    repro::record_call("read_console_cells", &result);
+    result
+}
+
+#[cfg(feature = "gui")]
+fn read_cells_from_conout(window: ReadWindow) -> Result<Grid<Cell>> {
+    let conout = open_conout()?;
+    let (csbi, block) = consistent_read(conout, |csbi| {
+        let (start, end) = window_rows(window, csbi);
+        read_block(conout, start, end, window_cols(window, csbi))
+    })?;
+    let cols = window_cols(window, &csbi);
+
+    let started = Instant::now();
+    let cells = block.chunks(cols.len().max(1)).flat_map(row_cells).collect();
+    add_timing(started, |t| &mut t.convert);
+    Ok(Grid {
+        width: cols.len(),
+        cells,
+        cursor_row: csbi.dwCursorPosition.Y.max(0) as usize,
+        cursor_col: csbi.dwCursorPosition.X.max(0) as usize,
+    })
+}
+
+/// Inclusive rectangle of buffer cells, laid out like Win32's SMALL_RECT
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmallRect {
+    pub left: i16,
+    pub top: i16,
+    pub right: i16,
+    pub bottom: i16,
+}
+
+#[cfg(feature = "gui")]
+impl SmallRect {
+    /// Columns `left..=right` of a single buffer row
+    pub fn row(row: i16, left: i16, right: i16) -> Self {
+        Self { left, top: row, right, bottom: row }
+    }
+}
+
+/// Read just the cells inside `rect`, e.g. a status line or progress bar
+/// row, one line per row with trailing spaces kept so columns stay put.
+/// The rectangle is clipped to the buffer; nothing is read when it falls
+/// entirely outside.
+#[cfg(feature = "gui")]
+pub fn read_console_region(rect: SmallRect) -> Result<Vec<StyledLine>> {
+    let result = read_region_from_conout(rect);
+    if repro::is_recording() {
+        let texts = result.as_ref()
+            .map(|lines| lines.iter().map(|l| l.text.clone()).collect())
+            .map_err(|e| anyhow!("{}", e));
+        repro::record_read("read_console_region", &texts);
+    }
+    result
+}
+
+#[cfg(feature = "gui")]
+fn read_region_from_conout(rect: SmallRect) -> Result<Vec<StyledLine>> {
+    let conout = open_conout()?;
+    let csbi = buffer_info(conout)?;
+    let clip = |lo: i16, hi: i16, size: i16| -> Range<usize> {
+        let size = size.max(0) as i32;
+        let start = (lo as i32).clamp(0, size) as usize;
+        let end = (hi as i32 + 1).clamp(0, size) as usize;
+        start..end.max(start)
+    };
+    let rows = clip(rect.top, rect.bottom, csbi.dwSize.Y);
+    let cols = clip(rect.left, rect.right, csbi.dwSize.X);
+    read_rows(conout, rows.start, rows.end, cols, true)
+}
 
 /// Read the last N lines from the console screen buffer
 pub fn read_console_lines(num_lines: usize) -> Result<Vec<String>> {
-    // Open CONOUT$ for reading
-    let conout = unsafe {
-        CreateFileW(
-            PCWSTR::from_raw(conout_wide().as_ptr()),
-            FILE_GENERIC_READ.0,
-            FILE_SHARE_READ | FILE_SHARE_WRITE,
-            None,
-            OPEN_EXISTING,
-            FILE_ATTRIBUTE_NORMAL,
-            None,
-        )
-    }?;
+    let result = read_window_from_conout(ReadWindow::ToCursor(num_lines))
+        .map(|lines| lines.into_iter().map(|l| l.text).collect());
+    repro::record_read("read_console_lines", &result);
+    result
+}
+
+/// Read a window of rows from CONOUT$ (uninstrumented)
+fn read_window_from_conout(window: ReadWindow) -> Result<Vec<StyledLine>> {
+    let conout = open_conout()?;
+    let (csbi, mut lines) = consistent_read(conout, |csbi| {
+        let (start, end) = window_rows(window, csbi);
+        read_rows(conout, start, end, window_cols(window, csbi), false)
+    })?;
 
-    if conout.is_invalid() {
-        return Err(anyhow!("Failed to open CONOUT$"));
+    // Blank rows past the cursor are just unused buffer
+    if window == ReadWindow::All {
+        let cursor_row = csbi.dwCursorPosition.Y.max(0) as usize;
+        let (start, _) = window_rows(window, &csbi);
+        lines.truncate(used_rows(&lines, cursor_row - start));
     }
+    Ok(lines)
+}
 
-    // Get console screen buffer info
-    let mut csbi = CONSOLE_SCREEN_BUFFER_INFO::default();
-    unsafe {
-        GetConsoleScreenBufferInfo(conout, &mut csbi)
-            .map_err(|e| anyhow!("Failed to get console buffer info: {}", e.to_string()))?;
+/// Polls after which every row of the window is re-read regardless of the cursor
+#[cfg(feature = "gui")]
+const RESYNC_EVERY: usize = 20;
+
+/// Reads made before giving up on the target holding still
+const CONSISTENT_READ_ATTEMPTS: usize = 3;
+
+/// Run `read` against fresh buffer info, and again with newer info when the
+/// cursor or window moved before it finished, so rows and positions come from
+/// the same moment. After the last attempt the torn read is returned anyway.
+fn consistent_read<T>(conout: HANDLE, mut read: impl FnMut(&CONSOLE_SCREEN_BUFFER_INFO) -> Result<T>) -> Result<(CONSOLE_SCREEN_BUFFER_INFO, T)> {
+    let mut csbi = buffer_info(conout)?;
+    let mut attempts = 1;
+    loop {
+        let value = read(&csbi)?;
+        let after = buffer_info(conout)?;
+        if !buffer_moved(&csbi, &after) || attempts >= CONSISTENT_READ_ATTEMPTS {
+            return Ok((csbi, value));
+        }
+        csbi = after;
+        attempts += 1;
     }
+}
+
+/// Whether output went by between two buffer queries
+fn buffer_moved(before: &CONSOLE_SCREEN_BUFFER_INFO, after: &CONSOLE_SCREEN_BUFFER_INFO) -> bool {
+    before.dwCursorPosition.X != after.dwCursorPosition.X
+        || before.dwCursorPosition.Y != after.dwCursorPosition.Y
+        || before.dwSize.X != after.dwSize.X
+        || before.dwSize.Y != after.dwSize.Y
+        || before.srWindow.Top != after.srWindow.Top
+        || before.srWindow.Left != after.srWindow.Left
+}
 
-    // Get the cursor position (current line)
-    let cursor_y = csbi.dwCursorPosition.Y;
-    let buffer_width = csbi.dwSize.X as usize;
-    let buffer_height = csbi.dwSize.Y as usize;
+/// Rows an incremental poll had to read, before they are merged into the mirror
+#[cfg(feature = "gui")]
+struct DirtyRows {
+    /// The window's columns or the buffer height changed, so the mirror is stale
+    reshaped: bool,
+    resync: bool,
+    read_from: usize,
+    fresh: Vec<StyledLine>,
+}
 
-    // Calculate the starting line
-    let start_y = if cursor_y >= num_lines as i16 {
-        cursor_y - num_lines as i16
-    } else {
-        0
-    };
+/// Changes found by one [`IncrementalReader::poll`]
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone)]
+pub struct LineDiff {
+    /// Buffer rows of the polled window
+    pub rows: Range<usize>,
+    pub cursor_row: usize,
+    pub cursor_col: usize,
+    /// Rows whose content changed since the previous poll, by buffer row
+    pub changed: Vec<(usize, StyledLine)>,
+    /// Whether the window covers different rows than last poll
+    pub moved: bool,
+}
+
+/// Reads only the rows that can have changed since the last poll.
+///
+/// Rows from the older of the previous and current cursor rows downward are
+/// re-read each poll; rows above are served from a mirror of the buffer. A
+/// changed first row (the buffer scrolled), a resize, or every
+/// [`RESYNC_EVERY`]th poll triggers a full re-read so edits above the cursor
+/// (cls, full-screen apps) are still picked up.
+#[cfg(feature = "gui")]
+pub struct IncrementalReader {
+    mirror: Vec<Option<(u64, StyledLine)>>,
+    cols: Range<usize>,
+    cursor_row: Option<usize>,
+    polls_since_resync: usize,
+    window_rows: Range<usize>,
+    keep_trailing: bool,
+}
+
+#[cfg(feature = "gui")]
+impl Default for IncrementalReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "gui")]
+impl IncrementalReader {
+    pub fn new() -> Self {
+        Self {
+            mirror: Vec::new(),
+            cols: 0..0,
+            cursor_row: None,
+            polls_since_resync: 0,
+            window_rows: 0..0,
+            keep_trailing: false,
+        }
+    }
+
+    /// Forget the mirror, e.g. after attaching to a different console
+    pub fn reset(&mut self) {
+        *self = Self { keep_trailing: self.keep_trailing, ..Self::new() };
+    }
+
+    /// Keep trailing blank cells as spaces instead of trimming them, so
+    /// column-aligned output lines up; changing it re-reads everything
+    pub fn set_keep_trailing(&mut self, keep: bool) {
+        if keep != self.keep_trailing {
+            self.keep_trailing = keep;
+            self.reset();
+        }
+    }
+
+    /// Read the window, returning only the rows that changed
+    pub fn poll(&mut self, window: ReadWindow) -> Result<LineDiff> {
+        let result = self.poll_conout(window);
+        // The whole window is recorded, so a replay knows where each line sits
+        if repro::is_recording() {
+            let texts = result.as_ref()
+                .map(|_| self.lines().into_iter().map(|l| l.text).collect())
+                .map_err(|e| anyhow!("{}", e));
+            repro::record_read("read_console_lines", &texts);
+        }
+        result
+    }
+
+    /// Lines of the last polled window, from the mirror
+    pub fn lines(&self) -> Vec<StyledLine> {
+        self.lines_in(self.window_rows.clone())
+    }
+
+    fn poll_conout(&mut self, window: ReadWindow) -> Result<LineDiff> {
+        let conout = open_conout()?;
+        let (csbi, dirty) = consistent_read(conout, |csbi| self.read_dirty(conout, window, csbi))?;
+
+        let cursor_row = csbi.dwCursorPosition.Y.max(0) as usize;
+        let height = csbi.dwSize.Y as usize;
+        let (start, end) = window_rows(window, &csbi);
+        let cols = window_cols(window, &csbi);
+
+        // Mirrored rows only hold the columns they were read with
+        if dirty.reshaped {
+            self.mirror = vec![None; height];
+            self.cols = cols.clone();
+        }
+        let resync = dirty.resync;
+
+        let mut changed = Vec::new();
+        for (y, line) in (dirty.read_from..end).zip(dirty.fresh) {
+            let hash = line_hash(&line);
+            if self.mirror[y].as_ref().is_none_or(|(h, _)| *h != hash) {
+                changed.push((y, line.clone()));
+                self.mirror[y] = Some((hash, line));
+            }
+        }
+
+        let mut end = end;
+        if window == ReadWindow::All {
+            let lines = self.lines_in(start..end);
+            end = start + used_rows(&lines, cursor_row - start);
+            changed.retain(|(y, _)| *y < end);
+        }
+
+        self.cursor_row = Some(cursor_row);
+        self.polls_since_resync = if resync { 0 } else { self.polls_since_resync + 1 };
+        let moved = self.window_rows != (start..end);
+        self.window_rows = start..end;
 
-    let lines_to_read = (cursor_y - start_y + 1) as usize;
-    let mut lines = Vec::with_capacity(lines_to_read);
+        let cursor_col = (csbi.dwCursorPosition.X.max(0) as usize).saturating_sub(cols.start);
+        Ok(LineDiff { rows: start..end, cursor_row, cursor_col, changed, moved })
+    }
+
+    /// Read the rows that cannot be served from the mirror, without touching it
+    fn read_dirty(&self, conout: HANDLE, window: ReadWindow, csbi: &CONSOLE_SCREEN_BUFFER_INFO) -> Result<DirtyRows> {
+        let cursor_row = csbi.dwCursorPosition.Y.max(0) as usize;
+        let height = csbi.dwSize.Y as usize;
+        let (start, end) = window_rows(window, csbi);
+        let cols = window_cols(window, csbi);
+
+        let reshaped = cols != self.cols || height != self.mirror.len();
+        let mut resync = reshaped || self.polls_since_resync >= RESYNC_EVERY;
 
-    // Read each line
-    for y in start_y..=cursor_y {
-        let line = read_line(conout, y as i16, buffer_width)?;
-        lines.push(line);
+        // A different first row means the whole buffer scrolled under us
+        if !resync && start < end {
+            let first = read_rows(conout, start, start + 1, cols.clone(), self.keep_trailing)?;
+            if self.mirror[start].as_ref().is_some_and(|(h, _)| first.first().is_none_or(|l| *h != line_hash(l))) {
+                resync = true;
+            }
+        }
+
+        let dirty_from = match self.cursor_row {
+            Some(prev) if !resync => prev.min(cursor_row),
+            _ => 0,
+        };
+
+        // Everything from the first row we cannot serve from the mirror is read in one block
+        let read_from = (start..end)
+            .find(|&y| y >= dirty_from || self.mirror[y].is_none())
+            .unwrap_or(end);
+        let fresh = read_rows(conout, read_from, end, cols, self.keep_trailing)?;
+        Ok(DirtyRows { reshaped, resync, read_from, fresh })
     }
 
+    fn lines_in(&self, rows: Range<usize>) -> Vec<StyledLine> {
+        rows.map(|y| self.mirror[y].as_ref().map(|(_, l)| l.clone()).unwrap_or_else(StyledLine::empty))
+            .collect()
+    }
+}
+
+/// What happened to a line of the read window between two [`ConsoleStream::poll`]s.
+/// Indices count from the first line of the window.
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineEvent {
+    /// A line appeared past the previous end of the window
+    Added { index: usize, line: StyledLine },
+    /// A line that was already there now reads differently
+    Changed { index: usize, line: StyledLine },
+    /// The window got shorter and lines from `from` on are gone (cls, resize)
+    Cleared { from: usize },
+}
+
+/// Rewrites the lines of a poll before they are compared, e.g. VT decoding
+#[cfg(feature = "gui")]
+pub type LineTransform = Box<dyn Fn(Vec<StyledLine>) -> Vec<StyledLine> + Send>;
+
+/// Turns successive reads of a window into line events, so every consumer
+/// shares one diffing implementation.
+///
+/// Reads go through an [`IncrementalReader`]; the window's lines are then
+/// passed through the optional transform and compared by hash with the
+/// lines of the previous poll.
+#[cfg(feature = "gui")]
+pub struct ConsoleStream {
+    reader: IncrementalReader,
+    transform: Option<LineTransform>,
+    /// Lines handed out by the last poll and their hashes; None until the first poll
+    lines: Option<(Vec<StyledLine>, Vec<u64>)>,
+    cursor: Option<(usize, usize)>,
+}
+
+#[cfg(feature = "gui")]
+impl Default for ConsoleStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ConsoleStream {
+    pub fn new() -> Self {
+        Self { reader: IncrementalReader::new(), transform: None, lines: None, cursor: None }
+    }
+
+    /// Forget everything, e.g. after attaching to a different console
+    pub fn reset(&mut self) {
+        self.reader.reset();
+        self.lines = None;
+        self.cursor = None;
+    }
+
+    /// See [`IncrementalReader::set_keep_trailing`]; the next poll reports every line
+    pub fn set_keep_trailing(&mut self, keep: bool) {
+        self.reader.set_keep_trailing(keep);
+        self.lines = None;
+    }
+
+    /// Replace the transform applied to each poll; the next poll reports every line
+    pub fn set_transform(&mut self, transform: Option<LineTransform>) {
+        self.transform = transform;
+        self.lines = None;
+    }
+
+    /// Lines of the window as of the last poll, after the transform
+    pub fn lines(&self) -> &[StyledLine] {
+        self.lines.as_ref().map_or(&[], |(lines, _)| lines.as_slice())
+    }
+
+    /// Cursor as (line index within the window, column), None when outside it
+    pub fn cursor(&self) -> Option<(usize, usize)> {
+        self.cursor
+    }
+
+    /// Read the window and report what changed since the previous poll;
+    /// the first poll after a reset reports every line as added
+    pub fn poll(&mut self, window: ReadWindow) -> Result<Vec<LineEvent>> {
+        let diff = self.reader.poll(window)?;
+        self.cursor = diff.rows.contains(&diff.cursor_row)
+            .then(|| (diff.cursor_row - diff.rows.start, diff.cursor_col));
+        if diff.changed.is_empty() && !diff.moved && self.lines.is_some() {
+            return Ok(Vec::new());
+        }
+        Ok(self.compare(self.reader.lines()))
+    }
+
+    /// Take `lines` as the window's content instead of reading the console,
+    /// e.g. lines replayed from a trace; the cursor is then unknown
+    pub fn feed(&mut self, lines: Vec<StyledLine>) -> Vec<LineEvent> {
+        self.cursor = None;
+        self.compare(lines)
+    }
+
+    /// Transform a poll's lines and report how they differ from the last ones
+    fn compare(&mut self, mut lines: Vec<StyledLine>) -> Vec<LineEvent> {
+        if let Some(transform) = &self.transform {
+            lines = transform(lines);
+        }
+        let hashes: Vec<u64> = lines.iter().map(line_hash).collect();
+
+        let mut events = Vec::new();
+        let old: &[u64] = self.lines.as_ref().map_or(&[], |(_, h)| h.as_slice());
+        if hashes.len() < old.len() {
+            events.push(LineEvent::Cleared { from: hashes.len() });
+        }
+        for (index, line) in lines.iter().enumerate() {
+            match old.get(index) {
+                None => events.push(LineEvent::Added { index, line: line.clone() }),
+                Some(h) if *h != hashes[index] => events.push(LineEvent::Changed { index, line: line.clone() }),
+                Some(_) => {}
+            }
+        }
+        self.lines = Some((lines, hashes));
+        events
+    }
+}
+
+#[cfg(feature = "gui")]
+impl StyledLine {
+    fn empty() -> Self {
+        Self { text: String::new(), runs: Vec::new() }
+    }
+}
+
+#[cfg(feature = "gui")]
+fn line_hash<T: Hash + ?Sized>(line: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Remembers a hash per line of the last output handed on, so a new read
+/// can be compared line by line instead of as one joined string
+#[cfg(feature = "gui")]
+#[derive(Debug, Default)]
+pub struct ChangeTracker {
+    hashes: Option<Vec<u64>>,
+}
+
+#[cfg(feature = "gui")]
+impl ChangeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the last output so the next [`ChangeTracker::compare`] reports a change
+    pub fn reset(&mut self) {
+        self.hashes = None;
+    }
+
+    /// Compare `lines` with the previous call and remember them.
+    ///
+    /// Returns None when nothing changed, otherwise the indices of lines that
+    /// are new or differ; the list is empty when lines were only removed
+    /// from the end.
+    pub fn compare<T: Hash>(&mut self, lines: &[T]) -> Option<Vec<usize>> {
+        let hashes: Vec<u64> = lines.iter().map(line_hash).collect();
+        let changed = match &self.hashes {
+            Some(old) if *old == hashes => return None,
+            Some(old) => (0..hashes.len()).filter(|&i| old.get(i) != Some(&hashes[i])).collect(),
+            None => (0..hashes.len()).collect(),
+        };
+        self.hashes = Some(hashes);
+        Some(changed)
+    }
+}
+
+/// Resolve a window to a row range inside the screen buffer
+fn window_rows(window: ReadWindow, csbi: &CONSOLE_SCREEN_BUFFER_INFO) -> (usize, usize) {
+    let cursor_row = csbi.dwCursorPosition.Y.max(0) as usize;
+    let height = csbi.dwSize.Y.max(0) as usize;
+    match window {
+        ReadWindow::ToCursor(n) => (cursor_row.saturating_sub(n), cursor_row + 1),
+        ReadWindow::All => (0, height),
+        ReadWindow::Viewport => {
+            let top = (csbi.srWindow.Top.max(0) as usize).min(height);
+            (top, (csbi.srWindow.Bottom.max(0) as usize + 1).clamp(top, height))
+        }
+    }
+}
+
+/// Columns to read: the visible ones for the viewport, otherwise the full buffer width
+fn window_cols(window: ReadWindow, csbi: &CONSOLE_SCREEN_BUFFER_INFO) -> Range<usize> {
+    let width = csbi.dwSize.X.max(0) as usize;
+    match window {
+        ReadWindow::Viewport => {
+            let left = (csbi.srWindow.Left.max(0) as usize).min(width);
+            left..(csbi.srWindow.Right.max(0) as usize + 1).clamp(left, width)
+        }
+        _ => 0..width,
+    }
+}
+
+/// Number of leading rows to keep: through the last non-blank row, and at least through the cursor
+fn used_rows(lines: &[StyledLine], cursor_index: usize) -> usize {
+    let keep = lines.iter().rposition(|l| !l.text.trim_end().is_empty()).map_or(0, |i| i + 1);
+    keep.max(cursor_index + 1).min(lines.len())
+}
+
+/// The attached console's screen buffer, shared for the whole attachment;
+/// see [`handles::conout`]
+pub(super) fn open_conout() -> Result<HANDLE> {
+    handles::conout()
+}
+
+/// Get console screen buffer info
+pub(super) fn buffer_info(conout: HANDLE) -> Result<CONSOLE_SCREEN_BUFFER_INFO> {
+    let started = Instant::now();
+    let mut csbi = CONSOLE_SCREEN_BUFFER_INFO::default();
+    let result = unsafe { GetConsoleScreenBufferInfo(conout, &mut csbi) }
+        .map_err(|e| anyhow!("Failed to get console buffer info: {}", e.to_string()));
+    add_timing(started, |t| &mut t.buffer_info);
+    result.map(|_| csbi)
+}
+
+/// Read rows `start..end` with one ReadConsoleOutputW call over the rectangle
+pub(super) fn read_rows(conout: HANDLE, start: usize, end: usize, cols: Range<usize>, keep_trailing: bool) -> Result<Vec<StyledLine>> {
+    let width = cols.len();
+    let block = read_block(conout, start, end, cols)?;
+    let started = Instant::now();
+    let lines = block
+        .chunks(width.max(1))
+        .map(|row| {
+            let (chars, attrs) = row_units(row);
+            styled_line(&chars, &attrs, keep_trailing)
+        })
+        .collect();
+    add_timing(started, |t| &mut t.convert);
     Ok(lines)
 }
 
-/// Read a single line from the console buffer
-fn read_line(conout: HANDLE, y: i16, width: usize) -> Result<String> {
-    let mut buffer = vec![0u16; width];
+/// Raw cells of rows `start..end`, row-major, `cols.len()` per row.
+///
+/// The rectangle is read in chunks of at most [`read_chunk_cells`] cells:
+/// bands of whole rows, each split into column strips when a single row is
+/// wider than the limit.
+fn read_block(conout: HANDLE, start: usize, end: usize, cols: Range<usize>) -> Result<Vec<CHAR_INFO>> {
+    let width = cols.len();
+    if start >= end || width == 0 {
+        return Ok(Vec::new());
+    }
+    let chunk = read_chunk_cells();
+    let strip = width.min(chunk);
+    let band = (chunk / strip).max(1);
+
+    let mut cells = vec![CHAR_INFO::default(); (end - start) * width];
+    let mut rows_read = 0;
+    'bands: for top in (start..end).step_by(band) {
+        let bottom = (top + band).min(end);
+        for left in cols.clone().step_by(strip) {
+            let right = (left + strip).min(cols.end);
+            let (part, got) = read_rect(conout, top..bottom, left..right)?;
+            let part_width = right - left;
+            for (y, row) in part.chunks(part_width).take(got).enumerate() {
+                let at = (top - start + y) * width + (left - cols.start);
+                cells[at..at + part_width].copy_from_slice(row);
+            }
+            // Rows past the end of the buffer are not there to read
+            if got < bottom - top {
+                rows_read = top - start + got;
+                break 'bands;
+            }
+        }
+        rows_read = bottom - start;
+    }
+
+    cells.truncate(rows_read * width);
+    Ok(cells)
+}
 
-    unsafe {
-        let coord = windows::Win32::System::Console::COORD { X: 0, Y: y };
-        let mut chars_read = 0;
+/// One ReadConsoleOutputW call over a rectangle, returning its cells and
+/// how many of its rows were actually read
+fn read_rect(conout: HANDLE, rows: Range<usize>, cols: Range<usize>) -> Result<(Vec<CHAR_INFO>, usize)> {
+    let width = cols.len();
+    let height = rows.len();
+    let mut cells = vec![CHAR_INFO::default(); height * width];
+    let mut region = SMALL_RECT {
+        Left: cols.start as i16,
+        Top: rows.start as i16,
+        Right: cols.end as i16 - 1,
+        Bottom: rows.end as i16 - 1,
+    };
 
-        ReadConsoleOutputCharacterW(
+    let started = Instant::now();
+    let result = unsafe {
+        ReadConsoleOutputW(
             conout,
-            &mut buffer,
-            coord,
-            &mut chars_read,
+            cells.as_mut_ptr(),
+            COORD { X: width as i16, Y: height as i16 },
+            COORD { X: 0, Y: 0 },
+            &mut region,
         )
-        .map_err(|e| anyhow!("Failed to read console output: {}", e.to_string()))?;
-    }
+    };
+    add_timing(started, |t| &mut t.read);
+    TIMINGS.with(|t| {
+        let mut timings = t.get();
+        timings.read_calls += 1;
+        t.set(timings);
+    });
+    result.map_err(|e| anyhow!("Failed to read console output: {}", e.to_string()))?;
 
-    // Convert to string and trim trailing nulls and spaces
-    let text = String::from_utf16_lossy(&buffer)
-        .trim_end_matches('\0')
-        .trim_end()
-        .to_string();
+    // The region shrinks to what was actually read
+    let rows_read = (region.Bottom - region.Top + 1).max(0) as usize;
+    Ok((cells, rows_read.min(height)))
+}
 
-    Ok(text)
+/// Cells of a row, one per buffer cell. The right half of a wide character
+/// and the second cell of a surrogate pair become `'\0'` continuation cells.
+#[cfg(feature = "gui")]
+fn row_cells(row: &[CHAR_INFO]) -> Vec<Cell> {
+    let flags = COMMON_LVB_LEADING_BYTE.0 | COMMON_LVB_TRAILING_BYTE.0;
+    let mut cells: Vec<Cell> = Vec::with_capacity(row.len());
+    let mut i = 0;
+    while i < row.len() {
+        let unit = unsafe { row[i].Char.UnicodeChar };
+        let attr = row[i].Attributes & !flags;
+        let trailing = row[i].Attributes & COMMON_LVB_TRAILING_BYTE.0 != 0;
+        let prev = (i > 0).then(|| unsafe { row[i - 1].Char.UnicodeChar });
+        if trailing && prev == Some(unit) {
+            cells.push(Cell { ch: '\0', attr });
+            i += 1;
+            continue;
+        }
+        let next = row.get(i + 1).map(|c| unsafe { c.Char.UnicodeChar });
+        let pair = next.filter(|_| (0xD800..0xDC00).contains(&unit));
+        match char::decode_utf16([Some(unit), pair].into_iter().flatten()).next() {
+            Some(Ok(ch)) if pair.is_some() && ch.len_utf16() == 2 => {
+                cells.push(Cell { ch, attr });
+                cells.push(Cell { ch: '\0', attr: row[i + 1].Attributes & !flags });
+                i += 2;
+            }
+            Some(Ok(ch)) => {
+                cells.push(Cell { ch, attr });
+                i += 1;
+            }
+            _ => {
+                cells.push(Cell { ch: char::REPLACEMENT_CHARACTER, attr });
+                i += 1;
+            }
+        }
+    }
+    cells
+}
+
+/// UTF-16 units and attributes of a row with double-width characters collapsed.
+///
+/// A wide (e.g. CJK) character fills two cells holding the same code unit,
+/// flagged leading and trailing byte; the trailing copy is dropped. A
+/// trailing cell with a different unit is the low half of a surrogate pair
+/// and is kept so the pair decodes. The flags are cleared so they do not
+/// split color runs.
+fn row_units(row: &[CHAR_INFO]) -> (Vec<u16>, Vec<u16>) {
+    let flags = COMMON_LVB_LEADING_BYTE.0 | COMMON_LVB_TRAILING_BYTE.0;
+    let mut chars = Vec::with_capacity(row.len());
+    let mut attrs = Vec::with_capacity(row.len());
+    for cell in row {
+        let unit = unsafe { cell.Char.UnicodeChar };
+        let trailing = cell.Attributes & COMMON_LVB_TRAILING_BYTE.0 != 0;
+        if trailing && chars.last() == Some(&unit) {
+            continue;
+        }
+        chars.push(unit);
+        attrs.push(cell.Attributes & !flags);
+    }
+    (chars, attrs)
 }
 
-/// Convert "CONOUT$" to a wide null-terminated string
-fn conout_wide() -> Vec<u16> {
-    let mut s: Vec<u16> = "CONOUT$".encode_utf16().collect();
-    s.push(0);
-    s
+/// Decode a row of cells, merging equal neighbouring attributes into runs
+/// and trimming trailing nulls and spaces unless `keep_trailing` is set
+pub(super) fn styled_line(cells: &[u16], attrs: &[u16], keep_trailing: bool) -> StyledLine {
+    let mut text = String::with_capacity(cells.len());
+    let mut runs: Vec<ColorRun> = Vec::new();
+    let mut cell = 0;
+
+    for ch in char::decode_utf16(cells.iter().copied()) {
+        let ch = match ch {
+            Ok('\0') => ' ',
+            Ok(ch) => ch,
+            Err(_) => char::REPLACEMENT_CHARACTER,
+        };
+        let attr = attrs.get(cell).copied().unwrap_or(0);
+        let start = text.len();
+        text.push(ch);
+        match runs.last_mut() {
+            Some(run) if run.attr == attr => run.range.end = text.len(),
+            _ => runs.push(ColorRun { range: start..text.len(), attr }),
+        }
+        cell += ch.len_utf16();
+    }
+
+    if keep_trailing {
+        return StyledLine { text, runs };
+    }
+    let trimmed = text.trim_end().len();
+    text.truncate(trimmed);
+    runs.retain_mut(|run| {
+        run.range.end = run.range.end.min(trimmed);
+        run.range.start < run.range.end
+    });
+
+    StyledLine { text, runs }
 }
 
 /// Read all available console content (for debugging)
+#[cfg(feature = "gui")]
 pub fn read_all_console() -> Result<String> {
     let lines = read_console_lines(500)?;
     Ok(lines.join("\n"))
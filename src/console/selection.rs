@@ -0,0 +1,54 @@
+use std::ops::Range;
+use windows::Win32::System::Console::{
+    GetConsoleSelectionInfo, CONSOLE_MOUSE_SELECTION, CONSOLE_SELECTION_INFO,
+    CONSOLE_SELECTION_IN_PROGRESS, CONSOLE_SELECTION_NOT_EMPTY,
+};
+use anyhow::{Result, anyhow};
+use super::read::{open_conout, read_rows};
+
+/// A selection the user made in the attached console's own window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleSelection {
+    /// Selected buffer rows and columns
+    pub rows: Range<usize>,
+    pub cols: Range<usize>,
+    /// Selection mode is active; conhost holds back the target's output meanwhile
+    pub in_progress: bool,
+    /// Made with the mouse (quick-edit) rather than Mark from the system menu
+    pub mouse: bool,
+    /// Text inside the selected rectangle, one entry per row
+    pub text: Vec<String>,
+}
+
+/// Current selection in the attached console, if anything is selected
+pub fn console_selection() -> Result<Option<ConsoleSelection>> {
+    let mut info = CONSOLE_SELECTION_INFO::default();
+    unsafe { GetConsoleSelectionInfo(&mut info) }
+        .map_err(|e| anyhow!("Failed to get selection info: {}", e))?;
+
+    let in_progress = info.dwFlags & CONSOLE_SELECTION_IN_PROGRESS != 0;
+    if !in_progress && info.dwFlags & CONSOLE_SELECTION_NOT_EMPTY == 0 {
+        return Ok(None);
+    }
+
+    let r = info.srSelection;
+    let rows = r.Top.max(0) as usize..(r.Bottom.max(0) as usize + 1);
+    let cols = r.Left.max(0) as usize..(r.Right.max(0) as usize + 1);
+    let text = if info.dwFlags & CONSOLE_SELECTION_NOT_EMPTY != 0 {
+        let conout = open_conout()?;
+        read_rows(conout, rows.start, rows.end, cols.clone(), false)?
+            .into_iter()
+            .map(|l| l.text)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Some(ConsoleSelection {
+        rows,
+        cols,
+        in_progress,
+        mouse: info.dwFlags & CONSOLE_MOUSE_SELECTION != 0,
+        text,
+    }))
+}
@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use windows::Win32::System::Console::{
+    GetConsoleWindow, ENHANCED_KEY, INPUT_RECORD, KEY_EVENT, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED,
+    RIGHT_ALT_PRESSED, RIGHT_CTRL_PRESSED, SHIFT_PRESSED,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_EXTENDEDKEY,
+    KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, VIRTUAL_KEY,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SetForegroundWindow};
+use anyhow::{Result, anyhow};
+
+/// Set while input is being typed with SendInput because the console refused
+/// WriteConsoleInputW; cleared by the next direct write that succeeds
+static DEGRADED: AtomicBool = AtomicBool::new(false);
+
+/// Virtual keys of the modifiers a record's control key state can ask for
+const VK_SHIFT: u16 = 0x10;
+const VK_CONTROL: u16 = 0x11;
+const VK_MENU: u16 = 0x12;
+
+/// Whether the latest input reached the target through the SendInput fallback
+#[cfg(feature = "gui")]
+pub fn input_degraded() -> bool {
+    DEGRADED.load(Ordering::Relaxed)
+}
+
+pub(super) fn set_degraded(on: bool) {
+    DEGRADED.store(on, Ordering::Relaxed);
+}
+
+/// Type key records into the attached console's window as real keystrokes.
+///
+/// The window is brought to the front first, since SendInput goes to
+/// whatever has focus. Mouse records are dropped. Fails when the window
+/// cannot be focused, or when UIPI blocks input to an elevated target.
+pub(super) fn type_records(records: &[INPUT_RECORD]) -> Result<()> {
+    let inputs: Vec<INPUT> = records.iter().flat_map(keyboard_inputs).collect();
+    if inputs.is_empty() {
+        return Err(anyhow!("Nothing SendInput can type"));
+    }
+
+    let hwnd = unsafe { GetConsoleWindow() };
+    if hwnd.is_invalid() {
+        return Err(anyhow!("Console has no window to type into"));
+    }
+    let _ = unsafe { SetForegroundWindow(hwnd) };
+    if unsafe { GetForegroundWindow() } != hwnd {
+        return Err(anyhow!("Could not bring the target window to the front"));
+    }
+
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err(anyhow!("SendInput typed {} of {} events", sent, inputs.len()));
+    }
+    set_degraded(true);
+    Ok(())
+}
+
+/// SendInput events for one key record. Printable characters without Ctrl
+/// or Alt go as Unicode input; other keys press their virtual key inside
+/// the modifiers the record's state names.
+fn keyboard_inputs(record: &INPUT_RECORD) -> Vec<INPUT> {
+    if record.EventType as u32 != KEY_EVENT {
+        return Vec::new();
+    }
+    let key = unsafe { record.Event.KeyEvent };
+    let up = if key.bKeyDown.as_bool() { KEYBD_EVENT_FLAGS(0) } else { KEYEVENTF_KEYUP };
+    let unit = unsafe { key.uChar.UnicodeChar };
+    let vk = key.wVirtualKeyCode;
+    let state = key.dwControlKeyState;
+
+    let mut mods = Vec::new();
+    if state & (LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED) != 0 && vk != VK_CONTROL {
+        mods.push(VK_CONTROL);
+    }
+    if state & (LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED) != 0 && vk != VK_MENU {
+        mods.push(VK_MENU);
+    }
+    let is_modifier = matches!(vk, VK_SHIFT | VK_CONTROL | VK_MENU);
+    if unit >= 0x20 && mods.is_empty() && !is_modifier {
+        return vec![keyboard(0, unit, KEYEVENTF_UNICODE | up)];
+    }
+    if state & SHIFT_PRESSED != 0 && vk != VK_SHIFT {
+        mods.push(VK_SHIFT);
+    }
+
+    let extended = if state & ENHANCED_KEY != 0 { KEYEVENTF_EXTENDEDKEY } else { KEYBD_EVENT_FLAGS(0) };
+    let pressed = keyboard(vk, key.wVirtualScanCode, extended | up);
+    if up.0 == 0 {
+        mods.iter().map(|m| keyboard(*m, 0, KEYBD_EVENT_FLAGS(0))).chain(Some(pressed)).collect()
+    } else {
+        Some(pressed).into_iter().chain(mods.iter().rev().map(|m| keyboard(*m, 0, KEYEVENTF_KEYUP))).collect()
+    }
+}
+
+fn keyboard(vk: u16, scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT { wVk: VIRTUAL_KEY(vk), wScan: scan, dwFlags: flags, time: 0, dwExtraInfo: 0 },
+        },
+    }
+}
@@ -0,0 +1,33 @@
+use super::read::ColorRun;
+
+/// Foreground bits of a console attribute, ignoring intensity
+const FG_COLOR: u16 = 0x07;
+const RED: u16 = 0x04;
+const YELLOW: u16 = 0x06;
+
+/// How alarming a line looks, judged by the colors the target printed it in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Some text is yellow (dark or bright)
+    Warning,
+    /// Some text is red (dark or bright)
+    Error,
+}
+
+/// Classify a line by the foreground color of its visible text; red wins over
+/// yellow, and whitespace in any color does not count
+pub fn classify(text: &str, runs: &[ColorRun]) -> Option<Severity> {
+    let mut found = None;
+    for run in runs {
+        let visible = text.get(run.range.clone()).is_some_and(|t| !t.trim().is_empty());
+        if !visible {
+            continue;
+        }
+        match run.attr & FG_COLOR {
+            RED => return Some(Severity::Error),
+            YELLOW => found = Some(Severity::Warning),
+            _ => {}
+        }
+    }
+    found
+}
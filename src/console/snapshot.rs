@@ -0,0 +1,113 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use windows::Win32::System::Console::{
+    GetConsoleCursorInfo, GetConsoleScreenBufferInfoEx, CONSOLE_CURSOR_INFO,
+    CONSOLE_SCREEN_BUFFER_INFOEX,
+};
+use anyhow::{Result, anyhow};
+use super::attach::console_title;
+use super::read::{open_conout, read_console_cells, Cell, ReadWindow};
+
+/// Version of the snapshot layout, bumped when fields change meaning
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Dump the whole screen buffer of the attached console to `path` as JSON
+// The UI saves snapshots through the storage backends; this is for callers that just want a file
+#[allow(dead_code)]
+pub fn snapshot_console(path: &Path) -> Result<()> {
+    let json = snapshot_json()?;
+    std::fs::write(path, json)
+        .map_err(|e| anyhow!("Failed to write snapshot {}: {}", path.display(), e))
+}
+
+/// Snapshot of the attached console's screen buffer as a JSON document.
+///
+/// Besides the buffer and window geometry, cursor and color table, every
+/// buffer row is stored as `text` plus attribute `runs` of
+/// `[first column, cell count, attribute]`. The text has one character per
+/// cell, with `\u0000` for the right half of a wide character, so a column
+/// in a run is also a character index; trailing blanks are trimmed from the
+/// text but the runs cover the full width.
+pub fn snapshot_json() -> Result<String> {
+    let conout = open_conout()?;
+    let mut info = CONSOLE_SCREEN_BUFFER_INFOEX {
+        cbSize: std::mem::size_of::<CONSOLE_SCREEN_BUFFER_INFOEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetConsoleScreenBufferInfoEx(conout, &mut info) }
+        .map_err(|e| anyhow!("Failed to get console buffer info: {}", e))?;
+    let mut cursor = CONSOLE_CURSOR_INFO::default();
+    unsafe { GetConsoleCursorInfo(conout, &mut cursor) }
+        .map_err(|e| anyhow!("Failed to get cursor info: {}", e))?;
+    let grid = read_console_cells(ReadWindow::All)?;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{{");
+    let _ = writeln!(out, "  \"version\": {},", SNAPSHOT_VERSION);
+    let _ = writeln!(out, "  \"taken_at\": {},", json_string(&chrono::Local::now().to_rfc3339()));
+    let _ = writeln!(out, "  \"title\": {},", json_string(&console_title()));
+    let _ = writeln!(out, "  \"buffer\": {{ \"width\": {}, \"height\": {} }},", info.dwSize.X, info.dwSize.Y);
+    let w = info.srWindow;
+    let _ = writeln!(out, "  \"window\": {{ \"left\": {}, \"top\": {}, \"right\": {}, \"bottom\": {} }},", w.Left, w.Top, w.Right, w.Bottom);
+    let _ = writeln!(out, "  \"cursor\": {{ \"row\": {}, \"col\": {}, \"visible\": {}, \"size\": {} }},",
+        grid.cursor_row, grid.cursor_col, cursor.bVisible.as_bool(), cursor.dwSize);
+    let _ = writeln!(out, "  \"attributes\": {},", info.wAttributes.0);
+    let colors: Vec<String> = info.ColorTable.iter()
+        .map(|c| {
+            // COLORREF is 0x00BBGGRR
+            let (r, g, b) = (c.0 & 0xFF, (c.0 >> 8) & 0xFF, (c.0 >> 16) & 0xFF);
+            format!("\"#{:02x}{:02x}{:02x}\"", r, g, b)
+        })
+        .collect();
+    let _ = writeln!(out, "  \"color_table\": [{}],", colors.join(", "));
+
+    let _ = writeln!(out, "  \"rows\": [");
+    let height = grid.height();
+    for (y, row) in grid.rows().enumerate() {
+        let comma = if y + 1 < height { "," } else { "" };
+        let _ = writeln!(out, "    {{ \"text\": {}, \"runs\": {} }}{}", json_string(&row_text(row)), row_runs(row), comma);
+    }
+    let _ = writeln!(out, "  ]");
+    let _ = writeln!(out, "}}");
+    Ok(out)
+}
+
+/// One character per cell, trailing blanks trimmed
+fn row_text(row: &[Cell]) -> String {
+    let text: String = row.iter().map(|c| c.ch).collect();
+    text.trim_end_matches(' ').to_string()
+}
+
+/// `[[first column, cell count, attribute], ...]` for a row
+fn row_runs(row: &[Cell]) -> String {
+    let mut runs: Vec<(usize, usize, u16)> = Vec::new();
+    for (col, cell) in row.iter().enumerate() {
+        match runs.last_mut() {
+            Some((_, len, attr)) if *attr == cell.attr => *len += 1,
+            _ => runs.push((col, 1, cell.attr)),
+        }
+    }
+    let parts: Vec<String> = runs.iter().map(|(start, len, attr)| format!("[{}, {}, {}]", start, len, attr)).collect();
+    format!("[{}]", parts.join(", "))
+}
+
+/// Quote and escape a string for JSON
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
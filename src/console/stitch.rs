@@ -0,0 +1,117 @@
+use anyhow::Result;
+use super::read::{buffer_info, open_conout, read_rows};
+
+/// Rows remembered from the end of the history to find our place after a scroll
+const ANCHOR_ROWS: usize = 3;
+
+/// Lines that became final since the last [`ScrollStitcher::poll`]
+#[derive(Debug, Clone, Default)]
+pub struct Stitched {
+    pub lines: Vec<String>,
+    /// The previous position could not be found again (more output than the
+    /// buffer holds went by between polls), so lines may be missing
+    pub gap: bool,
+    /// The screen was cleared (cls): the cursor moved up and everything
+    /// between it and where we stopped is blank. `lines` then holds what was
+    /// written after the clear.
+    pub cleared: bool,
+}
+
+/// Follows the screen buffer as it scrolls and hands out every line above
+/// the cursor exactly once, regardless of how far it scrolled between polls.
+///
+/// The stitcher remembers the buffer row of the first line it has not handed
+/// out yet and the text of the last few it has. While the buffer has room the
+/// cursor just moves down; once it is full the buffer scrolls and the anchor
+/// rows are searched for higher up to learn how far it moved.
+#[derive(Debug, Clone, Default)]
+struct ScrollStitcher {
+    next_row: Option<usize>,
+    anchor: Vec<String>,
+}
+
+impl ScrollStitcher {
+    /// Collect the lines finished since the last poll (rows above the cursor)
+    fn poll(&mut self) -> Result<Stitched> {
+        let conout = open_conout()?;
+        let csbi = buffer_info(conout)?;
+        let cursor_row = csbi.dwCursorPosition.Y.max(0) as usize;
+        let cols = 0..csbi.dwSize.X.max(0) as usize;
+
+        let mut gap = false;
+        let mut cleared = false;
+        let start = match self.next_row {
+            None => 0,
+            Some(next) => {
+                // Common case: the anchor is still right above where we stopped
+                let anchor_start = next.saturating_sub(self.anchor.len());
+                let end = cursor_row.max(next);
+                let rows = texts(read_rows(conout, anchor_start, end, cols.clone(), false)?);
+                if rows.starts_with(&self.anchor) {
+                    let fresh: Vec<String> = rows[self.anchor.len()..].iter().take(cursor_row.saturating_sub(next)).cloned().collect();
+                    self.next_row = Some(cursor_row.max(next));
+                    self.remember(&fresh);
+                    return Ok(Stitched { lines: fresh, gap: false, cleared: false });
+                }
+
+                // The buffer scrolled (or was cleared); look for the anchor higher up
+                let above = texts(read_rows(conout, 0, next.min(cursor_row + 1), cols.clone(), false)?);
+                match find_anchor(&above, &self.anchor) {
+                    Some(row) => row + self.anchor.len(),
+                    None if cursor_row < next && blank(&texts(read_rows(conout, cursor_row + 1, next, cols.clone(), false)?)) => {
+                        cleared = true;
+                        0
+                    }
+                    None => {
+                        gap = true;
+                        0
+                    }
+                }
+            }
+        }
+        .min(cursor_row);
+
+        let lines = texts(read_rows(conout, start, cursor_row, cols, false)?);
+        self.next_row = Some(cursor_row);
+        self.remember(&lines);
+        Ok(Stitched { lines, gap, cleared })
+    }
+
+    /// Keep the last handed-out rows as the anchor
+    fn remember(&mut self, lines: &[String]) {
+        self.anchor.extend(lines.iter().cloned());
+        let excess = self.anchor.len().saturating_sub(ANCHOR_ROWS);
+        self.anchor.drain(..excess);
+    }
+}
+
+/// Where a tail reader stopped: the first row it has not read yet and the
+/// last rows it has, so the position survives the buffer scrolling. The
+/// default marker starts at the top of the buffer.
+#[derive(Debug, Clone, Default)]
+pub struct CursorMarker(ScrollStitcher);
+
+/// `tail -f` style read: the lines finished since `marker` (rows the cursor
+/// has moved past) and the marker to pass next time. The line the cursor
+/// is on is only returned once the cursor leaves it.
+pub fn read_new_lines_since(marker: &CursorMarker) -> Result<(Stitched, CursorMarker)> {
+    let mut stitcher = marker.0.clone();
+    let stitched = stitcher.poll()?;
+    Ok((stitched, CursorMarker(stitcher)))
+}
+
+fn texts(lines: Vec<super::StyledLine>) -> Vec<String> {
+    lines.into_iter().map(|l| l.text).collect()
+}
+
+fn blank(rows: &[String]) -> bool {
+    rows.iter().all(|r| r.trim().is_empty())
+}
+
+/// Last position in `rows` where `anchor` appears as consecutive rows
+fn find_anchor(rows: &[String], anchor: &[String]) -> Option<usize> {
+    if anchor.is_empty() || rows.len() < anchor.len() {
+        return None;
+    }
+    (0..=rows.len() - anchor.len()).rev().find(|&i| rows[i..i + anchor.len()] == *anchor)
+}
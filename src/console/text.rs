@@ -0,0 +1,86 @@
+/// Line terminator written between exported lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::CrLf];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+}
+
+/// How read lines are cleaned up before they leave the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Drop trailing spaces even when the reader kept them
+    pub trim_trailing: bool,
+    /// Expand tab characters to stops this many columns apart; None keeps them
+    pub tab_width: Option<usize>,
+    pub line_ending: LineEnding,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            trim_trailing: false,
+            tab_width: None,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+impl TextOptions {
+    /// Normalize one line: stray CR/LF characters from the cells become
+    /// spaces so they cannot break the line ending, then tabs are expanded
+    /// and trailing spaces trimmed as configured
+    pub fn apply(&self, line: &str) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut col = 0;
+        for ch in line.chars() {
+            match ch {
+                '\r' | '\n' => {
+                    out.push(' ');
+                    col += 1;
+                }
+                '\t' if self.tab_width.is_some_and(|w| w > 0) => {
+                    let width = self.tab_width.unwrap();
+                    let pad = width - col % width;
+                    out.extend(std::iter::repeat_n(' ', pad));
+                    col += pad;
+                }
+                _ => {
+                    out.push(ch);
+                    col += 1;
+                }
+            }
+        }
+        if self.trim_trailing {
+            out.truncate(out.trim_end_matches(' ').len());
+        }
+        out
+    }
+
+    /// Normalize and join lines, ending each with the configured terminator
+    pub fn join<S: AsRef<str>>(&self, lines: &[S]) -> String {
+        let mut out = String::new();
+        for line in lines {
+            out.push_str(&self.apply(line.as_ref()));
+            out.push_str(self.line_ending.as_str());
+        }
+        out
+    }
+}
@@ -0,0 +1,347 @@
+use super::read::{styled_line, StyledLine};
+
+/// Escape character that starts a VT sequence
+const ESC: char = '\x1b';
+
+/// Console attribute bits for the foreground and background colors
+const FG_MASK: u16 = 0x0F;
+const BG_MASK: u16 = 0xF0;
+const FG_INTENSITY: u16 = 0x08;
+const BG_INTENSITY: u16 = 0x80;
+
+/// Where the parser is inside a sequence; kept across rows because a
+/// sequence can wrap onto the next buffer row
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi(String),
+    /// Operating system command (window title etc.), skipped up to BEL or ST
+    Osc { escape: bool },
+}
+
+/// Interpret VT/ANSI escape sequences left as literal text in the buffer.
+///
+/// Programs that write VT output to a console without VT processing leave
+/// the raw ESC sequences in the cells. This replays the rows through a small
+/// terminal model: SGR colors and bold become console attributes, cursor
+/// movement and erase act on the rows, and every other sequence is dropped.
+/// Rows without an ESC come back unchanged.
+pub fn interpret(lines: &[StyledLine]) -> Vec<StyledLine> {
+    if !lines.iter().any(|l| l.text.contains(ESC)) {
+        return lines.to_vec();
+    }
+
+    let width = lines.iter().map(|l| l.text.chars().count()).max().unwrap_or(0);
+    let mut screen = Screen::new(lines.len(), width);
+    for line in lines {
+        for (ch, attr) in cells_of(line) {
+            screen.feed(ch, attr);
+        }
+        screen.end_row();
+    }
+    screen.into_lines()
+}
+
+/// Characters of a line paired with the attribute of the run they sit in
+fn cells_of(line: &StyledLine) -> Vec<(char, u16)> {
+    line.text.char_indices()
+        .map(|(i, ch)| {
+            let attr = line.runs.iter()
+                .find(|r| r.range.contains(&i))
+                .map_or(0x07, |r| r.attr);
+            (ch, attr)
+        })
+        .collect()
+}
+
+/// Grid the rows are replayed into; the cursor stays inside the source rows
+struct Screen {
+    rows: Vec<Vec<(char, u16)>>,
+    height: usize,
+    width: usize,
+    row: usize,
+    col: usize,
+    /// Attribute set by SGR; None while the cells' own attributes apply
+    sgr: Option<u16>,
+    state: State,
+}
+
+impl Screen {
+    fn new(height: usize, width: usize) -> Self {
+        Self {
+            rows: vec![Vec::new()],
+            height: height.max(1),
+            width: width.max(1),
+            row: 0,
+            col: 0,
+            sgr: None,
+            state: State::Ground,
+        }
+    }
+
+    fn feed(&mut self, ch: char, attr: u16) {
+        match std::mem::replace(&mut self.state, State::Ground) {
+            State::Ground if ch == ESC => self.state = State::Escape,
+            State::Ground => self.put(ch, self.sgr.unwrap_or(attr)),
+            State::Escape => match ch {
+                '[' => self.state = State::Csi(String::new()),
+                ']' => self.state = State::Osc { escape: false },
+                // Intermediate bytes, e.g. the `(` of a charset designation
+                '\x20'..='\x2f' => self.state = State::Escape,
+                _ => {}
+            },
+            State::Csi(mut params) => {
+                if ('\x40'..='\x7e').contains(&ch) {
+                    self.csi(&params, ch, attr);
+                } else {
+                    params.push(ch);
+                    self.state = State::Csi(params);
+                }
+            }
+            State::Osc { escape } => {
+                let done = ch == '\x07' || (escape && ch == '\\');
+                if !done {
+                    self.state = State::Osc { escape: ch == ESC };
+                }
+            }
+        }
+    }
+
+    /// Move to the next source row, unless a sequence is still open
+    fn end_row(&mut self) {
+        if self.state == State::Ground {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    /// Write a cell; past the last column it overwrites the last cell
+    fn put(&mut self, ch: char, attr: u16) {
+        let col = self.col.min(self.width - 1);
+        let row = self.row_mut();
+        if row.len() <= col {
+            row.resize(col + 1, (' ', attr));
+        }
+        row[col] = (ch, attr);
+        self.col = col + 1;
+    }
+
+    fn row_mut(&mut self) -> &mut Vec<(char, u16)> {
+        let row = self.row.min(self.height - 1);
+        if self.rows.len() <= row {
+            self.rows.resize(row + 1, Vec::new());
+        }
+        &mut self.rows[row]
+    }
+
+    /// Apply a complete control sequence
+    fn csi(&mut self, params: &str, final_byte: char, attr: u16) {
+        let args: Vec<usize> = params.trim_start_matches('?')
+            .split(';')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let n = args.first().copied().unwrap_or(0).max(1);
+
+        match final_byte {
+            'm' => self.sgr(&args, attr),
+            'A' => self.row = self.row.saturating_sub(n),
+            'B' => self.row = (self.row + n).min(self.height - 1),
+            'C' => self.col = (self.col + n).min(self.width - 1),
+            'D' => self.col = self.col.saturating_sub(n),
+            'G' => self.col = (n - 1).min(self.width - 1),
+            'H' | 'f' => {
+                self.row = (n - 1).min(self.height - 1);
+                self.col = (args.get(1).copied().unwrap_or(0).max(1) - 1).min(self.width - 1);
+            }
+            'K' => {
+                let col = self.col;
+                let row = self.row_mut();
+                match args.first().copied().unwrap_or(0) {
+                    0 => row.truncate(col),
+                    1 => row.iter_mut().take(col + 1).for_each(|c| c.0 = ' '),
+                    _ => row.clear(),
+                }
+            }
+            'J' => match args.first().copied().unwrap_or(0) {
+                // Cursor to the end of the screen
+                0 => {
+                    let (row, col) = (self.row.min(self.height - 1), self.col);
+                    self.rows.truncate(row + 1);
+                    self.row_mut().truncate(col);
+                }
+                // Start of the screen to the cursor
+                1 => {
+                    let (row, col) = (self.row.min(self.height - 1), self.col);
+                    self.rows.iter_mut().take(row).for_each(Vec::clear);
+                    self.row_mut().iter_mut().take(col + 1).for_each(|c| c.0 = ' ');
+                }
+                2 | 3 => {
+                    self.rows = vec![Vec::new()];
+                    self.row = 0;
+                    self.col = 0;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Apply Select Graphic Rendition parameters
+    fn sgr(&mut self, args: &[usize], attr: u16) {
+        let mut current = self.sgr.unwrap_or(attr);
+        let mut i = 0;
+        while i < args.len() {
+            match args[i] {
+                0 => {
+                    self.sgr = None;
+                    current = attr;
+                    i += 1;
+                    continue;
+                }
+                1 => current |= FG_INTENSITY,
+                22 => current &= !FG_INTENSITY,
+                7 => current = (current & !0xFF) | ((current & FG_MASK) << 4) | ((current & BG_MASK) >> 4),
+                c @ 30..=37 => current = (current & !FG_MASK) | (current & FG_INTENSITY) | ansi_to_console(c - 30),
+                39 => current = (current & !FG_MASK) | 0x07,
+                c @ 40..=47 => current = (current & !BG_MASK) | (ansi_to_console(c - 40) << 4),
+                49 => current &= !BG_MASK,
+                c @ 90..=97 => current = (current & !FG_MASK) | FG_INTENSITY | ansi_to_console(c - 90),
+                c @ 100..=107 => current = (current & !BG_MASK) | BG_INTENSITY | (ansi_to_console(c - 100) << 4),
+                c @ (38 | 48) => {
+                    let (color, used) = extended_color(&args[i + 1..]);
+                    if let Some(color) = color {
+                        current = if c == 38 {
+                            (current & !FG_MASK) | color
+                        } else {
+                            (current & !BG_MASK) | (color << 4)
+                        };
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            self.sgr = Some(current);
+            i += 1;
+        }
+    }
+
+    fn into_lines(self) -> Vec<StyledLine> {
+        self.rows.iter()
+            .map(|row| {
+                let mut cells = Vec::with_capacity(row.len());
+                let mut attrs = Vec::with_capacity(row.len());
+                for &(ch, attr) in row {
+                    let mut buf = [0u16; 2];
+                    for unit in ch.encode_utf16(&mut buf) {
+                        cells.push(*unit);
+                        attrs.push(attr);
+                    }
+                }
+                styled_line(&cells, &attrs, true)
+            })
+            .collect()
+    }
+}
+
+/// Map an ANSI color index (red = 1, blue = 4) to console bits (blue = 1, red = 4)
+fn ansi_to_console(index: usize) -> u16 {
+    let index = index as u16;
+    ((index & 1) << 2) | (index & 2) | ((index & 4) >> 2)
+}
+
+/// Parse the tail of a 38/48 color (`5;n` or `2;r;g;b`) into the nearest
+/// of the 16 console colors, returning how many arguments were consumed
+fn extended_color(args: &[usize]) -> (Option<u16>, usize) {
+    match args {
+        [5, n, ..] if *n < 8 => (Some(ansi_to_console(*n)), 2),
+        [5, n, ..] if *n < 16 => (Some(FG_INTENSITY | ansi_to_console(n - 8)), 2),
+        [5, n, ..] if *n < 232 => {
+            let c = n - 16;
+            let scale = |v: usize| v * 51;
+            (Some(nearest_console_color(scale(c / 36), scale((c / 6) % 6), scale(c % 6))), 2)
+        }
+        [5, n, ..] => {
+            let gray = 8 + (n.min(&255) - 232) * 10;
+            (Some(nearest_console_color(gray, gray, gray)), 2)
+        }
+        [2, r, g, b, ..] => (Some(nearest_console_color(*r, *g, *b)), 4),
+        _ => (None, args.len()),
+    }
+}
+
+/// Rough match of an RGB color to a console color: a channel counts when it
+/// is over half, bright when the strongest channel is near full
+fn nearest_console_color(r: usize, g: usize, b: usize) -> u16 {
+    let mut color = 0;
+    if r > 127 {
+        color |= 0x04;
+    }
+    if g > 127 {
+        color |= 0x02;
+    }
+    if b > 127 {
+        color |= 0x01;
+    }
+    if r.max(g).max(b) > 191 {
+        color |= FG_INTENSITY;
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(texts: &[&str]) -> Vec<StyledLine> {
+        texts.iter().map(|t| StyledLine { text: t.to_string(), runs: Vec::new() }).collect()
+    }
+
+    fn texts(lines: &[StyledLine]) -> Vec<&str> {
+        lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn sgr_sets_console_attributes() {
+        let cases = [
+            ("\x1b[31mx", 0x04),
+            ("\x1b[1;32mx", 0x0A),
+            ("\x1b[44mx", 0x17),
+            ("\x1b[97mx", 0x0F),
+            ("\x1b[7mx", 0x70),
+            ("\x1b[31m\x1b[0mx", 0x07),
+            ("\x1b[31m\x1b[39mx", 0x07),
+            ("\x1b[38;5;9mx", 0x0C),
+            ("\x1b[48;2;0;0;255mx", 0x97),
+        ];
+        for (input, attr) in cases {
+            let out = interpret(&lines(&[input]));
+            assert_eq!(texts(&out), vec!["x"], "{:?}", input);
+            assert_eq!(out[0].runs[0].attr, attr, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn cursor_moves_and_erases_stay_in_the_source_rows() {
+        let cases: [(&[&str], &[&str]); 9] = [
+            (&["ab\x1b[Dc"], &["ac"]),
+            (&["ab\x1b[99Cc"], &["ab     c"]),
+            (&["a", "b\x1b[9Bc"], &["a", "bc"]),
+            (&["\x1b[5;3Hx", ""], &["", "  x"]),
+            (&["\x1b[99;99Hx"], &["        x"]),
+            (&["abcdef\x1b[3D\x1b[K"], &["abc"]),
+            (&["abc", "def", "gh\x1b[2;2H\x1b[J"], &["abc", "d"]),
+            (&["abc", "def", "gh\x1b[2;2H\x1b[1J"], &["", "  f", "gh"]),
+            (&["abc", "def\x1b[2J", "x"], &["", "x"]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(texts(&interpret(&lines(input))), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rows_without_escapes_are_unchanged() {
+        let input = lines(&["plain", "text"]);
+        assert_eq!(interpret(&input), input);
+    }
+}
@@ -1,146 +1,493 @@
+use std::cell::Cell;
+use std::time::Duration;
 use windows::Win32::System::Console::{
-    WriteConsoleInputW, INPUT_RECORD, KEY_EVENT_RECORD,
+    GenerateConsoleCtrlEvent, GetNumberOfConsoleInputEvents, WriteConsoleInputW, COORD, CTRL_C_EVENT,
+    MOUSE_EVENT, MOUSE_EVENT_RECORD, ENABLE_PROCESSED_INPUT, ENHANCED_KEY, INPUT_RECORD, INPUT_RECORD_0, KEY_EVENT_RECORD, KEY_EVENT_RECORD_0, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED,
+    SHIFT_PRESSED,
 };
-use windows::Win32::Storage::FileSystem::{
-    CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING, FILE_GENERIC_WRITE,
-    FILE_ATTRIBUTE_NORMAL,
-};
-use windows::Win32::Foundation::HANDLE;
-use windows::core::PCWSTR;
+#[cfg(feature = "gui")]
+use windows::Win32::System::Console::{FROM_LEFT_1ST_BUTTON_PRESSED, FROM_LEFT_2ND_BUTTON_PRESSED, RIGHTMOST_BUTTON_PRESSED};
+use windows::Win32::UI::Input::KeyboardAndMouse::{MapVirtualKeyExW, MapVirtualKeyW, VkKeyScanExW, HKL, MAPVK_VK_TO_VSC};
+use windows::Win32::Globalization::{GetACP, GetOEMCP, MultiByteToWideChar, MULTI_BYTE_TO_WIDE_CHAR_FLAGS};
 use anyhow::{Result, anyhow};
+use super::attach::ctrl_guarded;
+use super::dryrun;
+use super::handles;
+use super::keys::{is_enhanced, key_char};
+#[cfg(feature = "gui")]
+use super::keys::VirtualKey;
+use super::layout::target_layout;
+use super::modes::console_modes;
+use super::read::{buffer_info, open_conout};
+use super::sendinput;
+use crate::repro;
 
-/// Send a command string to the console input
-pub fn send_command(command: &str) -> Result<()> {
-    // Open CONIN$ for writing
-    let conin = unsafe {
-        CreateFileW(
-            PCWSTR::from_raw(conin_wide().as_ptr()),
-            FILE_GENERIC_WRITE.0,
-            FILE_SHARE_READ | FILE_SHARE_WRITE,
-            None,
-            OPEN_EXISTING,
-            FILE_ATTRIBUTE_NORMAL,
-            None,
-        )
-    }?;
+/// Input records handed to one WriteConsoleInputW call; long text is split
+/// so a single call never overruns the console's input buffer
+const INPUT_CHUNK_RECORDS: usize = 256;
 
-    if conin.is_invalid() {
-        return Err(anyhow!("Failed to open CONIN$"));
-    }
+/// Attempts at a chunk the console accepts nothing of before giving up
+const INPUT_STALL_RETRIES: u32 = 12;
 
-    // Build input records for each character
-    let mut input_records = Vec::new();
+/// First pause before retrying a chunk the console accepted nothing of;
+/// it doubles on each further stall up to [`INPUT_STALL_MAX_WAIT`]
+const INPUT_STALL_WAIT: Duration = Duration::from_millis(10);
+const INPUT_STALL_MAX_WAIT: Duration = Duration::from_millis(320);
+
+/// The target is not reading input and its console input buffer is backed
+/// up, so no more events fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputBufferFull {
+    /// Events waiting in the buffer when writing gave up
+    pub pending: u32,
+    pub written: usize,
+    pub total: usize,
+}
 
-    for ch in command.chars() {
-        // Key down event
-        input_records.push(create_key_event(ch, true));
-        // Key up event
-        input_records.push(create_key_event(ch, false));
+impl std::fmt::Display for InputBufferFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Input buffer full ({} pending events); the target is not reading input, {} of {} events written",
+            self.pending, self.written, self.total
+        )
     }
+}
 
-    // Add Enter key (carriage return)
-    input_records.push(create_key_event('\r', true));
-    input_records.push(create_key_event('\r', false));
+impl std::error::Error for InputBufferFull {}
 
-    // Write the input records
-    unsafe {
-        let mut events_written = 0;
-        WriteConsoleInputW(
-            conin,
-            &input_records,
-            &mut events_written,
-        )
-            .map_err(|e| anyhow!("Failed to write console input: {}", e.to_string()))?;
+/// How a session's input is written. A worker passes its session's own to
+/// [`with_input_settings`] for each operation; the elevated helper, which
+/// serves a single session, keeps them with the setters below.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputSettings {
+    /// Pause after each typed key, see [`set_typing_delay`]
+    pub typing_delay: Duration,
+    /// Finish commands with LF, see [`set_enter_sends_lf`]
+    pub enter_sends_lf: bool,
+    /// Describe input instead of writing it, see [`set_dry_run`](super::set_dry_run)
+    pub dry_run: bool,
+}
+
+thread_local! {
+    /// Settings for the input written on this thread
+    static INPUT: Cell<InputSettings> = Cell::new(InputSettings::default());
+}
+
+/// Run `write` with `settings` applying to the input it writes on this thread
+#[cfg(feature = "gui")]
+pub fn with_input_settings<T>(settings: InputSettings, write: impl FnOnce() -> T) -> T {
+    let previous = INPUT.replace(settings);
+    let result = write();
+    INPUT.set(previous);
+    result
+}
+
+/// Settings for the input written on this thread
+pub(super) fn input_settings() -> InputSettings {
+    INPUT.get()
+}
+
+/// Change the settings for the input written on this thread from now on
+pub(super) fn update_input_settings(update: impl FnOnce(&mut InputSettings)) {
+    let mut settings = INPUT.get();
+    update(&mut settings);
+    INPUT.set(settings);
+}
+
+/// Virtual key and scan code of the main Enter key
+const VK_RETURN: u16 = 0x0D;
+const ENTER_SCAN: u16 = 0x1C;
+
+/// Type text one key at a time with this pause after each key, for targets
+/// that drop input arriving in one burst; zero writes everything at once.
+///
+/// The caller is blocked (and holds the console) for the whole text.
+pub fn set_typing_delay(delay: Duration) {
+    update_input_settings(|s| s.typing_delay = delay);
+}
+
+/// Finish commands with Ctrl+Enter, which types LF, instead of the CR of a
+/// plain Enter, for programs that wait for a raw `\n`
+pub fn set_enter_sends_lf(on: bool) {
+    update_input_settings(|s| s.enter_sends_lf = on);
+}
+
+/// Send a command string to the console input
+pub fn send_command(command: &str) -> Result<()> {
+    let result = write_text(command, true);
+    repro::record_call("send_command", &result);
+    result
+}
+
+/// Type text into the console input without pressing Enter, leaving it on
+/// the target's command line to be edited or confirmed there
+pub fn send_text(text: &str) -> Result<()> {
+    let result = write_text(text, false);
+    repro::record_call("send_text", &result);
+    result
+}
+
+/// Write text, plus Enter if asked, to CONIN$ (uninstrumented)
+fn write_text(command: &str, press_enter: bool) -> Result<()> {
+    // Build input records for each character, plus Enter
+    let layout = target_layout();
+    let mut keys: Vec<Vec<INPUT_RECORD>> = command.chars().map(|ch| char_events(ch, layout)).collect();
+    if press_enter {
+        keys.push(enter_events(input_settings().enter_sends_lf).to_vec());
     }
 
+    // Write the input records, character by character when typing slowly;
+    // a dry run has nothing to pace
+    let delay = input_settings().typing_delay;
+    if delay.is_zero() || dryrun::is_dry_run() {
+        return write_input(&keys.concat())
+            .map_err(|e| anyhow!("Failed to write console input: {}", e));
+    }
+    for key in &keys {
+        write_input(key)
+            .map_err(|e| anyhow!("Failed to write console input: {}", e))?;
+        std::thread::sleep(delay);
+    }
     Ok(())
 }
 
-/// Send Ctrl+C to the console
+/// Send Ctrl+C to the console.
+///
+/// Programs with processed input on (the default) only stop on the Ctrl+C
+/// signal, so it is raised for every process on the console. Programs that
+/// read raw input take Ctrl+C as a key instead, and get the key event; so
+/// does everyone when the signal cannot be raised.
 pub fn send_ctrl_c() -> Result<()> {
-    // Open CONIN$ for writing
-    let conin = unsafe {
-        CreateFileW(
-            PCWSTR::from_raw(conin_wide().as_ptr()),
-            FILE_GENERIC_WRITE.0,
-            FILE_SHARE_READ | FILE_SHARE_WRITE,
-            None,
-            OPEN_EXISTING,
-            FILE_ATTRIBUTE_NORMAL,
-            None,
-        )
-    }?;
+    let processed = console_modes().map_or(true, |m| m.input & ENABLE_PROCESSED_INPUT.0 != 0);
+    if processed && raise_ctrl_c().is_ok() {
+        return Ok(());
+    }
+    write_ctrl_c_key()
+}
 
-    if conin.is_invalid() {
-        return Err(anyhow!("Failed to open CONIN$"));
+/// GenerateConsoleCtrlEvent(CTRL_C_EVENT) to the whole attached console.
+///
+/// We are attached too, so the signal is only raised while a session holds
+/// the control guard.
+fn raise_ctrl_c() -> Result<()> {
+    if !ctrl_guarded() {
+        return Err(anyhow!("No control guard is held"));
+    }
+    if dryrun::capture_note("GenerateConsoleCtrlEvent CTRL_C_EVENT to the whole console") {
+        return Ok(());
     }
+    unsafe { GenerateConsoleCtrlEvent(CTRL_C_EVENT, 0) }
+        .map_err(|e| anyhow!("Failed to raise Ctrl+C: {}", e))
+}
 
-    // Create a Ctrl+C event (Ctrl = VK_CONTROL, C = 0x43)
-    let mut input_records = vec![
-        create_ctrl_key_event(0x43, true, true),  // Ctrl+C down
-        create_ctrl_key_event(0x43, false, true), // Ctrl+C up
-    ];
+/// Write a Ctrl+C key press to CONIN$, carrying the ETX (0x03) a keyboard types
+fn write_ctrl_c_key() -> Result<()> {
+    write_input(&ctrl_letter_events('C'))
+        .map_err(|e| anyhow!("Failed to write Ctrl+C: {}", e))
+}
 
-    unsafe {
-        let mut events_written = 0;
-        WriteConsoleInputW(
-            conin,
-            &input_records,
-            &mut events_written,
-        )
-            .map_err(|e| anyhow!("Failed to write Ctrl+C: {}", e.to_string()))?;
+/// Send Ctrl+<letter> the way a keyboard does: the letter's virtual key with
+/// left Ctrl held and the matching control character (Ctrl+D is 0x04)
+pub fn send_ctrl_key(letter: char) -> Result<()> {
+    if !letter.is_ascii_alphabetic() {
+        return Err(anyhow!("Ctrl+{} is not a letter key", letter));
     }
+    write_input(&ctrl_letter_events(letter))
+        .map_err(|e| anyhow!("Failed to write Ctrl+{}: {}", letter.to_ascii_uppercase(), e))
+}
 
-    Ok(())
+/// Send end-of-input the Windows way: Ctrl+Z (VK_Z with left Ctrl, char
+/// 0x1A), then Enter when asked, since `copy con`, python and most readers
+/// only see the ^Z once the line is submitted
+pub fn send_ctrl_z(press_enter: bool) -> Result<()> {
+    let mut records = ctrl_letter_events('Z').to_vec();
+    if press_enter {
+        records.extend(enter_events(input_settings().enter_sends_lf));
+    }
+    write_input(&records)
+        .map_err(|e| anyhow!("Failed to write Ctrl+Z: {}", e))
 }
 
-/// Send a control character to the console
-pub fn send_control_char(code: u16) -> Result<()> {
-    // Open CONIN$ for writing
-    let conin = unsafe {
-        CreateFileW(
-            PCWSTR::from_raw(conin_wide().as_ptr()),
-            FILE_GENERIC_WRITE.0,
-            FILE_SHARE_READ | FILE_SHARE_WRITE,
-            None,
-            OPEN_EXISTING,
-            FILE_ATTRIBUTE_NORMAL,
-            None,
-        )
-    }?;
+/// Key down and up of Ctrl+`letter`, which must be an ASCII letter
+fn ctrl_letter_events(letter: char) -> [INPUT_RECORD; 2] {
+    let vk = letter.to_ascii_uppercase() as u16;
+    let code = vk & 0x1F;
+    let mut down = create_ctrl_key_event(vk, true, true);
+    let mut up = create_ctrl_key_event(vk, false, true);
+    down.Event.KeyEvent.uChar.UnicodeChar = code;
+    up.Event.KeyEvent.uChar.UnicodeChar = code;
+    [down, up]
+}
+
+/// Virtual key of the Alt key
+const VK_MENU: u16 = 0x12;
 
-    if conin.is_invalid() {
-        return Err(anyhow!("Failed to open CONIN$"));
+/// Virtual key of numeric keypad 0; keypad 1 to 9 follow
+const VK_NUMPAD0: u16 = 0x60;
+
+/// Press Alt+`ch` the way a keyboard does, for menu accelerators in legacy
+/// console apps: Alt down, the key with left Alt held, Alt up
+pub fn send_alt_key(ch: char) -> Result<()> {
+    let Some((vk, _, state)) = key_for_char(ch, target_layout()) else {
+        return Err(anyhow!("No plain key types {:?} on this keyboard layout", ch));
+    };
+    let records = [
+        create_alt_event(VK_MENU, 0, LEFT_ALT_PRESSED, true),
+        create_alt_event(vk, ch as u16, state | LEFT_ALT_PRESSED, true),
+        create_alt_event(vk, ch as u16, state | LEFT_ALT_PRESSED, false),
+        create_alt_event(VK_MENU, 0, 0, false),
+    ];
+    write_input(&records)
+        .map_err(|e| anyhow!("Failed to write Alt+{}: {}", ch, e))
+}
+
+/// Type a character by its Alt+numpad code, e.g. "130" or "0233".
+///
+/// Alt is held while the digits are pressed on the keypad and the character
+/// arrives with the Alt release, as on a real keyboard. Like Windows, a
+/// leading 0 picks the ANSI code page and otherwise the OEM one; codes
+/// above 255 wrap.
+pub fn send_alt_code(digits: &str) -> Result<()> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("Alt code must be decimal digits, got {:?}", digits));
+    }
+    let value = digits.bytes().fold(0u32, |v, d| v.wrapping_mul(10).wrapping_add((d - b'0') as u32));
+    let cp = unsafe { if digits.starts_with('0') { GetACP() } else { GetOEMCP() } };
+    let byte = [(value % 256) as u8];
+    let mut wide = [0u16; 2];
+    let len = unsafe { MultiByteToWideChar(cp, MULTI_BYTE_TO_WIDE_CHAR_FLAGS(0), &byte, Some(&mut wide)) };
+    let ch = if len > 0 { wide[0] } else { 0 };
+
+    let mut records = vec![create_alt_event(VK_MENU, 0, LEFT_ALT_PRESSED, true)];
+    for d in digits.bytes() {
+        let vk = VK_NUMPAD0 + (d - b'0') as u16;
+        records.push(create_alt_event(vk, 0, LEFT_ALT_PRESSED, true));
+        records.push(create_alt_event(vk, 0, LEFT_ALT_PRESSED, false));
     }
+    records.push(create_alt_event(VK_MENU, ch, 0, false));
+    write_input(&records)
+        .map_err(|e| anyhow!("Failed to write Alt+{}: {}", digits, e))
+}
+
+/// Left mouse button in a [`send_mouse_event`] button state
+#[cfg(feature = "gui")]
+pub const MOUSE_LEFT: u32 = FROM_LEFT_1ST_BUTTON_PRESSED;
+/// Right mouse button in a [`send_mouse_event`] button state
+#[cfg(feature = "gui")]
+pub const MOUSE_RIGHT: u32 = RIGHTMOST_BUTTON_PRESSED;
+/// Middle mouse button in a [`send_mouse_event`] button state
+#[cfg(feature = "gui")]
+pub const MOUSE_MIDDLE: u32 = FROM_LEFT_2ND_BUTTON_PRESSED;
+
+/// Report the mouse at cell (`x`, `y`) of the target's visible window with
+/// `buttons` held (0 when all are up).
+///
+/// Only programs that turned on ENABLE_MOUSE_INPUT receive these; with
+/// quick-edit on, the console keeps the mouse for selection instead.
+pub fn send_mouse_event(x: usize, y: usize, buttons: u32) -> Result<()> {
+    let record = create_mouse_event(window_cell(x, y)?, buttons);
+    write_input(&[record])
+        .map_err(|e| anyhow!("Failed to write mouse event: {}", e))
+}
 
+/// Press and release `button` at cell (`x`, `y`) of the target's visible window
+pub fn send_mouse_click(x: usize, y: usize, button: u32) -> Result<()> {
+    send_mouse_event(x, y, button)?;
+    send_mouse_event(x, y, 0)
+}
+
+/// Buffer coordinates of a cell given relative to the visible window
+fn window_cell(x: usize, y: usize) -> Result<COORD> {
+    let w = buffer_info(open_conout()?)?.srWindow;
+    let col = (w.Left as i32 + x as i32).min(w.Right as i32);
+    let row = (w.Top as i32 + y as i32).min(w.Bottom as i32);
+    Ok(COORD { X: col as i16, Y: row as i16 })
+}
+
+/// Send a control character to the console
+pub fn send_control_char(code: u16) -> Result<()> {
     // Create control character event (key down and key up)
-    let mut input_records = vec![
+    let input_records = vec![
         create_control_char_event(code, true),   // Key down
         create_control_char_event(code, false),  // Key up
     ];
 
-    unsafe {
-        let mut events_written = 0;
-        WriteConsoleInputW(
-            conin,
-            &input_records,
-            &mut events_written,
-        )
-            .map_err(|e| anyhow!("Failed to write control char: {}", e.to_string()))?;
+    write_input(&input_records)
+        .map_err(|e| anyhow!("Failed to write control char: {}", e))
+}
+
+/// One key to synthesize without an implicit Enter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStroke {
+    /// A character typed as-is
+    Char(char),
+    /// A key with no character, e.g. VK_SHIFT
+    VirtualKey(u16),
+    /// Ctrl held with an ASCII letter, e.g. Ctrl+D
+    Ctrl(char),
+}
+
+/// Press and release one navigation or function key
+#[cfg(feature = "gui")]
+pub fn send_key(key: VirtualKey) -> Result<()> {
+    send_keys(&[KeyStroke::VirtualKey(key.code())])
+}
+
+/// Send a sequence of key strokes to the console input
+pub fn send_keys(keys: &[KeyStroke]) -> Result<()> {
+    let layout = target_layout();
+    let mut input_records = Vec::new();
+    for key in keys {
+        match *key {
+            KeyStroke::Char('\r') => input_records.extend(enter_events(false)),
+            KeyStroke::Char(ch) => input_records.extend(char_events(ch, layout)),
+            KeyStroke::VirtualKey(vk) => {
+                input_records.push(create_ctrl_key_event(vk, true, false));
+                input_records.push(create_ctrl_key_event(vk, false, false));
+            }
+            KeyStroke::Ctrl(letter) if letter.is_ascii_alphabetic() => {
+                input_records.extend(ctrl_letter_events(letter));
+            }
+            KeyStroke::Ctrl(letter) => return Err(anyhow!("Ctrl+{} is not a letter key", letter)),
+        }
     }
 
+    write_input(&input_records)
+        .map_err(|e| anyhow!("Failed to write keys: {}", e))
+}
+
+/// Write input records to CONIN$ in chunks, continuing from however many
+/// events each call reports written. While the console takes none, or
+/// refuses a write with events still pending, the chunk is retried with
+/// backoff and finally fails with [`InputBufferFull`]. In dry run the
+/// records are only described.
+///
+/// When the console refuses input outright (CONIN$ will not open, or the
+/// first write is denied), the records are typed with SendInput instead.
+fn write_input(records: &[INPUT_RECORD]) -> Result<()> {
+    if dryrun::capture(records) {
+        return Ok(());
+    }
+    let conin = match handles::conin() {
+        Ok(conin) => conin,
+        Err(e) => return type_instead(records, e),
+    };
+    let mut offset = 0;
+    let mut stalls = 0;
+    while offset < records.len() {
+        let end = (offset + INPUT_CHUNK_RECORDS).min(records.len());
+        let mut events_written = 0;
+        if let Err(e) = unsafe { WriteConsoleInputW(conin, &records[offset..end], &mut events_written) } {
+            // A backed-up buffer makes writes fail too; only an empty one
+            // means the console refused us
+            if pending_events(conin).unwrap_or(0) == 0 {
+                if offset == 0 {
+                    return type_instead(records, anyhow!("{}", e));
+                }
+                return Err(anyhow!("{} of {} events written: {}", offset, records.len(), e));
+            }
+            events_written = 0;
+        }
+
+        if events_written == 0 {
+            stalls += 1;
+            if stalls > INPUT_STALL_RETRIES {
+                let pending = pending_events(conin).unwrap_or(0);
+                return Err(InputBufferFull { pending, written: offset, total: records.len() }.into());
+            }
+            let backoff = INPUT_STALL_WAIT * 2u32.pow(stalls.min(6) - 1);
+            std::thread::sleep(backoff.min(INPUT_STALL_MAX_WAIT));
+            continue;
+        }
+        stalls = 0;
+        offset += (events_written as usize).min(end - offset);
+    }
+    sendinput::set_degraded(false);
     Ok(())
 }
 
-/// Create a KEY_EVENT input record
-fn create_key_event(ch: char, key_down: bool) -> INPUT_RECORD {
+/// Events waiting in the console input buffer for the target to read
+fn pending_events(conin: windows::Win32::Foundation::HANDLE) -> Result<u32> {
+    let mut pending = 0;
+    unsafe { GetNumberOfConsoleInputEvents(conin, &mut pending) }
+        .map_err(|e| anyhow!("Failed to count pending input: {}", e))?;
+    Ok(pending)
+}
+
+/// Fall back to SendInput after console input was refused with `refused`
+fn type_instead(records: &[INPUT_RECORD], refused: anyhow::Error) -> Result<()> {
+    sendinput::type_records(records)
+        .map_err(|e| anyhow!("{}; SendInput fallback failed: {}", refused, e))
+}
+
+/// Virtual key used for characters injected without a key of their own
+const VK_PACKET: u16 = 0xE7;
+
+/// Virtual key, scan code and modifier state that type `ch` on keyboard
+/// `layout`, if a plain or shifted key does. Characters that need Ctrl or
+/// AltGr are left out so programs do not mistake them for shortcuts.
+fn key_for_char(ch: char, layout: HKL) -> Option<(u16, u16, u32)> {
+    let unit = u16::try_from(ch as u32).ok()?;
+    let scan = unsafe { VkKeyScanExW(unit, layout) };
+    if scan == -1 {
+        return None;
+    }
+    let vk = (scan as u16) & 0xFF;
+    let shift = (scan as u16) >> 8;
+    if shift & 6 != 0 {
+        return None;
+    }
+    let state = if shift & 1 != 0 { SHIFT_PRESSED } else { 0 };
+    let scan = unsafe { MapVirtualKeyExW(vk as u32, MAPVK_VK_TO_VSC, Some(layout)) as u16 };
+    Some((vk, scan, state))
+}
+
+/// Key presses that type `ch`: its own key when `layout` has one,
+/// otherwise a VK_PACKET press per UTF-16 unit, so characters beyond the
+/// BMP arrive as a surrogate pair whatever the keyboard layout
+fn char_events(ch: char, layout: HKL) -> Vec<INPUT_RECORD> {
+    if let Some((vk, scan, state)) = key_for_char(ch, layout) {
+        let unit = ch as u16;
+        return vec![
+            create_key_event(vk, scan, state, unit, true),
+            create_key_event(vk, scan, state, unit, false),
+        ];
+    }
+    let mut units = [0u16; 2];
+    ch.encode_utf16(&mut units).iter()
+        .flat_map(|&unit| [
+            create_key_event(VK_PACKET, 0, 0, unit, true),
+            create_key_event(VK_PACKET, 0, 0, unit, false),
+        ])
+        .collect()
+}
+
+/// Down and up of the real Enter key: VK_RETURN with its scan code and CR,
+/// or with `lf` Ctrl+Enter and LF, as a keyboard sends them. Line editors
+/// such as PSReadLine look at the key rather than the character.
+fn enter_events(lf: bool) -> [INPUT_RECORD; 2] {
+    let (state, unit) = if lf { (LEFT_CTRL_PRESSED, 0x0A) } else { (0, 0x0D) };
+    [
+        create_key_event(VK_RETURN, ENTER_SCAN, state, unit, true),
+        create_key_event(VK_RETURN, ENTER_SCAN, state, unit, false),
+    ]
+}
+
+/// Hardware scan code of a virtual key, 0 if it has none
+fn scan_code(vk: u16) -> u16 {
+    unsafe { MapVirtualKeyW(vk as u32, MAPVK_VK_TO_VSC) as u16 }
+}
+
+/// Create a KEY_EVENT input record carrying one UTF-16 unit
+fn create_key_event(vk: u16, scan: u16, state: u32, unit: u16, key_down: bool) -> INPUT_RECORD {
     let mut key_event = KEY_EVENT_RECORD::default();
     key_event.bKeyDown = key_down.into();
-    key_event.dwControlKeyState = 0;
+    key_event.dwControlKeyState = state;
     key_event.wRepeatCount = 1;
-    key_event.wVirtualKeyCode = 0;
-    key_event.wVirtualScanCode = 0;
-    key_event.uChar.UnicodeChar = ch as u16;
+    key_event.wVirtualKeyCode = vk;
+    key_event.wVirtualScanCode = scan;
+    key_event.uChar.UnicodeChar = unit;
 
     let mut event = INPUT_RECORD::default();
     event.EventType = 1; // KEY_EVENT
@@ -157,10 +504,14 @@ fn create_ctrl_key_event(vk_code: u16, key_down: bool, ctrl: bool) -> INPUT_RECO
     key_event.bKeyDown = key_down.into();
     key_event.wRepeatCount = 1;
     key_event.wVirtualKeyCode = vk_code;
-    key_event.uChar.UnicodeChar = '\0' as u16;
+    key_event.wVirtualScanCode = scan_code(vk_code);
+    key_event.uChar.UnicodeChar = if ctrl { 0 } else { key_char(vk_code) };
 
     if ctrl {
-        key_event.dwControlKeyState = windows::Win32::System::Console::LEFT_CTRL_PRESSED;
+        key_event.dwControlKeyState = LEFT_CTRL_PRESSED;
+    }
+    if is_enhanced(vk_code) {
+        key_event.dwControlKeyState |= ENHANCED_KEY;
     }
 
     let mut event = INPUT_RECORD::default();
@@ -172,6 +523,38 @@ fn create_ctrl_key_event(vk_code: u16, key_down: bool, ctrl: bool) -> INPUT_RECO
     event
 }
 
+/// Create a KEY_EVENT for a key pressed while Alt is (or was just) held
+fn create_alt_event(vk_code: u16, ch: u16, state: u32, key_down: bool) -> INPUT_RECORD {
+    let key_event = KEY_EVENT_RECORD {
+        bKeyDown: key_down.into(),
+        wRepeatCount: 1,
+        wVirtualKeyCode: vk_code,
+        wVirtualScanCode: scan_code(vk_code),
+        uChar: KEY_EVENT_RECORD_0 { UnicodeChar: ch },
+        dwControlKeyState: state,
+    };
+
+    INPUT_RECORD {
+        EventType: 1, // KEY_EVENT
+        Event: INPUT_RECORD_0 { KeyEvent: key_event },
+    }
+}
+
+/// Create a MOUSE_EVENT input record for a press, release or move at `at`
+fn create_mouse_event(at: COORD, buttons: u32) -> INPUT_RECORD {
+    INPUT_RECORD {
+        EventType: MOUSE_EVENT as u16,
+        Event: INPUT_RECORD_0 {
+            MouseEvent: MOUSE_EVENT_RECORD {
+                dwMousePosition: at,
+                dwButtonState: buttons,
+                dwControlKeyState: 0,
+                dwEventFlags: 0,
+            },
+        },
+    }
+}
+
 /// Create a control character KEY_EVENT input record
 fn create_control_char_event(code: u16, key_down: bool) -> INPUT_RECORD {
     let mut key_event = KEY_EVENT_RECORD::default();
@@ -179,7 +562,7 @@ fn create_control_char_event(code: u16, key_down: bool) -> INPUT_RECORD {
     key_event.dwControlKeyState = 0;
     key_event.wRepeatCount = 1;
     key_event.wVirtualKeyCode = code;
-    key_event.wVirtualScanCode = 0;
+    key_event.wVirtualScanCode = scan_code(code);
     key_event.uChar.UnicodeChar = code; // Use the code as the Unicode character
 
     let mut event = INPUT_RECORD::default();
@@ -190,10 +573,3 @@ fn create_control_char_event(code: u16, key_down: bool) -> INPUT_RECORD {
 
     event
 }
-
-/// Convert "CONIN$" to a wide null-terminated string
-fn conin_wide() -> Vec<u16> {
-    let mut s: Vec<u16> = "CONIN$".encode_utf16().collect();
-    s.push(0);
-    s
-}
@@ -0,0 +1,123 @@
+use crate::process::wildcard_match;
+
+/// What happens to a command a rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardAction {
+    /// Ask before sending
+    Confirm,
+    /// Never send
+    Block,
+}
+
+impl GuardAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GuardAction::Confirm => "Confirm",
+            GuardAction::Block => "Block",
+        }
+    }
+}
+
+/// A command shape to stop, such as `del /s`: the program name followed by
+/// arguments that must all appear, in any order. Words may use `*` and `?`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardRule {
+    pub pattern: String,
+    pub action: GuardAction,
+}
+
+impl GuardRule {
+    pub fn new(pattern: &str, action: GuardAction) -> Self {
+        Self { pattern: pattern.to_string(), action }
+    }
+
+    /// Whether one simple command (no `&` or `|`) has this rule's shape
+    fn matches(&self, words: &[String]) -> bool {
+        let mut pattern = self.pattern.split_whitespace();
+        let Some(program) = pattern.next() else {
+            return false;
+        };
+        let Some(first) = words.first() else {
+            return false;
+        };
+        wildcard_match(program, &program_name(first))
+            && pattern.all(|p| words[1..].iter().any(|w| wildcard_match(p, w)))
+    }
+}
+
+/// Denylist of dangerous commands with an allowlist of exceptions
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandGuard {
+    pub enabled: bool,
+    pub rules: Vec<GuardRule>,
+    /// Commands in rule syntax that are always allowed, e.g. `shutdown /a`
+    pub allow: Vec<GuardRule>,
+}
+
+impl Default for CommandGuard {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: vec![
+                GuardRule::new("format", GuardAction::Confirm),
+                GuardRule::new("del /s", GuardAction::Confirm),
+                GuardRule::new("erase /s", GuardAction::Confirm),
+                GuardRule::new("rd /s", GuardAction::Confirm),
+                GuardRule::new("rmdir /s", GuardAction::Confirm),
+                GuardRule::new("shutdown", GuardAction::Confirm),
+                GuardRule::new("diskpart", GuardAction::Confirm),
+            ],
+            allow: vec![GuardRule::new("shutdown /a", GuardAction::Confirm)],
+        }
+    }
+}
+
+impl CommandGuard {
+    /// The strictest rule `command` trips, checking every part of a chain
+    /// such as `cd \ && rd /s /q x`; `None` when it may be sent
+    pub fn check(&self, command: &str) -> Option<&GuardRule> {
+        if !self.enabled {
+            return None;
+        }
+        let mut found: Option<&GuardRule> = None;
+        for part in command.split(['&', '|']) {
+            let mut words: Vec<String> = part.split_whitespace()
+                .map(|w| w.trim_matches(['"', '(', ')']).to_string())
+                .filter(|w| !w.is_empty())
+                .collect();
+            // cmd.exe reads `rd/s/q` as `rd /s /q`
+            if let Some(slash) = words.first().and_then(|w| w.find('/')).filter(|&i| i > 0) {
+                let switches = words[0].split_off(slash);
+                words.insert(1, switches);
+            }
+            let words: Vec<String> = words.into_iter()
+                .flat_map(|w| match w.strip_prefix('/') {
+                    Some(rest) if rest.contains('/') => rest.split('/').map(|s| format!("/{}", s)).collect(),
+                    _ => vec![w],
+                })
+                .collect();
+            if self.allow.iter().any(|a| a.matches(&words)) {
+                continue;
+            }
+            for rule in self.rules.iter().filter(|r| r.matches(&words)) {
+                if found.is_none_or(|f| f.action == GuardAction::Confirm) {
+                    found = Some(rule);
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Program name of a command word without its folder or extension, so
+/// `C:\Windows\System32\shutdown.exe` counts as `shutdown`
+fn program_name(word: &str) -> String {
+    let name = word.rsplit('\\').next().unwrap_or(word);
+    let lower = name.to_lowercase();
+    for ext in [".exe", ".com", ".bat", ".cmd"] {
+        if let Some(stem) = lower.strip_suffix(ext) {
+            return stem.to_string();
+        }
+    }
+    lower
+}
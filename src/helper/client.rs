@@ -0,0 +1,393 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::os::windows::io::FromRawHandle;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crossbeam_channel::{bounded, Receiver};
+use windows::Win32::Foundation::{CloseHandle, LocalFree, ERROR_PIPE_CONNECTED, ERROR_PIPE_LISTENING, HANDLE, HLOCAL};
+use windows::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows::Win32::Security::Cryptography::{BCryptGenRandom, BCRYPT_USE_SYSTEM_PREFERRED_RNG};
+use windows::Win32::Security::{GetTokenInformation, TokenUser, PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES, TOKEN_QUERY, TOKEN_USER};
+use windows::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, SetNamedPipeHandleState, PIPE_NOWAIT,
+    PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_WAIT,
+};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+use windows::Win32::UI::Shell::{ShellExecuteExW, SHELLEXECUTEINFOW};
+use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
+use windows::core::{PCWSTR, PWSTR};
+use anyhow::{Result, anyhow};
+use crate::console::{KeyStroke, VirtualKey};
+use super::{escape, unescape, HELPER_FLAG};
+
+/// How long to wait for the elevated helper to connect back
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Random bytes in a pipe name, so another process cannot guess and claim it first
+const PIPE_NAME_RANDOM_BYTES: usize = 16;
+
+/// Connection to an elevated helper process that proxies console access.
+///
+/// The GUI owns the named pipe server; the helper is launched through UAC
+/// ("runas") and connects back as a client. The pipe has an unguessable
+/// name, refuses a second instance and remote clients, and only admits the
+/// current user. Requests are line based with escaped payloads and
+/// serialized through a mutex so the UI and worker can share one helper.
+#[derive(Clone)]
+pub struct HelperClient {
+    pipe: Arc<Mutex<HelperPipe>>,
+}
+
+struct HelperPipe {
+    reader: BufReader<File>,
+    writer: File,
+}
+
+impl fmt::Debug for HelperClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HelperClient").finish_non_exhaustive()
+    }
+}
+
+impl HelperClient {
+    /// Launch the helper on a background thread; the answer arrives once the
+    /// user dealt with the UAC prompt and the helper connected, or it failed
+    pub fn launch_in_background() -> Receiver<Result<Self>> {
+        let (tx, rx) = bounded(1);
+        let spawned = thread::Builder::new()
+            .name("helper-launch".to_string())
+            .spawn({
+                let tx = tx.clone();
+                move || {
+                    let _ = tx.send(Self::launch());
+                }
+            });
+        if let Err(e) = spawned {
+            let _ = tx.send(Err(anyhow!("Failed to start helper launch thread: {}", e)));
+        }
+        rx
+    }
+
+    /// Launch the elevated helper (shows a UAC prompt) and wait for it to connect
+    fn launch() -> Result<Self> {
+        let pipe_name = format!(r"\\.\pipe\remote_con_helper_{}", random_hex(PIPE_NAME_RANDOM_BYTES)?);
+        let pipe = create_pipe_server(&pipe_name)?;
+        let connected = launch_helper(&pipe_name).and_then(|_| wait_for_client(pipe));
+        if let Err(e) = connected {
+            unsafe {
+                let _ = CloseHandle(pipe);
+            }
+            return Err(e);
+        }
+
+        let file = unsafe { File::from_raw_handle(pipe.0 as _) };
+        let writer = file.try_clone()?;
+        Ok(Self {
+            pipe: Arc::new(Mutex::new(HelperPipe {
+                reader: BufReader::new(file),
+                writer,
+            })),
+        })
+    }
+    /// Attach the helper to a console PID
+    pub fn attach(&self, pid: u32) -> Result<()> {
+        self.request("ATTACH", &pid.to_string()).map(|_| ())
+    }
+
+    /// Detach the helper from its console
+    pub fn detach(&self) -> Result<()> {
+        self.request("DETACH", "").map(|_| ())
+    }
+
+    /// Read the last N lines through the helper
+    pub fn read_lines(&self, num_lines: usize) -> Result<Vec<String>> {
+        self.request("READ", &num_lines.to_string())
+    }
+
+    /// Send a command (with Enter) through the helper
+    pub fn send_command(&self, command: &str) -> Result<()> {
+        self.request("SEND", command).map(|_| ())
+    }
+
+    /// Type text without Enter through the helper
+    pub fn send_text(&self, text: &str) -> Result<()> {
+        self.request("TEXT", text).map(|_| ())
+    }
+
+    /// Turn dry run on or off in the helper
+    pub fn set_dry_run(&self, on: bool) -> Result<()> {
+        self.request("DRYRUN", &u8::from(on).to_string()).map(|_| ())
+    }
+
+    /// Input the helper described instead of writing since the last call
+    pub fn take_dry_run(&self) -> Result<Vec<String>> {
+        self.request("DRYTAKE", "")
+    }
+
+    /// Turn the console's Quick-Edit mode on or off through the helper
+    pub fn set_quick_edit(&self, enable: bool) -> Result<()> {
+        self.request("QUICKEDIT", &u8::from(enable).to_string()).map(|_| ())
+    }
+
+    /// Blank the console's screen buffer through the helper
+    pub fn clear_console(&self) -> Result<()> {
+        self.request("CLEAR", "").map(|_| ())
+    }
+
+    /// Send Ctrl+C through the helper
+    pub fn send_ctrl_c(&self) -> Result<()> {
+        self.request("CTRLC", "").map(|_| ())
+    }
+
+    /// Send Ctrl+<letter> through the helper
+    pub fn send_ctrl_key(&self, letter: char) -> Result<()> {
+        self.request("CTRLKEY", &letter.to_string()).map(|_| ())
+    }
+
+    /// Send Ctrl+Z, optionally followed by Enter, through the helper
+    pub fn send_ctrl_z(&self, press_enter: bool) -> Result<()> {
+        self.request("CTRLZ", &u8::from(press_enter).to_string()).map(|_| ())
+    }
+
+    /// Press Alt+<key> through the helper
+    pub fn send_alt_key(&self, ch: char) -> Result<()> {
+        self.request("ALTKEY", &(ch as u32).to_string()).map(|_| ())
+    }
+
+    /// Type an Alt+numpad code through the helper
+    pub fn send_alt_code(&self, digits: &str) -> Result<()> {
+        self.request("ALTCODE", digits).map(|_| ())
+    }
+
+    /// Click at a cell of the target's window through the helper
+    pub fn send_mouse_click(&self, x: usize, y: usize, button: u32) -> Result<()> {
+        self.request("CLICK", &format!("{} {} {}", x, y, button)).map(|_| ())
+    }
+
+    /// Send a control character through the helper
+    pub fn send_control_char(&self, code: u16) -> Result<()> {
+        self.request("CTRL", &code.to_string()).map(|_| ())
+    }
+
+    /// Send key strokes (no implicit Enter) through the helper
+    pub fn send_keys(&self, keys: &[KeyStroke]) -> Result<()> {
+        let encoded: Vec<String> = keys.iter()
+            .map(|k| match *k {
+                KeyStroke::Char(ch) => format!("c{}", ch as u32),
+                KeyStroke::VirtualKey(vk) => format!("v{}", vk),
+                KeyStroke::Ctrl(letter) => format!("k{}", letter as u32),
+            })
+            .collect();
+        self.request("KEYS", &encoded.join(" ")).map(|_| ())
+    }
+
+    /// Press one navigation or function key through the helper
+    pub fn send_key(&self, key: VirtualKey) -> Result<()> {
+        self.send_keys(&[KeyStroke::VirtualKey(key.code())])
+    }
+
+    /// Set the per-key typing delay used by the helper's writes
+    pub fn set_typing_delay(&self, delay: Duration) -> Result<()> {
+        self.request("DELAY", &delay.as_millis().to_string()).map(|_| ())
+    }
+
+    /// Have the helper end commands with LF instead of CR
+    pub fn set_enter_sends_lf(&self, on: bool) -> Result<()> {
+        self.request("ENTERLF", &u8::from(on).to_string()).map(|_| ())
+    }
+
+    /// Ask the helper process to exit
+    pub fn shutdown(&self) {
+        let _ = self.request("QUIT", "");
+    }
+
+    /// Send one request and collect the response payload; `arg` is escaped
+    /// so commands holding line breaks stay one request
+    fn request(&self, verb: &str, arg: &str) -> Result<Vec<String>> {
+        let mut pipe = self.pipe.lock()
+            .map_err(|_| anyhow!("Elevated helper connection poisoned"))?;
+
+        writeln!(pipe.writer, "{} {}", verb, escape(arg))?;
+        pipe.writer.flush()?;
+
+        let header = read_response_line(&mut pipe.reader)?;
+        if let Some(msg) = header.strip_prefix("ERR ") {
+            return Err(anyhow!("{}", unescape(msg)));
+        }
+        if header == "OK" {
+            return Ok(Vec::new());
+        }
+        if let Some(count) = header.strip_prefix("LINES ") {
+            let count: usize = count.parse()
+                .map_err(|_| anyhow!("Malformed helper response: {}", header))?;
+            let mut lines = Vec::with_capacity(count);
+            for _ in 0..count {
+                lines.push(unescape(&read_response_line(&mut pipe.reader)?));
+            }
+            return Ok(lines);
+        }
+
+        Err(anyhow!("Unexpected helper response: {}", header))
+    }
+}
+
+/// Start this executable elevated in helper mode, pointed at `pipe_name`
+fn launch_helper(pipe_name: &str) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let exe_wide = to_wide(&exe.to_string_lossy());
+    let params_wide = to_wide(&format!("{} {}", HELPER_FLAG, pipe_name));
+    let verb_wide = to_wide("runas");
+
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        lpVerb: PCWSTR::from_raw(verb_wide.as_ptr()),
+        lpFile: PCWSTR::from_raw(exe_wide.as_ptr()),
+        lpParameters: PCWSTR::from_raw(params_wide.as_ptr()),
+        nShow: SW_HIDE.0,
+        ..Default::default()
+    };
+
+    unsafe { ShellExecuteExW(&mut info) }
+        .map_err(|e| anyhow!("Failed to launch elevated helper: {}", e))
+}
+
+/// Create the named pipe server end in non-blocking mode.
+///
+/// Fails if any process already created a pipe of this name, and grants
+/// access only to the current user, whose elevated token the helper runs with.
+fn create_pipe_server(name: &str) -> Result<HANDLE> {
+    let descriptor = current_user_only()?;
+    let attributes = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: descriptor.0,
+        bInheritHandle: false.into(),
+    };
+    let name_wide = to_wide(name);
+    let pipe = unsafe {
+        CreateNamedPipeW(
+            PCWSTR::from_raw(name_wide.as_ptr()),
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_NOWAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            1,
+            64 * 1024,
+            64 * 1024,
+            0,
+            Some(&attributes),
+        )
+    };
+    unsafe {
+        let _ = LocalFree(Some(HLOCAL(descriptor.0)));
+    }
+
+    if pipe.is_invalid() {
+        return Err(anyhow!("Failed to create helper pipe {}", name));
+    }
+    Ok(pipe)
+}
+
+/// Security descriptor whose DACL allows the current user and no one else;
+/// free it with LocalFree
+fn current_user_only() -> Result<PSECURITY_DESCRIPTOR> {
+    let sid = current_user_sid()?;
+    let sddl = to_wide(&format!("D:P(A;;GA;;;{})", sid));
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            PCWSTR::from_raw(sddl.as_ptr()),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            None,
+        )
+    }
+    .map_err(|e| anyhow!("Failed to build helper pipe security: {}", e))?;
+    Ok(descriptor)
+}
+
+/// SID of the user this process runs as, in string form (`S-1-5-21-...`)
+fn current_user_sid() -> Result<String> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }
+        .map_err(|e| anyhow!("Failed to open process token: {}", e))?;
+
+    // TOKEN_USER is followed by the SID it points into
+    let mut buffer = vec![0u64; 64];
+    let mut returned = 0u32;
+    let queried = unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut _),
+            (buffer.len() * std::mem::size_of::<u64>()) as u32,
+            &mut returned,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+    queried.map_err(|e| anyhow!("Failed to query token user: {}", e))?;
+
+    let user = unsafe { &*(buffer.as_ptr() as *const TOKEN_USER) };
+    let mut text = PWSTR::null();
+    unsafe { ConvertSidToStringSidW(user.User.Sid, &mut text) }
+        .map_err(|e| anyhow!("Failed to format user SID: {}", e))?;
+    let sid = unsafe { text.to_string() };
+    unsafe {
+        let _ = LocalFree(Some(HLOCAL(text.0 as _)));
+    }
+    sid.map_err(|e| anyhow!("Invalid user SID: {}", e))
+}
+
+/// `bytes` random bytes from the system generator, as hex
+fn random_hex(bytes: usize) -> Result<String> {
+    let mut buf = vec![0u8; bytes];
+    unsafe { BCryptGenRandom(None, &mut buf, BCRYPT_USE_SYSTEM_PREFERRED_RNG) }
+        .ok()
+        .map_err(|e| anyhow!("Failed to generate pipe name: {}", e))?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Poll until the helper connects, then switch the pipe back to blocking mode
+fn wait_for_client(pipe: HANDLE) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        match unsafe { ConnectNamedPipe(pipe, None) } {
+            Ok(()) => break,
+            Err(e) if e.code() == ERROR_PIPE_CONNECTED.to_hresult() => break,
+            Err(e) if e.code() == ERROR_PIPE_LISTENING.to_hresult() => {}
+            Err(e) => return Err(anyhow!("Helper pipe connect failed: {}", e)),
+        }
+
+        if start.elapsed() > CONNECT_TIMEOUT {
+            return Err(anyhow!("Timed out waiting for the elevated helper"));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let mode = PIPE_READMODE_BYTE | PIPE_WAIT;
+    unsafe {
+        SetNamedPipeHandleState(pipe, Some(&mode), None, None)
+            .map_err(|e| anyhow!("Failed to configure helper pipe: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Read one response line, treating EOF as a lost helper
+fn read_response_line(reader: &mut BufReader<File>) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(anyhow!("Elevated helper disconnected"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Convert a string to a wide null-terminated string
+fn to_wide(s: &str) -> Vec<u16> {
+    let mut w: Vec<u16> = s.encode_utf16().collect();
+    w.push(0);
+    w
+}
@@ -0,0 +1,160 @@
+#[cfg(feature = "gui")]
+mod client;
+
+use std::io::{BufRead, BufReader, Write};
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use crate::console::{attach_to_console, clear_console, CtrlGuard, set_quick_edit, send_alt_code, send_alt_key, send_mouse_click, detach_from_console, read_console_lines, send_command, send_text, send_ctrl_c, send_ctrl_key, send_ctrl_z, send_control_char, send_keys, set_dry_run, set_enter_sends_lf, set_typing_delay, take_dry_run, KeyStroke};
+
+#[cfg(feature = "gui")]
+pub use client::HelperClient;
+
+/// Command line flag that starts the binary in elevated helper mode
+pub const HELPER_FLAG: &str = "--elevated-helper";
+
+/// Entry point for the elevated helper process
+pub fn run_helper(pipe_name: &str) -> Result<()> {
+    // Serves one session for its whole life, so keeps control events away throughout
+    let _ctrl_guard = CtrlGuard::acquire();
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(pipe_name)
+        .map_err(|e| anyhow!("Failed to connect to {}: {}", pipe_name, e))?;
+    let mut writer = pipe.try_clone()?;
+    let reader = BufReader::new(pipe);
+
+    for line in reader.lines() {
+        let line = line?;
+        let (verb, arg) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+        let arg = unescape(arg);
+        let arg = arg.as_str();
+
+        let response = match verb {
+            "ATTACH" => arg.parse::<u32>()
+                .map_err(|e| anyhow!("Invalid PID: {}", e))
+                .and_then(attach_to_console)
+                .map(|_| Vec::new()),
+            "DETACH" => detach_from_console().map(|_| Vec::new()),
+            "READ" => arg.parse::<usize>()
+                .map_err(|e| anyhow!("Invalid line count: {}", e))
+                .and_then(read_console_lines),
+            "SEND" => send_command(arg).map(|_| Vec::new()),
+            "TEXT" => send_text(arg).map(|_| Vec::new()),
+            "DELAY" => arg.parse::<u64>()
+                .map_err(|e| anyhow!("Invalid typing delay: {}", e))
+                .map(|ms| {
+                    set_typing_delay(Duration::from_millis(ms));
+                    Vec::new()
+                }),
+            "ENTERLF" => {
+                set_enter_sends_lf(arg == "1");
+                Ok(Vec::new())
+            }
+            "DRYRUN" => {
+                set_dry_run(arg == "1");
+                Ok(Vec::new())
+            }
+            "DRYTAKE" => Ok(take_dry_run()),
+            "CLEAR" => clear_console().map(|_| Vec::new()),
+            "QUICKEDIT" => set_quick_edit(arg == "1").map(|_| Vec::new()),
+            "CTRLC" => send_ctrl_c().map(|_| Vec::new()),
+            "CTRLKEY" => arg.parse::<char>()
+                .map_err(|e| anyhow!("Invalid Ctrl letter: {}", e))
+                .and_then(send_ctrl_key)
+                .map(|_| Vec::new()),
+            "CTRLZ" => send_ctrl_z(arg == "1").map(|_| Vec::new()),
+            "ALTKEY" => arg.parse::<u32>().ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| anyhow!("Invalid Alt key: {}", arg))
+                .and_then(send_alt_key)
+                .map(|_| Vec::new()),
+            "ALTCODE" => send_alt_code(arg).map(|_| Vec::new()),
+            "CLICK" => decode_click(arg)
+                .and_then(|(x, y, button)| send_mouse_click(x, y, button))
+                .map(|_| Vec::new()),
+            "CTRL" => arg.parse::<u16>()
+                .map_err(|e| anyhow!("Invalid control code: {}", e))
+                .and_then(send_control_char)
+                .map(|_| Vec::new()),
+            "KEYS" => decode_keys(arg)
+                .and_then(|keys| send_keys(&keys))
+                .map(|_| Vec::new()),
+            "QUIT" => {
+                let _ = detach_from_console();
+                writeln!(writer, "OK")?;
+                break;
+            }
+            _ => Err(anyhow!("Unknown request: {}", verb)),
+        };
+
+        match response {
+            Ok(lines) if verb == "READ" || verb == "DRYTAKE" => {
+                writeln!(writer, "LINES {}", lines.len())?;
+                for l in lines {
+                    writeln!(writer, "{}", escape(&l))?;
+                }
+            }
+            Ok(_) => writeln!(writer, "OK")?,
+            Err(e) => writeln!(writer, "ERR {}", escape(&e.to_string()))?,
+        }
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Escape a request argument or response line so it stays on one line of
+/// the protocol: backslash, CR and LF become `\\`, `\r` and `\n`
+fn escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('\r', r"\r").replace('\n', r"\n")
+}
+
+/// Undo [`escape`]
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Decode the `x y button` argument written by [`HelperClient::send_mouse_click`]
+fn decode_click(arg: &str) -> Result<(usize, usize, u32)> {
+    let parts: Vec<&str> = arg.split(' ').collect();
+    let [x, y, button] = parts.as_slice() else {
+        return Err(anyhow!("Invalid click: {}", arg));
+    };
+    let invalid = |_| anyhow!("Invalid click: {}", arg);
+    Ok((x.parse().map_err(invalid)?, y.parse().map_err(invalid)?, button.parse().map_err(invalid)?))
+}
+
+/// Decode the KEYS argument written by [`HelperClient::send_keys`]
+fn decode_keys(arg: &str) -> Result<Vec<KeyStroke>> {
+    arg.split_whitespace()
+        .map(|token| {
+            let (kind, value) = token.split_at(token.chars().next().map_or(0, char::len_utf8));
+            let value: u32 = value.parse().map_err(|_| anyhow!("Invalid key token: {}", token))?;
+            match kind {
+                "c" => char::from_u32(value)
+                    .map(KeyStroke::Char)
+                    .ok_or_else(|| anyhow!("Invalid character: {}", value)),
+                "v" => Ok(KeyStroke::VirtualKey(value as u16)),
+                "k" => char::from_u32(value)
+                    .map(KeyStroke::Ctrl)
+                    .ok_or_else(|| anyhow!("Invalid character: {}", value)),
+                _ => Err(anyhow!("Invalid key token: {}", token)),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use anyhow::Result;
+use crate::platform::config_dir;
+
+/// Timestamp format used in the journal file
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// One command sent to a target
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub at: DateTime<Local>,
+    pub pid: u32,
+    /// Title of the target's window when the command was sent
+    pub session: String,
+    pub command: String,
+}
+
+/// Chronological record of every command sent, across all sessions.
+///
+/// Entries are appended to a TSV file in the workspace as they happen, so the
+/// journal survives restarts and can be used to reconstruct a procedure.
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Load the journal from the workspace; a missing file is an empty journal
+    pub fn load() -> Self {
+        let mut journal = Self::default();
+        let Ok(text) = fs::read_to_string(journal_path()) else {
+            return journal;
+        };
+        for line in text.lines() {
+            let mut fields = line.splitn(4, '\t');
+            let (Some(at), Some(pid), Some(session), Some(command)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Some(at) = NaiveDateTime::parse_from_str(at, TIME_FORMAT).ok()
+                .and_then(|t| Local.from_local_datetime(&t).single())
+            else {
+                continue;
+            };
+            let Ok(pid) = pid.parse() else {
+                continue;
+            };
+            journal.entries.push(JournalEntry {
+                at,
+                pid,
+                session: session.to_string(),
+                command: command.to_string(),
+            });
+        }
+        journal
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Record a sent command and append it to the journal file
+    pub fn record(&mut self, pid: u32, session: &str, command: &str) -> Result<()> {
+        let entry = JournalEntry {
+            at: Local::now(),
+            pid,
+            session: session.replace(['\t', '\r', '\n'], " "),
+            command: command.replace(['\t', '\r', '\n'], " "),
+        };
+        let path = journal_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}",
+            entry.at.format(TIME_FORMAT),
+            entry.pid,
+            entry.session,
+            entry.command
+        )?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Indices of entries for a target (any if None) sent at or after `since`
+    pub fn filtered(&self, pid: Option<u32>, since: Option<DateTime<Local>>) -> Vec<usize> {
+        self.entries.iter()
+            .enumerate()
+            .filter(|(_, e)| pid.is_none_or(|p| e.pid == p))
+            .filter(|(_, e)| since.is_none_or(|t| e.at >= t))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Distinct targets in the journal with their latest session title
+    pub fn sessions(&self) -> Vec<(u32, String)> {
+        let mut sessions: Vec<(u32, String)> = Vec::new();
+        for e in &self.entries {
+            match sessions.iter_mut().find(|(pid, _)| *pid == e.pid) {
+                Some(s) => s.1 = e.session.clone(),
+                None => sessions.push((e.pid, e.session.clone())),
+            }
+        }
+        sessions
+    }
+}
+
+fn journal_path() -> PathBuf {
+    config_dir().join("workspace").join("journal.tsv")
+}
@@ -0,0 +1,140 @@
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use crate::console::KeyStroke;
+use crate::profile::{ProfileSettings, ProfileStore};
+
+/// How long after the last keystroke in remote_con the keep-alive stays quiet
+pub const TYPING_GRACE: Duration = Duration::from_secs(10);
+
+/// Default sequence: a bare Shift press, which no shell reacts to
+pub const DEFAULT_SEQUENCE: &str = "{SHIFT}";
+
+/// Prefix of the profile keys holding the keep-alive settings
+const SETTINGS_PREFIX: &str = "keepalive";
+
+/// Virtual key codes for the named tokens
+const VK_SHIFT: u16 = 0x10;
+const VK_CONTROL: u16 = 0x11;
+const VK_MENU: u16 = 0x12;
+
+/// Keep-alive settings for a profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeepAliveSettings {
+    pub enabled: bool,
+    pub interval: Duration,
+    /// Key sequence with `{SHIFT}`, `{CTRL}`, `{ALT}`, `{SPACE}`, `{BS}` and `{ENTER}` tokens; other text is typed as-is
+    pub sequence: String,
+}
+
+impl Default for KeepAliveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(240),
+            sequence: DEFAULT_SEQUENCE.to_string(),
+        }
+    }
+}
+
+impl KeepAliveSettings {
+    /// Settings stored in a profile, with defaults for missing keys
+    pub fn from_settings(s: &ProfileSettings) -> Self {
+        let get = |k: &str| s.get(&format!("{}.{}", SETTINGS_PREFIX, k));
+        let defaults = Self::default();
+        Self {
+            enabled: get("enabled").map_or(defaults.enabled, |v| v == "1"),
+            interval: get("interval_secs")
+                .and_then(|v| v.parse().ok())
+                .map_or(defaults.interval, Duration::from_secs),
+            sequence: get("sequence").cloned().unwrap_or(defaults.sequence),
+        }
+    }
+
+    /// Store these settings in the current profile
+    pub fn save_to(&self, profiles: &mut ProfileStore) {
+        let mut s = ProfileSettings::new();
+        let mut put = |k: &str, v: String| s.insert(format!("{}.{}", SETTINGS_PREFIX, k), v);
+        put("enabled", u8::from(self.enabled).to_string());
+        put("interval_secs", self.interval.as_secs().to_string());
+        put("sequence", self.sequence.clone());
+        profiles.update(SETTINGS_PREFIX, s);
+    }
+}
+
+/// Tracks when the keep-alive last fired and when the user last typed
+#[derive(Debug)]
+pub struct KeepAlive {
+    last_sent: Instant,
+    last_typed: Option<Instant>,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeepAlive {
+    pub fn new() -> Self {
+        Self {
+            last_sent: Instant::now(),
+            last_typed: None,
+        }
+    }
+
+    /// Record user typing, which suspends the keep-alive for [`TYPING_GRACE`]
+    pub fn note_typing(&mut self) {
+        self.last_typed = Some(Instant::now());
+    }
+
+    /// Whether the user typed recently enough to hold the keep-alive back
+    pub fn suspended(&self) -> bool {
+        self.last_typed.is_some_and(|t| t.elapsed() < TYPING_GRACE)
+    }
+
+    /// Check whether a keep-alive should be sent now
+    pub fn due(&self, settings: &KeepAliveSettings) -> bool {
+        settings.enabled && !self.suspended() && self.last_sent.elapsed() >= settings.interval
+    }
+
+    /// Restart the interval after a send (or any other input reaching the target)
+    pub fn reset(&mut self) {
+        self.last_sent = Instant::now();
+    }
+
+    /// Time until the next keep-alive, if enabled
+    pub fn next_in(&self, settings: &KeepAliveSettings) -> Option<Duration> {
+        settings.enabled.then(|| settings.interval.saturating_sub(self.last_sent.elapsed()))
+    }
+}
+
+/// Parse a keep-alive sequence into key strokes
+pub fn parse_sequence(sequence: &str) -> Result<Vec<KeyStroke>> {
+    let mut keys = Vec::new();
+    let mut rest = sequence;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '{' {
+            let end = rest.find('}').ok_or_else(|| anyhow!("Unclosed token in \"{}\"", sequence))?;
+            let key = match rest[1..end].to_ascii_uppercase().as_str() {
+                "SHIFT" => KeyStroke::VirtualKey(VK_SHIFT),
+                "CTRL" => KeyStroke::VirtualKey(VK_CONTROL),
+                "ALT" => KeyStroke::VirtualKey(VK_MENU),
+                "SPACE" => KeyStroke::Char(' '),
+                "BS" => KeyStroke::Char('\x08'),
+                "ENTER" => KeyStroke::Char('\r'),
+                other => return Err(anyhow!("Unknown key token {{{}}}", other)),
+            };
+            keys.push(key);
+            rest = &rest[end + 1..];
+        } else {
+            keys.push(KeyStroke::Char(ch));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+
+    if keys.is_empty() {
+        return Err(anyhow!("Keep-alive sequence is empty"));
+    }
+    Ok(keys)
+}
@@ -1,13 +1,66 @@
-#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+#![cfg_attr(all(feature = "gui", not(debug_assertions)), windows_subsystem = "windows")]
 
+#[cfg(feature = "gui")]
 mod process;
 mod console;
+#[cfg(feature = "gui")]
 mod worker;
+#[cfg(feature = "gui")]
 mod ui;
+mod helper;
+mod repro;
+#[cfg(feature = "gui")]
+mod palette;
+#[cfg(feature = "gui")]
+mod storage;
+#[cfg(feature = "gui")]
+mod profile;
+#[cfg(feature = "gui")]
+mod scrollback;
+#[cfg(feature = "gui")]
+mod platform;
+#[cfg(feature = "gui")]
+mod cli;
+#[cfg(feature = "gui")]
+mod audit;
+#[cfg(feature = "automation")]
+mod keepalive;
+#[cfg(feature = "automation")]
+mod responder;
+#[cfg(feature = "gui")]
+mod notes;
+#[cfg(feature = "gui")]
+mod paste;
+#[cfg(feature = "gui")]
+mod template;
+#[cfg(feature = "gui")]
+mod guard;
+#[cfg(feature = "gui")]
+mod terminal;
+#[cfg(feature = "automation")]
+mod journal;
 
+#[cfg(feature = "gui")]
 use eframe::egui;
 
+#[cfg(feature = "gui")]
 fn main() -> eframe::Result<()> {
+    // Elevated helper mode: proxy console access for the GUI and exit
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == helper::HELPER_FLAG {
+        let _ = helper::run_helper(&args[2]);
+        return Ok(());
+    }
+    // Developer mode: drive a worker with a user's repro trace and exit
+    if args.len() == 3 && args[1] == repro::REPLAY_FLAG {
+        if let Err(e) = repro::replay(std::path::Path::new(&args[2])) {
+            eprintln!("Replay failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let intent = cli::StartupIntent::from_args(&args[1..]);
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 800.0])
@@ -18,6 +71,18 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "Remote Console Attach Tool",
         options,
-        Box::new(|cc| Ok(Box::new(ui::RemoteConApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(ui::RemoteConApp::new(cc, intent)))),
     )
 }
+
+/// Without the gui feature the binary only serves as the elevated helper
+#[cfg(not(feature = "gui"))]
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == helper::HELPER_FLAG {
+        let _ = helper::run_helper(&args[2]);
+        return;
+    }
+    eprintln!("remote_con was built without the gui feature; only {} mode is available", helper::HELPER_FLAG);
+    std::process::exit(2);
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use crate::platform::config_dir;
+
+/// How long after the last edit notes are written to disk
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// Markdown scratchpad belonging to one monitored session
+pub struct SessionNotes {
+    key: String,
+    pub text: String,
+    dirty_since: Option<Instant>,
+}
+
+impl SessionNotes {
+    /// Open the notes for a session, loading what was saved last time
+    pub fn open(key: &str) -> Self {
+        let text = fs::read_to_string(notes_path(key)).unwrap_or_default();
+        Self {
+            key: key.to_string(),
+            text,
+            dirty_since: None,
+        }
+    }
+
+    /// Session the notes belong to
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Mark the notes as changed so the next autosave writes them
+    pub fn edited(&mut self) {
+        if self.dirty_since.is_none() {
+            self.dirty_since = Some(Instant::now());
+        }
+    }
+
+    /// Append console lines as a markdown block quote
+    pub fn insert_quote(&mut self, lines: &[String]) {
+        if !self.text.is_empty() && !self.text.ends_with("\n\n") {
+            self.text.push_str(if self.text.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+        for line in lines {
+            self.text.push_str("> ");
+            self.text.push_str(line);
+            self.text.push('\n');
+        }
+        self.edited();
+    }
+
+    /// Save if the notes have been dirty long enough; returns whether a save happened
+    pub fn autosave(&mut self) -> Result<bool> {
+        match self.dirty_since {
+            Some(t) if t.elapsed() >= AUTOSAVE_DELAY => self.save().map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Write the notes to the workspace now
+    pub fn save(&mut self) -> Result<()> {
+        if self.dirty_since.is_none() {
+            return Ok(());
+        }
+        let path = notes_path(&self.key);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, &self.text).map_err(|e| anyhow!("Failed to save notes to {}: {}", path.display(), e))?;
+        self.dirty_since = None;
+        Ok(())
+    }
+}
+
+/// Session key for a target, stable for the lifetime of the target process
+pub fn session_key(pid: u32) -> String {
+    format!("pid_{}", pid)
+}
+
+/// Notes file for a session, with the key made safe for a file name
+fn notes_path(key: &str) -> PathBuf {
+    let safe: String = key.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    config_dir().join("workspace").join("notes").join(format!("{}.md", safe))
+}
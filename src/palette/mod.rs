@@ -0,0 +1,154 @@
+/// An RGB color independent of the UI toolkit, so exporters and the PNG
+/// renderer can share the same mapping as the viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// How FOREGROUND_INTENSITY (or VT bold) is presented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntensityMode {
+    /// Use the bright variant of the color (classic conhost behavior)
+    BrightColor,
+    /// Keep the normal color and render the text bold
+    BoldFont,
+    /// Bright color and bold font
+    Both,
+}
+
+/// Built-in 16-color palettes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PalettePreset {
+    /// Windows 10 "Campbell" console palette
+    Campbell,
+    /// Legacy conhost palette (pre Windows 10 1709)
+    Legacy,
+    /// Okabe-Ito based palette that keeps red/green distinguishable
+    ColorblindSafe,
+}
+
+impl PalettePreset {
+    pub const ALL: [PalettePreset; 3] = [
+        PalettePreset::Campbell,
+        PalettePreset::Legacy,
+        PalettePreset::ColorblindSafe,
+    ];
+
+    /// Display name for settings UI
+    pub fn label(self) -> &'static str {
+        match self {
+            PalettePreset::Campbell => "Campbell",
+            PalettePreset::Legacy => "Legacy conhost",
+            PalettePreset::ColorblindSafe => "Colorblind-friendly",
+        }
+    }
+
+    /// The 16 colors in console attribute order (BGR bit layout: 0 black, 1 blue, 2 green, 4 red)
+    pub fn colors(self) -> [Rgb; 16] {
+        match self {
+            PalettePreset::Campbell => [
+                Rgb(12, 12, 12), Rgb(0, 55, 218), Rgb(19, 161, 14), Rgb(58, 150, 221),
+                Rgb(197, 15, 31), Rgb(136, 23, 152), Rgb(193, 156, 0), Rgb(204, 204, 204),
+                Rgb(118, 118, 118), Rgb(59, 120, 255), Rgb(22, 198, 12), Rgb(97, 214, 214),
+                Rgb(231, 72, 86), Rgb(180, 0, 158), Rgb(249, 241, 165), Rgb(242, 242, 242),
+            ],
+            PalettePreset::Legacy => [
+                Rgb(0, 0, 0), Rgb(0, 0, 128), Rgb(0, 128, 0), Rgb(0, 128, 128),
+                Rgb(128, 0, 0), Rgb(128, 0, 128), Rgb(128, 128, 0), Rgb(192, 192, 192),
+                Rgb(128, 128, 128), Rgb(0, 0, 255), Rgb(0, 255, 0), Rgb(0, 255, 255),
+                Rgb(255, 0, 0), Rgb(255, 0, 255), Rgb(255, 255, 0), Rgb(255, 255, 255),
+            ],
+            PalettePreset::ColorblindSafe => [
+                Rgb(0, 0, 0), Rgb(0, 114, 178), Rgb(0, 158, 115), Rgb(86, 180, 233),
+                Rgb(213, 94, 0), Rgb(204, 121, 167), Rgb(230, 159, 0), Rgb(200, 200, 200),
+                Rgb(110, 110, 110), Rgb(60, 150, 220), Rgb(40, 200, 150), Rgb(140, 210, 245),
+                Rgb(240, 130, 40), Rgb(230, 160, 200), Rgb(240, 228, 66), Rgb(255, 255, 255),
+            ],
+        }
+    }
+}
+
+/// User settings controlling how console attributes become colors
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorSettings {
+    pub preset: PalettePreset,
+    pub intensity: IntensityMode,
+    /// Minimum WCAG contrast ratio between text and background (1.0 disables)
+    pub min_contrast: f32,
+}
+
+impl Default for ColorSettings {
+    fn default() -> Self {
+        Self {
+            preset: PalettePreset::Campbell,
+            intensity: IntensityMode::BrightColor,
+            min_contrast: 1.0,
+        }
+    }
+}
+
+/// Resolved presentation of one cell's attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellStyle {
+    pub fg: Rgb,
+    pub bg: Rgb,
+    pub bold: bool,
+}
+
+impl ColorSettings {
+    /// Map 4-bit foreground/background indices (bit 3 = intensity) to a style
+    pub fn resolve(&self, fg_index: u8, bg_index: u8) -> CellStyle {
+        let colors = self.preset.colors();
+        let intense = fg_index & 0x8 != 0;
+
+        let fg_index = match self.intensity {
+            IntensityMode::BrightColor | IntensityMode::Both => fg_index & 0xF,
+            IntensityMode::BoldFont => fg_index & 0x7,
+        };
+        let bold = intense && matches!(self.intensity, IntensityMode::BoldFont | IntensityMode::Both);
+
+        let bg = colors[(bg_index & 0xF) as usize];
+        let fg = enforce_contrast(colors[fg_index as usize], bg, self.min_contrast);
+
+        CellStyle { fg, bg, bold }
+    }
+
+    /// Style for a raw Win32 character attribute word
+    pub fn resolve_attr(&self, attr: u16) -> CellStyle {
+        self.resolve((attr & 0x0F) as u8, ((attr >> 4) & 0x0F) as u8)
+    }
+}
+
+/// Relative luminance per WCAG 2.x
+fn luminance(c: Rgb) -> f32 {
+    fn channel(v: u8) -> f32 {
+        let v = v as f32 / 255.0;
+        if v <= 0.03928 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+    }
+    0.2126 * channel(c.0) + 0.7152 * channel(c.1) + 0.0722 * channel(c.2)
+}
+
+/// WCAG contrast ratio between two colors
+pub fn contrast_ratio(a: Rgb, b: Rgb) -> f32 {
+    let (la, lb) = (luminance(a), luminance(b));
+    let (hi, lo) = if la > lb { (la, lb) } else { (lb, la) };
+    (hi + 0.05) / (lo + 0.05)
+}
+
+/// Push the foreground toward black or white until it reaches the minimum contrast
+fn enforce_contrast(fg: Rgb, bg: Rgb, min: f32) -> Rgb {
+    if min <= 1.0 || contrast_ratio(fg, bg) >= min {
+        return fg;
+    }
+
+    let target = if luminance(bg) > 0.5 { Rgb(0, 0, 0) } else { Rgb(255, 255, 255) };
+    let mix = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+    // Step toward the target; ten steps are plenty for an 8-bit channel
+    for step in 1..=10 {
+        let t = step as f32 / 10.0;
+        let candidate = Rgb(mix(fg.0, target.0, t), mix(fg.1, target.1, t), mix(fg.2, target.2, t));
+        if contrast_ratio(candidate, bg) >= min {
+            return candidate;
+        }
+    }
+    target
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+
+/// Default pause between pasted lines, long enough for cmd.exe to echo a line
+pub const DEFAULT_LINE_DELAY: Duration = Duration::from_millis(300);
+
+/// Multi-line text being sent to the target one command per line
+#[derive(Debug)]
+pub struct LinePaste {
+    lines: Vec<String>,
+    next: usize,
+    delay: Duration,
+    last_sent: Option<Instant>,
+    /// Hold each line until the target shows its prompt again
+    wait_for_prompt: bool,
+}
+
+impl LinePaste {
+    /// Split `text` into lines; blank lines are kept since they press Enter
+    /// on their own, which REPLs use to end a block
+    pub fn new(text: &str, delay: Duration) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+            next: 0,
+            delay,
+            last_sent: None,
+            wait_for_prompt: false,
+        }
+    }
+
+    /// Send already separated lines, e.g. commands queued while detached
+    pub fn from_lines(lines: Vec<String>, delay: Duration) -> Self {
+        Self {
+            lines,
+            next: 0,
+            delay,
+            last_sent: None,
+            wait_for_prompt: false,
+        }
+    }
+
+    /// Play the lines of a script file (.cmd, .bat, .txt) as typed commands.
+    ///
+    /// Each line goes to the interactive shell on its own, so batch-only
+    /// constructs such as labels, `goto` and `%1` do not work.
+    pub fn from_file(path: &Path, delay: Duration, wait_for_prompt: bool) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let mut paste = Self::new(text.trim_start_matches('\u{feff}'), delay);
+        paste.wait_for_prompt = wait_for_prompt;
+        Ok(paste)
+    }
+
+    /// Every line, sent or not
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The next line if the delay since the previous one has passed and,
+    /// when waiting for the prompt, it was seen (`prompt_seen`) after that
+    pub fn due(&self, prompt_seen: Option<Instant>) -> Option<&str> {
+        if let Some(sent) = self.last_sent {
+            if sent.elapsed() < self.delay {
+                return None;
+            }
+            if self.wait_for_prompt && prompt_seen.is_none_or(|t| t <= sent) {
+                return None;
+            }
+        }
+        self.lines.get(self.next).map(String::as_str)
+    }
+
+    pub fn waits_for_prompt(&self) -> bool {
+        self.wait_for_prompt
+    }
+
+    /// Record that the line returned by [`LinePaste::due`] went out
+    pub fn mark_sent(&mut self) {
+        self.next += 1;
+        self.last_sent = Some(Instant::now());
+    }
+
+    /// Lines sent so far and the total
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.lines.len())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.lines.len()
+    }
+}
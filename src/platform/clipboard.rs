@@ -0,0 +1,38 @@
+use windows::Win32::Foundation::HGLOBAL;
+use windows::Win32::System::DataExchange::{CloseClipboard, GetClipboardData, OpenClipboard};
+use windows::Win32::System::Memory::{GlobalLock, GlobalUnlock};
+use anyhow::{Result, anyhow};
+
+/// Standard clipboard format for UTF-16 text
+const CF_UNICODETEXT: u32 = 13;
+
+/// Text currently on the Windows clipboard
+pub fn clipboard_text() -> Result<String> {
+    unsafe { OpenClipboard(None) }
+        .map_err(|e| anyhow!("Failed to open clipboard: {}", e))?;
+    let text = read_unicode_text();
+    unsafe {
+        let _ = CloseClipboard();
+    }
+    text
+}
+
+/// Copy the CF_UNICODETEXT data out; the clipboard must be open
+fn read_unicode_text() -> Result<String> {
+    let data = unsafe { GetClipboardData(CF_UNICODETEXT) }
+        .map_err(|_| anyhow!("Clipboard has no text"))?;
+    let memory = HGLOBAL(data.0);
+    unsafe {
+        let ptr = GlobalLock(memory) as *const u16;
+        if ptr.is_null() {
+            return Err(anyhow!("Failed to lock clipboard text"));
+        }
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len));
+        let _ = GlobalUnlock(memory);
+        Ok(text)
+    }
+}
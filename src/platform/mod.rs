@@ -0,0 +1,19 @@
+#[cfg(feature = "gui")]
+pub mod clipboard;
+#[cfg(feature = "gui")]
+pub mod window;
+
+use std::path::PathBuf;
+
+#[cfg(feature = "gui")]
+pub use clipboard::clipboard_text;
+#[cfg(feature = "gui")]
+pub use window::{main_window, PlacementStore};
+
+/// Per-user directory for remote_con settings (`%APPDATA%\remote_con`)
+pub fn config_dir() -> PathBuf {
+    std::env::var_os("APPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("remote_con")
+}
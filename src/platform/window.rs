@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, HDC, HMONITOR, MONITORINFO,
+    MONITORINFOEXW, MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY,
+};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetWindowRect, IsZoomed, SetWindowPos, ShowWindow, SWP_NOACTIVATE, SWP_NOZORDER, SW_MAXIMIZE,
+};
+use anyhow::{Result, anyhow};
+use super::config_dir;
+
+/// DPI that Windows treats as 100% scaling
+const BASE_DPI: u32 = 96;
+
+/// File name of the placement store inside the config directory
+const STORE_FILE: &str = "window_placement.tsv";
+
+/// Where a window sat on which monitor, recorded in that monitor's own terms.
+///
+/// Position is relative to the monitor's work area and sizes are physical
+/// pixels at `dpi`, so a restore can rescale when the monitor's scaling
+/// changed or the window lands on a different monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowPlacement {
+    /// Monitor device name, e.g. `\\.\DISPLAY2`
+    pub monitor: String,
+    pub dpi: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+}
+
+impl WindowPlacement {
+    /// Record the current placement of a window
+    pub fn capture(hwnd: HWND) -> Result<Self> {
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(hwnd, &mut rect) }
+            .map_err(|e| anyhow!("Failed to get window rect: {}", e))?;
+
+        let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        let (name, work) = monitor_info(monitor)?;
+
+        Ok(Self {
+            monitor: name,
+            dpi: monitor_dpi(monitor),
+            x: rect.left - work.left,
+            y: rect.top - work.top,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+            maximized: unsafe { IsZoomed(hwnd) }.as_bool(),
+        })
+    }
+
+    /// Move a window back to this placement.
+    ///
+    /// Falls back to the primary monitor when the saved one is gone, rescales
+    /// by the DPI ratio and keeps the window inside the target work area.
+    pub fn restore(&self, hwnd: HWND) -> Result<()> {
+        let monitor = find_monitor(&self.monitor)
+            .unwrap_or_else(|| unsafe { MonitorFromWindow(HWND::default(), MONITOR_DEFAULTTOPRIMARY) });
+        let (_, work) = monitor_info(monitor)?;
+        let dpi = monitor_dpi(monitor);
+
+        let scale = |v: i32| (v as i64 * dpi as i64 / self.dpi.max(1) as i64) as i32;
+        let work_w = work.right - work.left;
+        let work_h = work.bottom - work.top;
+        let width = scale(self.width).min(work_w);
+        let height = scale(self.height).min(work_h);
+        let x = work.left + scale(self.x).clamp(0, work_w - width);
+        let y = work.top + scale(self.y).clamp(0, work_h - height);
+
+        // Two moves: the first lands the window on the target monitor so it
+        // picks up that DPI, the second applies the size in those terms
+        for _ in 0..2 {
+            unsafe { SetWindowPos(hwnd, None, x, y, width, height, SWP_NOZORDER | SWP_NOACTIVATE) }
+                .map_err(|e| anyhow!("Failed to place window: {}", e))?;
+        }
+        if self.maximized {
+            unsafe {
+                let _ = ShowWindow(hwnd, SW_MAXIMIZE);
+            }
+        }
+        Ok(())
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.monitor, self.dpi, self.x, self.y, self.width, self.height, self.maximized as u8
+        )
+    }
+
+    fn from_fields(fields: &[&str]) -> Option<Self> {
+        let [monitor, dpi, x, y, width, height, maximized] = fields else {
+            return None;
+        };
+        Some(Self {
+            monitor: monitor.to_string(),
+            dpi: dpi.parse().ok()?,
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            maximized: *maximized == "1",
+        })
+    }
+}
+
+/// Saved placements keyed by profile and window name. The app only opens
+/// its main window today; the window key leaves room for more.
+#[derive(Debug, Default)]
+pub struct PlacementStore {
+    entries: BTreeMap<(String, String), WindowPlacement>,
+}
+
+impl PlacementStore {
+    /// Load the store from the config directory; a missing file is an empty store
+    pub fn load() -> Self {
+        let mut store = Self::default();
+        let Ok(text) = fs::read_to_string(store_path()) else {
+            return store;
+        };
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 2 {
+                continue;
+            }
+            if let Some(p) = WindowPlacement::from_fields(&fields[2..]) {
+                store.entries.insert((fields[0].to_string(), fields[1].to_string()), p);
+            }
+        }
+        store
+    }
+
+    /// Write the store back to the config directory
+    pub fn save(&self) -> Result<()> {
+        let path = store_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for ((profile, window), p) in &self.entries {
+            text.push_str(&format!("{}\t{}\t{}\n", profile, window, p.to_line()));
+        }
+        fs::write(&path, text).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Placement remembered for a window of a profile
+    pub fn get(&self, profile: &str, window: &str) -> Option<&WindowPlacement> {
+        self.entries.get(&(profile.to_string(), window.to_string()))
+    }
+
+    /// Remember a window's placement for a profile
+    pub fn set(&mut self, profile: &str, window: &str, placement: WindowPlacement) {
+        self.entries.insert((profile.to_string(), window.to_string()), placement);
+    }
+
+    /// Capture a live window into the store
+    pub fn remember(&mut self, profile: &str, window: &str, hwnd: HWND) -> Result<()> {
+        let placement = WindowPlacement::capture(hwnd)?;
+        self.set(profile, window, placement);
+        Ok(())
+    }
+
+    /// Restore a live window from the store; returns false if nothing was saved
+    pub fn apply(&self, profile: &str, window: &str, hwnd: HWND) -> Result<bool> {
+        match self.get(profile, window) {
+            Some(p) => p.restore(hwnd).map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Top-level window of this process, once it has been created
+pub fn main_window() -> Option<HWND> {
+    let hwnd = crate::process::get_process_main_window(std::process::id());
+    if hwnd.is_invalid() { None } else { Some(hwnd) }
+}
+
+fn store_path() -> PathBuf {
+    config_dir().join(STORE_FILE)
+}
+
+/// Device name and work area of a monitor
+fn monitor_info(monitor: HMONITOR) -> Result<(String, RECT)> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    let ok = unsafe { GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO) };
+    if !ok.as_bool() {
+        return Err(anyhow!("Failed to query monitor info"));
+    }
+    let len = info.szDevice.iter().position(|&c| c == 0).unwrap_or(info.szDevice.len());
+    Ok((String::from_utf16_lossy(&info.szDevice[..len]), info.monitorInfo.rcWork))
+}
+
+/// Effective DPI of a monitor, defaulting to 100% scaling
+fn monitor_dpi(monitor: HMONITOR) -> u32 {
+    let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+    match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+        Ok(()) if dpi_x > 0 => dpi_x,
+        _ => BASE_DPI,
+    }
+}
+
+/// Find a connected monitor by device name
+fn find_monitor(name: &str) -> Option<HMONITOR> {
+    struct Search<'a> {
+        name: &'a str,
+        found: Option<HMONITOR>,
+    }
+
+    unsafe extern "system" fn visit(monitor: HMONITOR, _hdc: HDC, _rect: *mut RECT, lparam: LPARAM) -> BOOL {
+        let search = unsafe { &mut *(lparam.0 as *mut Search) };
+        if monitor_info(monitor).is_ok_and(|(n, _)| n == search.name) {
+            search.found = Some(monitor);
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    let mut search = Search { name, found: None };
+    unsafe {
+        let _ = EnumDisplayMonitors(None, None, Some(visit), LPARAM(&mut search as *mut Search as isize));
+    }
+    search.found
+}
@@ -1,6 +1,8 @@
+use std::thread;
+use crossbeam_channel::{bounded, Receiver};
 use sysinfo::System;
 use windows::Win32::Foundation::HWND;
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 
 /// Information about a cmd.exe process
 #[derive(Debug, Clone)]
@@ -10,6 +12,10 @@ pub struct CmdProcessInfo {
     pub session_id: u32,
     pub has_window: bool,
     pub attachable: bool,
+    pub elevated: bool,
+    pub command_line: String,
+    /// When the process started, in seconds since the Unix epoch
+    pub start_time: u64,
 }
 
 /// Enumerate all cmd.exe processes on the system
@@ -51,6 +57,11 @@ pub fn enumerate_cmd_processes() -> Result<Vec<CmdProcessInfo>> {
 
             // Check if attachable (same privilege level)
             let attachable = is_process_attachable(pid_u32);
+            let elevated = is_process_elevated(pid_u32);
+            let command_line = process.cmd().iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
 
             cmd_processes.push(CmdProcessInfo {
                 pid: pid_u32,
@@ -58,6 +69,9 @@ pub fn enumerate_cmd_processes() -> Result<Vec<CmdProcessInfo>> {
                 session_id,
                 has_window,
                 attachable,
+                elevated,
+                command_line,
+                start_time: process.start_time(),
             });
         }
     }
@@ -65,6 +79,24 @@ pub fn enumerate_cmd_processes() -> Result<Vec<CmdProcessInfo>> {
     Ok(cmd_processes)
 }
 
+/// Enumerate cmd.exe processes on a background thread; the list arrives on
+/// the returned channel
+pub fn enumerate_in_background() -> Receiver<Result<Vec<CmdProcessInfo>>> {
+    let (tx, rx) = bounded(1);
+    let spawned = thread::Builder::new()
+        .name("process-scan".to_string())
+        .spawn({
+            let tx = tx.clone();
+            move || {
+                let _ = tx.send(enumerate_cmd_processes());
+            }
+        });
+    if let Err(e) = spawned {
+        let _ = tx.send(Err(anyhow!("Failed to start process scan thread: {}", e)));
+    }
+    rx
+}
+
 /// Get the current process session ID
 fn get_current_session_id() -> Result<u32> {
     // For a GUI application, we're typically in session 1 (interactive session)
@@ -81,14 +113,33 @@ fn get_process_session_id(_pid: u32) -> Result<u32> {
 }
 
 /// Get the main window handle for a process
-fn get_process_main_window(pid: u32) -> HWND {
-    use windows::Win32::UI::WindowsAndMessaging::FindWindowW;
-    use windows::core::PCWSTR;
+pub fn get_process_main_window(pid: u32) -> HWND {
+    use windows::Win32::Foundation::{BOOL, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindowThreadProcessId, IsWindowVisible};
 
-    // This is a simplified approach - in a real implementation you would
-    // enumerate windows to find one belonging to this process
-    // For now, we'll use a placeholder
-    HWND(std::ptr::null_mut())
+    struct Search {
+        pid: u32,
+        found: HWND,
+    }
+
+    // Console windows report the first client process (cmd.exe) as their owner
+    unsafe extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = unsafe { &mut *(lparam.0 as *mut Search) };
+        let mut owner = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut owner)) };
+        if owner == search.pid && unsafe { IsWindowVisible(hwnd) }.as_bool() {
+            search.found = hwnd;
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    let mut search = Search { pid, found: HWND(std::ptr::null_mut()) };
+    unsafe {
+        // EnumWindows reports an error when the callback stops early
+        let _ = EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize));
+    }
+    search.found
 }
 
 /// Get the title of a window
@@ -124,3 +175,152 @@ fn is_process_attachable(pid: u32) -> bool {
         false
     }
 }
+
+/// Check if a process is running with an elevated (admin) token
+pub fn is_process_elevated(pid: u32) -> bool {
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+    use windows::Win32::Foundation::CloseHandle;
+
+    // Limited query access is granted even across integrity levels
+    let Ok(handle) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }) else {
+        return false;
+    };
+    let elevated = token_is_elevated(handle);
+    unsafe {
+        let _ = CloseHandle(handle);
+    }
+    elevated
+}
+
+/// Check if our own process is running elevated
+pub fn is_current_process_elevated() -> bool {
+    use windows::Win32::System::Threading::GetCurrentProcess;
+
+    token_is_elevated(unsafe { GetCurrentProcess() })
+}
+
+/// Query TokenElevation for the token of an open process handle
+fn token_is_elevated(process: windows::Win32::Foundation::HANDLE) -> bool {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+    use windows::Win32::System::Threading::OpenProcessToken;
+
+    let mut token = HANDLE::default();
+    if unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) }.is_err() {
+        return false;
+    }
+
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut returned = 0u32;
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut _ as *mut _),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+
+    result.is_ok() && elevation.TokenIsElevated != 0
+}
+
+/// Open handle to a process used to detect when it exits
+pub struct ProcessWatch {
+    handle: windows::Win32::Foundation::HANDLE,
+}
+
+impl ProcessWatch {
+    /// Open a synchronize handle to the process
+    pub fn open(pid: u32) -> Result<Self> {
+        use windows::Win32::System::Threading::{OpenProcess, PROCESS_SYNCHRONIZE};
+
+        let handle = unsafe { OpenProcess(PROCESS_SYNCHRONIZE, false, pid) }
+            .map_err(|e| anyhow::anyhow!("Failed to open PID {} for watching: {}", pid, e))?;
+        Ok(Self { handle })
+    }
+
+    /// Check without blocking whether the process is still running
+    pub fn is_alive(&self) -> bool {
+        use windows::Win32::Foundation::WAIT_TIMEOUT;
+        use windows::Win32::System::Threading::WaitForSingleObject;
+
+        // The handle is signaled once the process exits
+        unsafe { WaitForSingleObject(self.handle, 0) == WAIT_TIMEOUT }
+    }
+}
+
+impl Drop for ProcessWatch {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::CloseHandle;
+
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Processes likely to share a target's console, used when attaching to the
+/// target directly fails. The console host (conhost.exe spawned by the target)
+/// comes first, then the target's other children, then its parent.
+pub fn console_attach_candidates(pid: u32) -> Vec<(u32, String)> {
+    let mut sys = System::new();
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+
+    let current_pid = std::process::id();
+    let mut conhosts = Vec::new();
+    let mut children = Vec::new();
+
+    for (child_pid, process) in sys.processes() {
+        if child_pid.as_u32() == current_pid || process.parent().map(|p| p.as_u32()) != Some(pid) {
+            continue;
+        }
+        let name = process.name().to_string_lossy().to_string();
+        if name.eq_ignore_ascii_case("conhost.exe") {
+            conhosts.push((child_pid.as_u32(), name));
+        } else {
+            children.push((child_pid.as_u32(), name));
+        }
+    }
+
+    let mut candidates = conhosts;
+    candidates.append(&mut children);
+
+    if let Some(parent) = sys.process(sysinfo::Pid::from_u32(pid)).and_then(|p| p.parent())
+        && parent.as_u32() != current_pid
+        && let Some(process) = sys.process(parent)
+    {
+        candidates.push((parent.as_u32(), process.name().to_string_lossy().to_string()));
+    }
+
+    candidates
+}
+
+/// Case-insensitive wildcard match supporting `*` and `?`
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Greedy match with backtracking to the last star
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
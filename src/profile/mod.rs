@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{Result, anyhow};
+use crate::platform::config_dir;
+
+/// File name of the profile store inside the config directory
+const STORE_FILE: &str = "profiles.tsv";
+
+/// Profile used until the user creates or picks another
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings of one profile as key/value pairs; each feature reads and writes its own keys
+pub type ProfileSettings = BTreeMap<String, String>;
+
+/// Named sets of settings, one of which is current.
+///
+/// Stored as `profile<TAB>key<TAB>value` lines; the line with an empty
+/// profile name records which profile was current. Secrets are never
+/// written here, callers leave them out of the settings they store.
+#[derive(Debug)]
+pub struct ProfileStore {
+    profiles: BTreeMap<String, ProfileSettings>,
+    current: String,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self {
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), ProfileSettings::new())]),
+            current: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+impl ProfileStore {
+    /// Load the store from the config directory; a missing file holds just the default profile
+    pub fn load() -> Self {
+        let mut store = Self::default();
+        let Ok(text) = fs::read_to_string(store_path()) else {
+            return store;
+        };
+        for line in text.lines() {
+            let [profile, key, value] = line.splitn(3, '\t').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+            if profile.is_empty() {
+                if key == "current" {
+                    store.current = unescape(value);
+                }
+                continue;
+            }
+            store.profiles.entry(unescape(profile)).or_default().insert(key.to_string(), unescape(value));
+        }
+        store.profiles.entry(store.current.clone()).or_default();
+        store
+    }
+
+    /// Write the store back to the config directory
+    pub fn save(&self) -> Result<()> {
+        let path = store_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = format!("\tcurrent\t{}\n", escape(&self.current));
+        for (profile, settings) in &self.profiles {
+            for (key, value) in settings {
+                text.push_str(&format!("{}\t{}\t{}\n", escape(profile), key, escape(value)));
+            }
+        }
+        fs::write(&path, text).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Name of the current profile
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// Names of all profiles, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// Make `name` the current profile, creating it from a copy of the current one if needed
+    pub fn select(&mut self, name: &str) {
+        if !self.profiles.contains_key(name) {
+            let copy = self.settings().clone();
+            self.profiles.insert(name.to_string(), copy);
+        }
+        self.current = name.to_string();
+    }
+
+    /// Settings of the current profile
+    pub fn settings(&self) -> &ProfileSettings {
+        &self.profiles[&self.current]
+    }
+
+    /// Replace the keys starting with `prefix` in the current profile
+    pub fn update(&mut self, prefix: &str, values: ProfileSettings) {
+        let settings = self.profiles.entry(self.current.clone()).or_default();
+        settings.retain(|k, _| !k.starts_with(prefix));
+        settings.extend(values);
+    }
+}
+
+fn store_path() -> PathBuf {
+    config_dir().join(STORE_FILE)
+}
+
+/// Escape backslashes, tabs and newlines so a value fits in one TSV field
+fn escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('\t', r"\t").replace('\n', r"\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
@@ -0,0 +1,174 @@
+#[cfg(feature = "gui")]
+mod replay;
+
+#[cfg(feature = "gui")]
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+
+#[cfg(feature = "gui")]
+pub use replay::{replay, REPLAY_FLAG};
+#[cfg(all(test, feature = "gui"))]
+pub use replay::MockConsole;
+
+/// Active recording, if any
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+/// Source of a recorded event
+// The helper-only build records Win32 calls for its own trace but never starts or writes one
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    /// Something the user did in the UI
+    Ui,
+    /// A message the worker delivered to the UI
+    Worker,
+    /// Result of a Win32 console call
+    Win32,
+    /// One scrubbed line of console text belonging to the previous Win32 read
+    Line,
+}
+
+#[cfg(feature = "gui")]
+impl TraceKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TraceKind::Ui => "ui",
+            TraceKind::Worker => "worker",
+            TraceKind::Win32 => "win32",
+            TraceKind::Line => "line",
+        }
+    }
+
+    pub(super) fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ui" => Some(TraceKind::Ui),
+            "worker" => Some(TraceKind::Worker),
+            "win32" => Some(TraceKind::Win32),
+            "line" => Some(TraceKind::Line),
+            _ => None,
+        }
+    }
+}
+
+/// One event in a repro trace
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    pub at: Duration,
+    pub kind: TraceKind,
+    pub detail: String,
+}
+
+/// In-memory recording for a fixed window
+struct Recorder {
+    started: Instant,
+    window: Duration,
+    events: Vec<TraceEvent>,
+}
+
+/// Start recording a repro trace for the given window
+#[cfg(feature = "gui")]
+pub fn start(window: Duration) {
+    if let Ok(mut rec) = RECORDER.lock() {
+        *rec = Some(Recorder {
+            started: Instant::now(),
+            window,
+            events: Vec::new(),
+        });
+    }
+}
+
+/// Check whether a recording is in progress
+pub fn is_recording() -> bool {
+    RECORDER.lock().map(|r| r.is_some()).unwrap_or(false)
+}
+
+/// Time left in the current recording window
+#[cfg(feature = "gui")]
+pub fn remaining() -> Option<Duration> {
+    let rec = RECORDER.lock().ok()?;
+    rec.as_ref().map(|r| r.window.saturating_sub(r.started.elapsed()))
+}
+
+/// Record an event verbatim; callers scrub user content with [`scrub`] first
+pub fn record(kind: TraceKind, detail: &str) {
+    if let Ok(mut rec) = RECORDER.lock()
+        && let Some(r) = rec.as_mut()
+        && r.started.elapsed() <= r.window
+    {
+        r.events.push(TraceEvent {
+            at: r.started.elapsed(),
+            kind,
+            detail: sanitize(detail),
+        });
+    }
+}
+
+/// Record the outcome of a Win32 call; error text can quote paths or
+/// console content, so it is scrubbed like the lines
+pub fn record_call<T>(call: &str, result: &Result<T>) {
+    if !is_recording() {
+        return;
+    }
+    match result {
+        Ok(_) => record(TraceKind::Win32, &format!("{} ok", call)),
+        Err(e) => record(TraceKind::Win32, &format!("{} err {}", call, scrub(&e.to_string()))),
+    }
+}
+
+/// Record a successful console read followed by its scrubbed lines
+pub fn record_read(call: &str, result: &Result<Vec<String>>) {
+    record_call(call, result);
+    if let Ok(lines) = result {
+        for line in lines {
+            record(TraceKind::Line, &scrub(line));
+        }
+    }
+}
+
+/// Finish the recording if its window elapsed (or `force` is set).
+/// Returns the suggested file name and the serialized trace.
+#[cfg(feature = "gui")]
+pub fn finish_if_due(force: bool) -> Option<(String, Vec<u8>)> {
+    let mut rec = RECORDER.lock().ok()?;
+    let due = rec.as_ref().is_some_and(|r| force || r.started.elapsed() >= r.window);
+    if !due {
+        return None;
+    }
+    let r = rec.take()?;
+    let name = format!("repro_{}.log", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    Some((name, serialize_trace(&r.events)))
+}
+
+/// Serialize a trace in the tab separated v1 format
+#[cfg(feature = "gui")]
+fn serialize_trace(events: &[TraceEvent]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let _ = writeln!(out, "# remote_con repro trace v1");
+    for ev in events {
+        let _ = writeln!(out, "{}\t{}\t{}", ev.at.as_millis(), ev.kind.as_str(), ev.detail);
+    }
+    out
+}
+
+/// Replace user content with placeholders that keep the text's shape
+pub fn scrub(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a'..='z' => 'x',
+            'A'..='Z' => 'X',
+            '0'..='9' => '0',
+            '\t' | '\r' | '\n' => ' ',
+            c if c.is_ascii() => c,
+            c if c.is_whitespace() => ' ',
+            _ => '?',
+        })
+        .collect()
+}
+
+/// Keep text verbatim but make it safe for the tab separated trace format
+fn sanitize(text: &str) -> String {
+    text.replace(['\t', '\r', '\n'], " ")
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use crate::console::{AttachRoute, ConsoleBackend, ConsoleStream, LineEvent, ReadWindow, StyledLine};
+use crate::worker::{ConsoleWorker, UiMessage, WorkerConfig, WorkerMessage};
+use super::{TraceEvent, TraceKind};
+
+/// Command line flag that replays a trace instead of starting the GUI
+pub const REPLAY_FLAG: &str = "--replay";
+
+/// How long a replay waits for the worker's next message before giving up
+const REPLAY_IDLE: Duration = Duration::from_secs(30);
+
+/// Load a trace written by a recording
+pub fn load_trace(path: &Path) -> Result<Vec<TraceEvent>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = Vec::new();
+
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let mut parts = line.splitn(3, '\t');
+        let (Some(ms), Some(kind), detail) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(anyhow!("Malformed trace line {}", n + 1));
+        };
+        let ms: u64 = ms.parse().map_err(|_| anyhow!("Bad timestamp on line {}", n + 1))?;
+        let kind = TraceKind::parse(kind).ok_or_else(|| anyhow!("Unknown event kind on line {}", n + 1))?;
+        events.push(TraceEvent {
+            at: Duration::from_millis(ms),
+            kind,
+            detail: detail.unwrap_or("").to_string(),
+        });
+    }
+
+    Ok(events)
+}
+
+/// Mock console backend that answers calls from a recorded trace in order.
+///
+/// Each attach/read/send call consumes the next recorded Win32 result of the
+/// same name, so a developer can drive the worker logic through exactly the
+/// sequence of successes and failures the user saw.
+pub struct MockConsole {
+    calls: VecDeque<(String, Result<Vec<String>, String>)>,
+    /// PID the user attached to when the recording started, if it shows
+    target: Option<u32>,
+}
+
+impl MockConsole {
+    /// Build a mock from a trace file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Ok(Self::from_events(&load_trace(path)?))
+    }
+
+    /// Build a mock from already loaded events
+    pub fn from_events(events: &[TraceEvent]) -> Self {
+        let mut calls: VecDeque<(String, Result<Vec<String>, String>)> = VecDeque::new();
+        let mut target = None;
+
+        for ev in events {
+            match ev.kind {
+                TraceKind::Win32 => {
+                    let (name, rest) = ev.detail.split_once(' ').unwrap_or((ev.detail.as_str(), ""));
+                    let result = match rest.strip_prefix("err ") {
+                        Some(msg) => Err(msg.to_string()),
+                        None => Ok(Vec::new()),
+                    };
+                    calls.push_back((name.to_string(), result));
+                }
+                TraceKind::Line => {
+                    if let Some((_, Ok(lines))) = calls.back_mut() {
+                        lines.push(ev.detail.clone());
+                    }
+                }
+                TraceKind::Ui => {
+                    if let Some(pid) = ev.detail.strip_prefix("attach pid=") {
+                        target = target.or(pid.parse().ok());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { calls, target }
+    }
+
+    /// PID the recording attached to, if the trace shows it
+    pub fn target(&self) -> Option<u32> {
+        self.target
+    }
+
+    /// Number of recorded calls not yet replayed
+    #[cfg(test)]
+    pub fn remaining(&self) -> usize {
+        self.calls.len()
+    }
+
+    fn next(&mut self, name: &str) -> Result<Vec<String>> {
+        let idx = self.calls.iter().position(|(n, _)| n == name)
+            .ok_or_else(|| anyhow!("Trace has no more {} calls", name))?;
+        let (_, result) = self.calls.remove(idx).unwrap();
+        result.map_err(|e| anyhow!("{}", e))
+    }
+}
+
+impl ConsoleBackend for MockConsole {
+    /// Replay the next recorded attach
+    fn attach(&mut self, _pid: u32) -> Result<()> {
+        self.next("attach_to_console").map(|_| ())
+    }
+
+    /// Replay the next recorded attach; fallback routes are not recorded,
+    /// so a success counts as a direct attach
+    fn attach_route(&mut self, pid: u32) -> Result<AttachRoute> {
+        self.attach(pid).map(|()| AttachRoute::Direct)
+    }
+
+    /// Detaching is not recorded, so there is nothing to replay
+    fn detach(&mut self) {}
+
+    /// Replay the next recorded read as the window's lines
+    fn poll(&mut self, stream: &mut ConsoleStream, _window: ReadWindow) -> Result<Vec<LineEvent>> {
+        let lines = self.next("read_console_lines")?
+            .into_iter()
+            .map(|text| StyledLine { text, runs: Vec::new() })
+            .collect();
+        Ok(stream.feed(lines))
+    }
+
+    /// Replay the next recorded command send
+    fn send_command(&mut self, _command: &str) -> Result<()> {
+        self.next("send_command").map(|_| ())
+    }
+}
+
+/// Run a worker session against the trace at `path` and print every message
+/// it sends, until the trace runs out and the session is given up.
+///
+/// Run from a debug build, which keeps its console, to see the output.
+pub fn replay(path: &Path) -> Result<()> {
+    let mock = MockConsole::from_file(path)?;
+    let pid = mock.target().unwrap_or(0);
+    let worker = ConsoleWorker::with_backend(WorkerConfig::default(), mock)?;
+    worker.send(UiMessage::Attach(pid))?;
+
+    loop {
+        let Some(msg) = worker.recv_timeout(REPLAY_IDLE) else {
+            println!("No message for {:?}, stopping", REPLAY_IDLE);
+            break;
+        };
+        println!("{:?}", msg);
+        if matches!(msg, WorkerMessage::Disconnected) {
+            break;
+        }
+    }
+    worker.send(UiMessage::Stop)
+}
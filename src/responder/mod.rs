@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+/// Shortest gap between two answers, so a prompt the target redraws in
+/// place is not answered in a loop
+pub const MIN_ANSWER_GAP: Duration = Duration::from_secs(1);
+
+/// Prompt text to watch for and the keys that answer it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptRule {
+    /// Text the prompt line contains, matched case-insensitively
+    pub pattern: String,
+    /// Answer in keep-alive sequence syntax, e.g. `Y{ENTER}`
+    pub keys: String,
+}
+
+impl PromptRule {
+    pub fn new(pattern: &str, keys: &str) -> Self {
+        Self { pattern: pattern.to_string(), keys: keys.to_string() }
+    }
+}
+
+/// Auto-respond settings for a profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponderSettings {
+    pub enabled: bool,
+    pub rules: Vec<PromptRule>,
+}
+
+impl Default for ResponderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: vec![
+                PromptRule::new("Press any key to continue", "{SPACE}"),
+                PromptRule::new("Terminate batch job (Y/N)?", "Y{ENTER}"),
+            ],
+        }
+    }
+}
+
+/// Remembers which prompt was answered so each appearance gets one answer
+#[derive(Debug, Default)]
+pub struct Responder {
+    answered: Option<String>,
+    last_sent: Option<Instant>,
+}
+
+impl Responder {
+    /// The rule to answer `line` (the target's prompt line) with, if any.
+    ///
+    /// A line stays answered until the target moves on to a different line,
+    /// so the answer is sent once even though polls keep reporting it.
+    pub fn check<'a>(&mut self, settings: &'a ResponderSettings, line: &str) -> Option<&'a PromptRule> {
+        if self.answered.as_deref() != Some(line) {
+            self.answered = None;
+        }
+        if !settings.enabled || self.answered.is_some() {
+            return None;
+        }
+        if self.last_sent.is_some_and(|t| t.elapsed() < MIN_ANSWER_GAP) {
+            return None;
+        }
+        let lower = line.to_lowercase();
+        let rule = settings.rules.iter()
+            .find(|r| !r.pattern.trim().is_empty() && lower.contains(&r.pattern.trim().to_lowercase()))?;
+        self.answered = Some(line.to_string());
+        self.last_sent = Some(Instant::now());
+        Some(rule)
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::Write;
+use std::os::windows::io::AsRawHandle;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Memory::{
+    CreateFileMappingW, MapViewOfFile, UnmapViewOfFile, FILE_MAP_READ, MEMORY_MAPPED_VIEW_ADDRESS,
+    PAGE_READONLY,
+};
+use windows::core::PCWSTR;
+use anyhow::{Result, anyhow};
+
+/// Lines moved to disk at a time once the RAM cap is exceeded
+const SPILL_CHUNK_LINES: usize = 4096;
+
+/// Default RAM budget for in-memory lines
+pub const DEFAULT_RAM_CAP: usize = 16 * 1024 * 1024;
+
+/// Counter to keep spill directories unique within the process
+static SPILL_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Append-only line history that keeps recent lines in RAM and spills older
+/// ones to chunk files on disk. Only the per-chunk line offsets stay in
+/// memory; chunk contents are memory-mapped on demand when read or searched.
+pub struct Scrollback {
+    ram_cap: usize,
+    memory: VecDeque<String>,
+    memory_bytes: usize,
+    spill_dir: PathBuf,
+    chunks: Vec<SpillChunk>,
+    spilled_lines: usize,
+    mapped: Option<(usize, MappedFile)>,
+}
+
+/// Index entry for one chunk file on disk
+struct SpillChunk {
+    path: PathBuf,
+    first_line: usize,
+    /// Byte offset of each line start, plus the end offset
+    offsets: Vec<u64>,
+}
+
+impl Scrollback {
+    /// Create an empty scrollback with a RAM budget in bytes
+    pub fn new(ram_cap: usize) -> Self {
+        let spill_dir = std::env::temp_dir().join(format!(
+            "remote_con_scrollback_{}_{}",
+            std::process::id(),
+            SPILL_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self {
+            ram_cap,
+            memory: VecDeque::new(),
+            memory_bytes: 0,
+            spill_dir,
+            chunks: Vec::new(),
+            spilled_lines: 0,
+            mapped: None,
+        }
+    }
+
+    /// Total number of lines (in RAM and on disk)
+    pub fn len(&self) -> usize {
+        self.spilled_lines + self.memory.len()
+    }
+
+    /// Bytes currently held in RAM
+    pub fn memory_bytes(&self) -> usize {
+        self.memory_bytes
+    }
+
+    /// Number of lines stored on disk
+    pub fn spilled_lines(&self) -> usize {
+        self.spilled_lines
+    }
+
+    /// Change the RAM budget, spilling immediately if it shrank
+    pub fn set_ram_cap(&mut self, ram_cap: usize) -> Result<()> {
+        self.ram_cap = ram_cap;
+        self.enforce_cap()
+    }
+
+    /// Append a line, spilling old lines to disk if over the RAM cap
+    pub fn push(&mut self, line: String) -> Result<()> {
+        self.memory_bytes += line.len();
+        self.memory.push_back(line);
+        self.enforce_cap()
+    }
+
+    /// Fetch line `index`, mapping its chunk from disk if needed
+    pub fn get(&mut self, index: usize) -> Option<String> {
+        if index >= self.len() {
+            return None;
+        }
+        if index >= self.spilled_lines {
+            return self.memory.get(index - self.spilled_lines).cloned();
+        }
+
+        let chunk_idx = self.chunks.partition_point(|c| c.first_line <= index) - 1;
+        self.map_chunk(chunk_idx).ok()?;
+        let bytes = self.mapped_bytes();
+        let chunk = &self.chunks[chunk_idx];
+        let n = index - chunk.first_line;
+        let (start, end) = (chunk.offsets[n] as usize, chunk.offsets[n + 1] as usize);
+        Some(String::from_utf8_lossy(&bytes[start..end]).into_owned())
+    }
+
+    /// Find indices of lines containing `needle` (case-insensitive), up to `limit`
+    pub fn search(&mut self, needle: &str, limit: usize) -> Vec<usize> {
+        let needle = needle.to_lowercase();
+        let mut hits = Vec::new();
+        if needle.is_empty() {
+            return hits;
+        }
+
+        for chunk_idx in 0..self.chunks.len() {
+            if self.map_chunk(chunk_idx).is_err() {
+                continue;
+            }
+            let bytes = self.mapped_bytes();
+            let chunk = &self.chunks[chunk_idx];
+            for n in 0..chunk.offsets.len() - 1 {
+                let line = &bytes[chunk.offsets[n] as usize..chunk.offsets[n + 1] as usize];
+                if String::from_utf8_lossy(line).to_lowercase().contains(&needle) {
+                    hits.push(chunk.first_line + n);
+                    if hits.len() >= limit {
+                        return hits;
+                    }
+                }
+            }
+        }
+
+        for (n, line) in self.memory.iter().enumerate() {
+            if line.to_lowercase().contains(&needle) {
+                hits.push(self.spilled_lines + n);
+                if hits.len() >= limit {
+                    break;
+                }
+            }
+        }
+        hits
+    }
+
+    /// Drop all lines and remove spill files
+    pub fn clear(&mut self) {
+        self.mapped = None;
+        let _ = fs::remove_dir_all(&self.spill_dir);
+        self.memory.clear();
+        self.memory_bytes = 0;
+        self.chunks.clear();
+        self.spilled_lines = 0;
+    }
+
+    /// Spill the oldest in-memory lines until under the RAM cap
+    fn enforce_cap(&mut self) -> Result<()> {
+        while self.memory_bytes > self.ram_cap && !self.memory.is_empty() {
+            self.spill_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Move up to SPILL_CHUNK_LINES of the oldest lines into a new chunk file
+    fn spill_chunk(&mut self) -> Result<()> {
+        fs::create_dir_all(&self.spill_dir)
+            .map_err(|e| anyhow!("Failed to create spill dir: {}", e))?;
+        let path = self.spill_dir.join(format!("chunk_{:06}.bin", self.chunks.len()));
+        let mut file = File::create(&path)
+            .map_err(|e| anyhow!("Failed to create {}: {}", path.display(), e))?;
+
+        let count = SPILL_CHUNK_LINES.min(self.memory.len());
+        let mut offsets = Vec::with_capacity(count + 1);
+        let mut pos = 0u64;
+        let mut buf = Vec::new();
+        for line in self.memory.drain(..count) {
+            offsets.push(pos);
+            pos += line.len() as u64;
+            self.memory_bytes -= line.len();
+            buf.extend_from_slice(line.as_bytes());
+        }
+        offsets.push(pos);
+        file.write_all(&buf)?;
+
+        self.chunks.push(SpillChunk {
+            path,
+            first_line: self.spilled_lines,
+            offsets,
+        });
+        self.spilled_lines += count;
+        Ok(())
+    }
+
+    /// Map a chunk file, reusing the last mapping when possible
+    fn map_chunk(&mut self, chunk_idx: usize) -> Result<()> {
+        if self.mapped.as_ref().map(|(i, _)| *i) != Some(chunk_idx) {
+            self.mapped = None;
+            let map = MappedFile::open(&self.chunks[chunk_idx].path)?;
+            self.mapped = Some((chunk_idx, map));
+        }
+        Ok(())
+    }
+
+    /// Contents of the currently mapped chunk
+    fn mapped_bytes(&self) -> &[u8] {
+        self.mapped.as_ref().map(|(_, m)| m.bytes()).unwrap_or(&[])
+    }
+}
+
+impl Drop for Scrollback {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Read-only memory mapping of a whole file
+struct MappedFile {
+    _file: File,
+    mapping: HANDLE,
+    view: MEMORY_MAPPED_VIEW_ADDRESS,
+    len: usize,
+}
+
+impl MappedFile {
+    fn open(path: &std::path::Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Self {
+                _file: file,
+                mapping: HANDLE::default(),
+                view: MEMORY_MAPPED_VIEW_ADDRESS::default(),
+                len: 0,
+            });
+        }
+
+        let mapping = unsafe {
+            CreateFileMappingW(HANDLE(file.as_raw_handle() as _), None, PAGE_READONLY, 0, 0, PCWSTR::null())
+        }
+        .map_err(|e| anyhow!("Failed to map {}: {}", path.display(), e))?;
+
+        let view = unsafe { MapViewOfFile(mapping, FILE_MAP_READ, 0, 0, 0) };
+        if view.Value.is_null() {
+            unsafe {
+                let _ = CloseHandle(mapping);
+            }
+            return Err(anyhow!("Failed to map view of {}", path.display()));
+        }
+
+        Ok(Self { _file: file, mapping, view, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.view.Value as *const u8, self.len) }
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        unsafe {
+            let _ = UnmapViewOfFile(self.view);
+            let _ = CloseHandle(self.mapping);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{Result, anyhow};
+use super::StorageSink;
+
+/// Writes files into a local folder, creating it if needed
+pub struct LocalFolderSink {
+    root: PathBuf,
+}
+
+impl LocalFolderSink {
+    pub fn new(root: &str) -> Self {
+        Self { root: PathBuf::from(root) }
+    }
+}
+
+impl StorageSink for LocalFolderSink {
+    fn describe(&self) -> String {
+        format!("folder {}", self.root.display())
+    }
+
+    fn put(&self, name: &str, data: &[u8]) -> Result<String> {
+        fs::create_dir_all(&self.root)
+            .map_err(|e| anyhow!("Failed to create {}: {}", self.root.display(), e))?;
+        let path = self.root.join(name);
+        fs::write(&path, data)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path.display().to_string())
+    }
+}
@@ -0,0 +1,202 @@
+pub mod local;
+#[cfg(feature = "web")]
+pub mod s3;
+pub mod share;
+
+use std::thread;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use anyhow::{Result, anyhow};
+use crate::profile::{ProfileSettings, ProfileStore};
+
+pub use local::LocalFolderSink;
+#[cfg(feature = "web")]
+pub use s3::S3Sink;
+pub use share::NetworkShareSink;
+
+/// Prefix of the profile keys holding the storage settings
+const SETTINGS_PREFIX: &str = "storage";
+
+/// Destination for saved logs, recordings and other captured history
+pub trait StorageSink: Send {
+    /// Short description of where data goes, for error messages
+    fn describe(&self) -> String;
+
+    /// Store `data` under `name` and return the resulting location
+    fn put(&self, name: &str, data: &[u8]) -> Result<String>;
+}
+
+/// Which storage backend to use and its settings
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageConfig {
+    /// A folder on the local machine
+    LocalFolder { path: String },
+    /// A UNC path such as \\server\share\logs, optionally with explicit credentials
+    NetworkShare { unc_path: String, username: String, password: String },
+    /// An S3-compatible bucket (AWS, MinIO, ...)
+    #[cfg(feature = "web")]
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        prefix: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig::LocalFolder { path: ".".to_string() }
+    }
+}
+
+impl StorageConfig {
+    /// Display name of the backend kind
+    pub fn kind_label(&self) -> &'static str {
+        match self {
+            StorageConfig::LocalFolder { .. } => "Local folder",
+            StorageConfig::NetworkShare { .. } => "Network share",
+            #[cfg(feature = "web")]
+            StorageConfig::S3 { .. } => "S3-compatible",
+        }
+    }
+
+    /// Profile settings for this configuration; passwords and secret keys are left out
+    pub fn to_settings(&self) -> ProfileSettings {
+        let mut s = ProfileSettings::new();
+        let mut put = |k: &str, v: &str| s.insert(format!("{}.{}", SETTINGS_PREFIX, k), v.to_string());
+        match self {
+            StorageConfig::LocalFolder { path } => {
+                put("kind", "local");
+                put("path", path);
+            }
+            StorageConfig::NetworkShare { unc_path, username, .. } => {
+                put("kind", "share");
+                put("unc_path", unc_path);
+                put("username", username);
+            }
+            #[cfg(feature = "web")]
+            StorageConfig::S3 { endpoint, region, bucket, prefix, access_key, .. } => {
+                put("kind", "s3");
+                put("endpoint", endpoint);
+                put("region", region);
+                put("bucket", bucket);
+                put("prefix", prefix);
+                put("access_key", access_key);
+            }
+        }
+        s
+    }
+
+    /// Configuration stored in a profile, or the default when it has none
+    pub fn from_settings(s: &ProfileSettings) -> Self {
+        let get = |k: &str| s.get(&format!("{}.{}", SETTINGS_PREFIX, k)).cloned().unwrap_or_default();
+        match get("kind").as_str() {
+            "share" => StorageConfig::NetworkShare {
+                unc_path: get("unc_path"),
+                username: get("username"),
+                password: String::new(),
+            },
+            #[cfg(feature = "web")]
+            "s3" => StorageConfig::S3 {
+                endpoint: get("endpoint"),
+                region: get("region"),
+                bucket: get("bucket"),
+                prefix: get("prefix"),
+                access_key: get("access_key"),
+                secret_key: String::new(),
+            },
+            "local" => StorageConfig::LocalFolder { path: get("path") },
+            _ => StorageConfig::default(),
+        }
+    }
+
+    /// Store these settings in the current profile of `profiles`
+    pub fn save_to(&self, profiles: &mut ProfileStore) {
+        profiles.update(SETTINGS_PREFIX, self.to_settings());
+    }
+
+    /// Create the sink described by this configuration
+    pub fn open(&self) -> Result<Box<dyn StorageSink>> {
+        Ok(match self {
+            StorageConfig::LocalFolder { path } => Box::new(LocalFolderSink::new(path)),
+            StorageConfig::NetworkShare { unc_path, username, password } => {
+                Box::new(NetworkShareSink::connect(unc_path, username, password)?)
+            }
+            #[cfg(feature = "web")]
+            StorageConfig::S3 { endpoint, region, bucket, prefix, access_key, secret_key } => {
+                Box::new(S3Sink::new(endpoint, region, bucket, prefix, access_key, secret_key)?)
+            }
+        })
+    }
+}
+
+/// A save queued on the [`StorageWriter`]
+struct SaveJob {
+    config: StorageConfig,
+    name: String,
+    data: Vec<u8>,
+    what: String,
+}
+
+/// Result of a queued save: what was saved and where it went
+pub struct Saved {
+    pub what: String,
+    pub result: Result<String, String>,
+}
+
+/// Saves captures on a background thread so a slow share or upload never
+/// stalls the UI. Sinks are opened once per configuration and reused, so a
+/// network share is connected once rather than on every save; a sink that
+/// fails a write is dropped and reopened on the next save.
+pub struct StorageWriter {
+    jobs: Sender<SaveJob>,
+    done: Receiver<Saved>,
+}
+
+impl StorageWriter {
+    pub fn new() -> Self {
+        let (jobs, job_rx) = unbounded::<SaveJob>();
+        let (done_tx, done) = unbounded();
+        // If the thread cannot start, `save` reports the closed channel
+        let _ = thread::Builder::new()
+            .name("storage-writer".to_string())
+            .spawn(move || {
+                let mut sinks: Vec<(StorageConfig, Box<dyn StorageSink>)> = Vec::new();
+                for job in job_rx {
+                    let result = put(&mut sinks, &job).map_err(|e| e.to_string());
+                    if done_tx.send(Saved { what: job.what, result }).is_err() {
+                        break;
+                    }
+                }
+            });
+        Self { jobs, done }
+    }
+
+    /// Queue `data` to be stored as `name`; `what` names it in the result
+    pub fn save(&self, config: &StorageConfig, name: &str, data: Vec<u8>, what: &str) -> Result<()> {
+        self.jobs
+            .send(SaveJob { config: config.clone(), name: name.to_string(), data, what: what.to_string() })
+            .map_err(|_| anyhow!("Storage writer is not running"))
+    }
+
+    /// Next finished save, if any
+    pub fn try_recv(&self) -> Option<Saved> {
+        self.done.try_recv().ok()
+    }
+}
+
+/// Store one job, opening (and caching) the sink for its configuration
+fn put(sinks: &mut Vec<(StorageConfig, Box<dyn StorageSink>)>, job: &SaveJob) -> Result<String> {
+    let index = match sinks.iter().position(|(c, _)| *c == job.config) {
+        Some(i) => i,
+        None => {
+            sinks.push((job.config.clone(), job.config.open()?));
+            sinks.len() - 1
+        }
+    };
+    sinks[index].1.put(&job.name, &job.data).map_err(|e| {
+        let (_, sink) = sinks.remove(index);
+        anyhow!("{} ({})", e, sink.describe())
+    })
+}
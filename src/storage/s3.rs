@@ -0,0 +1,236 @@
+use std::ffi::c_void;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use windows::Win32::Networking::WinHttp::{
+    WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest, WinHttpQueryHeaders,
+    WinHttpReceiveResponse, WinHttpSendRequest, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+    WINHTTP_FLAG_SECURE, WINHTTP_OPEN_REQUEST_FLAGS, WINHTTP_QUERY_FLAG_NUMBER,
+    WINHTTP_QUERY_STATUS_CODE,
+};
+use windows::core::PCWSTR;
+use anyhow::{Result, anyhow};
+use super::StorageSink;
+
+/// Uploads objects to an S3-compatible bucket using path-style PUT requests
+/// signed with AWS Signature Version 4. Transport goes through WinHTTP so the
+/// system proxy and certificate store apply.
+pub struct S3Sink {
+    secure: bool,
+    host: String,
+    port: u16,
+    region: String,
+    bucket: String,
+    prefix: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Sink {
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        prefix: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Result<Self> {
+        let (secure, rest) = if let Some(rest) = endpoint.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = endpoint.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            (true, endpoint)
+        };
+        let authority = rest.trim_end_matches('/');
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((h, p)) => (h.to_string(), p.parse().map_err(|_| anyhow!("Invalid port in {}", endpoint))?),
+            None => (authority.to_string(), if secure { 443 } else { 80 }),
+        };
+
+        if host.is_empty() || bucket.is_empty() {
+            return Err(anyhow!("S3 endpoint and bucket are required"));
+        }
+
+        Ok(Self {
+            secure,
+            host,
+            port,
+            region: if region.is_empty() { "us-east-1".to_string() } else { region.to_string() },
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        })
+    }
+
+    /// Host header value as WinHTTP will send it
+    fn host_header(&self) -> String {
+        let default_port = if self.secure { 443 } else { 80 };
+        if self.port == default_port {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Build the SigV4 headers for a PUT of `payload` to `path`
+    fn signed_headers(&self, path: &str, payload: &[u8]) -> String {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(payload));
+        let host = self.host_header();
+
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            path, host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let k_date = hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes());
+        let k_region = hmac_sha256(&k_date, self.region.as_bytes());
+        let k_service = hmac_sha256(&k_region, b"s3");
+        let k_signing = hmac_sha256(&k_service, b"aws4_request");
+        let signature = hex(&hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+
+        format!(
+            "x-amz-date: {}\r\nx-amz-content-sha256: {}\r\nAuthorization: AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}\r\n",
+            amz_date, payload_hash, self.access_key, scope, signature
+        )
+    }
+}
+
+impl StorageSink for S3Sink {
+    fn describe(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.prefix)
+    }
+
+    fn put(&self, name: &str, data: &[u8]) -> Result<String> {
+        let key = if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        };
+        let path = format!("/{}/{}", uri_encode(&self.bucket), uri_encode(&key));
+        let headers = self.signed_headers(&path, data);
+
+        let status = http_put(self.secure, &self.host, self.port, &path, &headers, data)?;
+        if !(200..300).contains(&status) {
+            return Err(anyhow!("S3 upload of {} failed with HTTP {}", key, status));
+        }
+        Ok(format!("s3://{}/{}", self.bucket, key))
+    }
+}
+
+/// Owned WinHTTP handle that closes on drop
+struct InternetHandle(*mut c_void);
+
+impl InternetHandle {
+    fn new(raw: *mut c_void, what: &str) -> Result<Self> {
+        if raw.is_null() {
+            return Err(anyhow!("{} failed: {}", what, windows::core::Error::from_win32()));
+        }
+        Ok(Self(raw))
+    }
+}
+
+impl Drop for InternetHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = WinHttpCloseHandle(self.0);
+        }
+    }
+}
+
+/// Perform a PUT request and return the HTTP status code
+fn http_put(secure: bool, host: &str, port: u16, path: &str, headers: &str, body: &[u8]) -> Result<u32> {
+    let agent = to_wide("remote_con");
+    let host_wide = to_wide(host);
+    let verb = to_wide("PUT");
+    let path_wide = to_wide(path);
+    let headers_wide: Vec<u16> = headers.encode_utf16().collect();
+
+    unsafe {
+        let session = InternetHandle::new(
+            WinHttpOpen(PCWSTR::from_raw(agent.as_ptr()), WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, PCWSTR::null(), PCWSTR::null(), 0),
+            "WinHttpOpen",
+        )?;
+        let connect = InternetHandle::new(
+            WinHttpConnect(session.0, PCWSTR::from_raw(host_wide.as_ptr()), port, 0),
+            "WinHttpConnect",
+        )?;
+        let flags = if secure { WINHTTP_FLAG_SECURE } else { WINHTTP_OPEN_REQUEST_FLAGS(0) };
+        let request = InternetHandle::new(
+            WinHttpOpenRequest(
+                connect.0,
+                PCWSTR::from_raw(verb.as_ptr()),
+                PCWSTR::from_raw(path_wide.as_ptr()),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                std::ptr::null(),
+                flags,
+            ),
+            "WinHttpOpenRequest",
+        )?;
+
+        WinHttpSendRequest(
+            request.0,
+            Some(&headers_wide),
+            Some(body.as_ptr() as *const c_void),
+            body.len() as u32,
+            body.len() as u32,
+            0,
+        )
+        .map_err(|e| anyhow!("Failed to send S3 request: {}", e))?;
+
+        WinHttpReceiveResponse(request.0, std::ptr::null_mut())
+            .map_err(|e| anyhow!("No response from S3 endpoint: {}", e))?;
+
+        let mut status = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        WinHttpQueryHeaders(
+            request.0,
+            WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+            PCWSTR::null(),
+            Some(&mut status as *mut u32 as *mut c_void),
+            &mut size,
+            std::ptr::null_mut(),
+        )
+        .map_err(|e| anyhow!("Failed to read S3 response status: {}", e))?;
+
+        Ok(status)
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encode a key for the canonical URI, keeping '/' separators
+fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Convert a string to a wide null-terminated string
+fn to_wide(s: &str) -> Vec<u16> {
+    let mut w: Vec<u16> = s.encode_utf16().collect();
+    w.push(0);
+    w
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::PathBuf;
+use windows::Win32::Foundation::{ERROR_SESSION_CREDENTIAL_CONFLICT, NO_ERROR};
+use windows::Win32::NetworkManagement::WNet::{WNetAddConnection2W, NETRESOURCEW, NET_CONNECT_FLAGS, RESOURCETYPE_DISK};
+use windows::core::{PCWSTR, PWSTR};
+use anyhow::{Result, anyhow};
+use super::StorageSink;
+
+/// Writes files to a UNC network share, connecting with explicit credentials when given
+pub struct NetworkShareSink {
+    root: PathBuf,
+}
+
+impl NetworkShareSink {
+    /// Establish the share connection (if credentials are set) and return the sink
+    pub fn connect(unc_path: &str, username: &str, password: &str) -> Result<Self> {
+        let share = share_root(unc_path)
+            .ok_or_else(|| anyhow!("Not a UNC path: {}", unc_path))?;
+
+        if !username.is_empty() {
+            let mut remote = to_wide(&share);
+            let resource = NETRESOURCEW {
+                dwType: RESOURCETYPE_DISK,
+                lpRemoteName: PWSTR(remote.as_mut_ptr()),
+                ..Default::default()
+            };
+            let user_wide = to_wide(username);
+            let pass_wide = to_wide(password);
+
+            let status = unsafe {
+                WNetAddConnection2W(
+                    &resource,
+                    PCWSTR::from_raw(pass_wide.as_ptr()),
+                    PCWSTR::from_raw(user_wide.as_ptr()),
+                    NET_CONNECT_FLAGS(0),
+                )
+            };
+            // A conflict means a session already exists; let the write decide
+            if status != NO_ERROR && status != ERROR_SESSION_CREDENTIAL_CONFLICT {
+                return Err(anyhow!("Failed to connect to {}: error {}", share, status.0));
+            }
+        }
+
+        Ok(Self { root: PathBuf::from(unc_path) })
+    }
+}
+
+impl StorageSink for NetworkShareSink {
+    fn describe(&self) -> String {
+        format!("share {}", self.root.display())
+    }
+
+    fn put(&self, name: &str, data: &[u8]) -> Result<String> {
+        fs::create_dir_all(&self.root)
+            .map_err(|e| anyhow!("Failed to create {}: {}", self.root.display(), e))?;
+        let path = self.root.join(name);
+        fs::write(&path, data)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path.display().to_string())
+    }
+}
+
+/// Extract \\server\share from a longer UNC path
+fn share_root(unc_path: &str) -> Option<String> {
+    let rest = unc_path.strip_prefix(r"\\")?;
+    let mut parts = rest.split('\\').filter(|p| !p.is_empty());
+    let server = parts.next()?;
+    let share = parts.next()?;
+    Some(format!(r"\\{}\{}", server, share))
+}
+
+/// Convert a string to a wide null-terminated string
+fn to_wide(s: &str) -> Vec<u16> {
+    let mut w: Vec<u16> = s.encode_utf16().collect();
+    w.push(0);
+    w
+}
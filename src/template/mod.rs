@@ -0,0 +1,54 @@
+use anyhow::{Result, anyhow};
+
+/// Variables every command can use without defining them
+pub const BUILTIN_VARIABLES: [&str; 5] = ["pid", "date", "time", "clipboard", "selection"];
+
+/// Expand `{name}` placeholders in `template` using `lookup`.
+///
+/// Only names made of letters, digits and `_` are placeholders, so other
+/// braces (PowerShell blocks, `%{...}`) pass through untouched. `{{` and
+/// `}}` stand for literal braces.
+pub fn expand(template: &str, mut lookup: impl FnMut(&str) -> Result<String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        out.push_str(&rest[..open]);
+        let brace = &rest[open..open + 1];
+        rest = &rest[open + 1..];
+        if rest.starts_with(brace) {
+            out.push_str(brace);
+            rest = &rest[1..];
+            continue;
+        }
+        let name = rest.find('}').map(|close| &rest[..close]);
+        match name {
+            Some(name) if brace == "{" && is_name(name) => {
+                out.push_str(&lookup(name)?);
+                rest = &rest[name.len() + 1..];
+            }
+            _ => out.push_str(brace),
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parse user variables written one `name=value` per line; blank lines and
+/// lines starting with `#` are skipped
+pub fn parse_variables(text: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (name, value) = line.split_once('=')
+            .ok_or_else(|| anyhow!("Variable line without '=': {}", line))?;
+        let name = name.trim();
+        if !is_name(name) {
+            return Err(anyhow!("Invalid variable name: {}", name));
+        }
+        vars.push((name.to_string(), value.trim().to_string()));
+    }
+    Ok(vars)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,176 @@
+use std::ffi::c_void;
+use std::fs::File;
+use std::io::Read;
+use std::os::windows::io::FromRawHandle;
+use std::thread;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Console::{ClosePseudoConsole, CreatePseudoConsole, ResizePseudoConsole, COORD, HPCON};
+use windows::Win32::System::Pipes::CreatePipe;
+use windows::Win32::System::Threading::{
+    CreateProcessW, DeleteProcThreadAttributeList, InitializeProcThreadAttributeList,
+    TerminateProcess, UpdateProcThreadAttribute, EXTENDED_STARTUPINFO_PRESENT,
+    LPPROC_THREAD_ATTRIBUTE_LIST, PROCESS_INFORMATION, PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE,
+    STARTUPINFOEXW,
+};
+use windows::core::{PCWSTR, PWSTR};
+use anyhow::{Result, anyhow};
+
+/// Shell started by the local terminal
+pub const DEFAULT_SHELL: &str = "cmd.exe";
+
+/// Initial pseudoconsole size in cells
+const DEFAULT_COLS: i16 = 120;
+const DEFAULT_ROWS: i16 = 40;
+
+/// A shell on this machine hosted in a pseudoconsole that remote_con owns.
+///
+/// The pseudoconsole keeps a real screen buffer, so the shell is read and
+/// written like any attached target (same worker, renderer, history, audit
+/// log and journal). The VT stream ConPTY emits is only drained so the
+/// shell never blocks.
+pub struct LocalTerminal {
+    pseudo_console: HPCON,
+    process: HANDLE,
+    pid: u32,
+    /// Our end of the input pipe; input goes through the console instead,
+    /// but closing it would end the pseudoconsole
+    input: HANDLE,
+    /// Screen size in cells, as last set
+    size: (i16, i16),
+}
+
+impl LocalTerminal {
+    /// Start `command` in a new pseudoconsole
+    pub fn spawn(command: &str) -> Result<Self> {
+        let (mut pty_in, mut input) = (HANDLE::default(), HANDLE::default());
+        let (mut output, mut pty_out) = (HANDLE::default(), HANDLE::default());
+        unsafe {
+            CreatePipe(&mut pty_in, &mut input, None, 0)
+                .map_err(|e| anyhow!("Failed to create input pipe: {}", e))?;
+            CreatePipe(&mut output, &mut pty_out, None, 0)
+                .map_err(|e| anyhow!("Failed to create output pipe: {}", e))?;
+        }
+
+        let size = COORD { X: DEFAULT_COLS, Y: DEFAULT_ROWS };
+        let pseudo_console = unsafe { CreatePseudoConsole(size, pty_in, pty_out, 0) };
+        // The pseudoconsole holds its own references to its ends of the pipes
+        unsafe {
+            let _ = CloseHandle(pty_in);
+            let _ = CloseHandle(pty_out);
+        }
+        let pseudo_console = pseudo_console.map_err(|e| anyhow!("Failed to create pseudoconsole: {}", e))?;
+
+        let info = match start_process(command, pseudo_console) {
+            Ok(info) => info,
+            Err(e) => {
+                unsafe {
+                    ClosePseudoConsole(pseudo_console);
+                    let _ = CloseHandle(input);
+                    let _ = CloseHandle(output);
+                }
+                return Err(e);
+            }
+        };
+        unsafe {
+            let _ = CloseHandle(info.hThread);
+        }
+
+        // Drain the VT stream; conhost stalls the shell if this pipe fills up
+        let mut reader = unsafe { File::from_raw_handle(output.0 as _) };
+        thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            while matches!(reader.read(&mut buf), Ok(n) if n > 0) {}
+        });
+
+        Ok(Self {
+            pseudo_console,
+            process: info.hProcess,
+            pid: info.dwProcessId,
+            input,
+            size: (DEFAULT_COLS, DEFAULT_ROWS),
+        })
+    }
+
+    /// PID of the shell, used to attach to the pseudoconsole
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Resize the pseudoconsole's screen, if it is not that size already
+    pub fn resize(&mut self, cols: usize, rows: usize) -> Result<()> {
+        let size = (cols.clamp(1, i16::MAX as usize) as i16, rows.clamp(1, i16::MAX as usize) as i16);
+        if size == self.size {
+            return Ok(());
+        }
+        // Recorded first, so a size the pseudoconsole refuses is not retried every frame
+        self.size = size;
+        unsafe { ResizePseudoConsole(self.pseudo_console, COORD { X: size.0, Y: size.1 }) }
+            .map_err(|e| anyhow!("Failed to resize pseudoconsole: {}", e))
+    }
+}
+
+impl Drop for LocalTerminal {
+    fn drop(&mut self) {
+        unsafe {
+            ClosePseudoConsole(self.pseudo_console);
+            let _ = TerminateProcess(self.process, 0);
+            let _ = CloseHandle(self.process);
+            let _ = CloseHandle(self.input);
+        }
+    }
+}
+
+/// Create the shell process bound to the pseudoconsole
+fn start_process(command: &str, pseudo_console: HPCON) -> Result<PROCESS_INFORMATION> {
+    // Size and build the attribute list that carries the pseudoconsole
+    let mut size = 0usize;
+    unsafe {
+        let _ = InitializeProcThreadAttributeList(None, 1, None, &mut size);
+    }
+    let mut attr_buf = vec![0u8; size];
+    let attr_list = LPPROC_THREAD_ATTRIBUTE_LIST(attr_buf.as_mut_ptr() as *mut c_void);
+    unsafe {
+        InitializeProcThreadAttributeList(Some(attr_list), 1, None, &mut size)
+            .map_err(|e| anyhow!("Failed to initialize attribute list: {}", e))?;
+    }
+
+    let result = unsafe {
+        UpdateProcThreadAttribute(
+            attr_list,
+            0,
+            PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE as usize,
+            Some(pseudo_console.0 as *const c_void),
+            std::mem::size_of::<HPCON>(),
+            None,
+            None,
+        )
+        .map_err(|e| anyhow!("Failed to set pseudoconsole attribute: {}", e))
+        .and_then(|_| {
+            let mut startup = STARTUPINFOEXW::default();
+            startup.StartupInfo.cb = std::mem::size_of::<STARTUPINFOEXW>() as u32;
+            startup.lpAttributeList = attr_list;
+
+            let mut command_line: Vec<u16> = command.encode_utf16().chain(Some(0)).collect();
+            let mut info = PROCESS_INFORMATION::default();
+            CreateProcessW(
+                PCWSTR::null(),
+                Some(PWSTR(command_line.as_mut_ptr())),
+                None,
+                None,
+                false,
+                EXTENDED_STARTUPINFO_PRESENT,
+                None,
+                PCWSTR::null(),
+                &startup.StartupInfo,
+                &mut info,
+            )
+            .map(|_| info)
+            .map_err(|e| anyhow!("Failed to start {}: {}", command, e))
+        })
+    };
+
+    unsafe {
+        DeleteProcThreadAttributeList(attr_list);
+    }
+    result
+}
@@ -1,8 +1,97 @@
 use eframe::egui;
+#[cfg(feature = "automation")]
+use std::collections::BTreeSet;
 use std::time::{Duration, Instant};
-use crate::process::{enumerate_cmd_processes, CmdProcessInfo};
-use crate::worker::{ConsoleWorker, WorkerMessage, UiMessage, WorkerConfig};
-use crate::console::{attach_to_console, send_command, send_ctrl_c, send_control_char, detach_from_console};
+use crate::process::{enumerate_cmd_processes, enumerate_in_background, is_current_process_elevated, wildcard_match, CmdProcessInfo};
+use crate::worker::{AdaptiveInterval, ConsoleWorker, DEFAULT_HISTORY_LINES, FilterSpec, WorkerEvent, HEARTBEAT_INTERVAL, PollTimings, SnapshotKind, WorkerMessage, WorkerStats, UiMessage, WorkerConfig, WorkerPool};
+use crate::console::{input_degraded, take_dry_run};
+use crate::console::{unsubscribe, HookId, KeyStroke};
+#[cfg(feature = "automation")]
+use crate::console::BatchOptions;
+use crossbeam_channel::Receiver;
+use crate::helper::HelperClient;
+use crate::repro::{self, TraceKind};
+use crate::storage::{StorageConfig, StorageWriter};
+use crate::profile::ProfileStore;
+use crate::scrollback::{Scrollback, DEFAULT_RAM_CAP};
+use crate::platform::{clipboard_text, main_window, PlacementStore};
+use crate::cli::StartupIntent;
+use crate::console::{classify_severity, set_read_chunk_cells, ColorRun, CursorShape, Severity, LineEnding, TextDecoding, TextOptions, VirtualKey, DEFAULT_CHUNK_CELLS, MOUSE_LEFT, MOUSE_MIDDLE, MOUSE_RIGHT, ConsoleMetrics, ConsoleModes, ConsoleSelection, InputSettings, ReadWindow, SmallRect};
+use crate::palette::{ColorSettings, IntensityMode, PalettePreset, Rgb};
+#[cfg(feature = "automation")]
+use crate::keepalive::{parse_sequence, KeepAlive, KeepAliveSettings};
+#[cfg(feature = "automation")]
+use crate::responder::{PromptRule, Responder, ResponderSettings};
+use crate::audit;
+use crate::notes::{session_key, SessionNotes};
+use crate::paste::{LinePaste, DEFAULT_LINE_DELAY};
+use crate::template::{expand, parse_variables, BUILTIN_VARIABLES};
+use crate::guard::{CommandGuard, GuardAction, GuardRule};
+use crate::terminal::{LocalTerminal, DEFAULT_SHELL};
+#[cfg(feature = "automation")]
+use crate::journal::Journal;
+
+/// Length of a repro recording window
+const REPRO_WINDOW_SECS: u64 = 60;
+
+/// Placement key of the main window, the only window the app opens
+const MAIN_WINDOW_KEY: &str = "main";
+
+/// How long the window has to stay put after a move or resize before its placement is saved
+const PLACEMENT_SAVE_DELAY: Duration = Duration::from_millis(750);
+
+/// Time windows offered by the journal filter, in minutes (0 = everything)
+#[cfg(feature = "automation")]
+const JOURNAL_WINDOWS: [(u32, &str); 4] = [(0, "All time"), (15, "Last 15 min"), (60, "Last hour"), (24 * 60, "Last 24 h")];
+
+/// How long a journal replay waits for its target to be attached
+#[cfg(feature = "automation")]
+const REPLAY_ATTACH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ctrl+<letter> keys offered in the send dropdown, with what they usually do
+const CTRL_KEYS: [(char, &str); 8] = [
+    ('D', "End of input (ssh, Unix shells, python on WSL)"),
+    ('Z', "End of input on Windows (python, more)"),
+    ('X', "Exit (nano)"),
+    ('S', "Pause output / save"),
+    ('Q', "Resume output"),
+    ('L', "Clear screen"),
+    ('R', "Reverse history search"),
+    ('U', "Erase line"),
+];
+
+/// History line standing in for output that was lost between polls
+const HISTORY_GAP_MARKER: &str = "--- lines lost between polls ---";
+
+/// Separator inserted into the history where the target cleared its screen
+const HISTORY_CLEAR_MARKER: &str = "--- screen cleared ---";
+
+/// How often to rescan processes while waiting to auto-reattach
+const REATTACH_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Which process property auto-reattach matches against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReattachMatch {
+    WindowTitle,
+    CommandLine,
+}
+
+/// Lines fetched around the visible rows of the worker history view
+const WORKER_HISTORY_PAGE: usize = 200;
+
+/// A row of the poll timings table: its label and how to show it for one poll
+type TimingRow = (&'static str, fn(&PollTimings) -> String);
+
+/// What the UI holds of the worker's history ring: one fetched range
+#[derive(Default)]
+struct WorkerHistoryView {
+    first: usize,
+    lines: Vec<String>,
+    total: usize,
+    /// When the outstanding `GetHistory` was sent
+    requested: Option<Instant>,
+    fetched: Option<Instant>,
+}
 
 /// Main application state
 pub struct RemoteConApp {
@@ -12,15 +101,67 @@ pub struct RemoteConApp {
     show_refresh_error: Option<String>,
 
     // Worker for background polling
-    worker: Option<ConsoleWorker>,
+    workers: WorkerPool,
 
     // Console output state
     console_output: Vec<String>,
+    console_colors: Vec<Vec<ColorRun>>,
+    // Target cursor as (line in console_output, column)
+    console_cursor: Option<(usize, usize)>,
+    // Rows picked in the output by click / shift-click (anchor, end)
+    output_selection: Option<(usize, usize)>,
+    // Output row to bring into view on the next frame
+    jump_to_line: Option<usize>,
+    colors: ColorSettings,
+    decode_vt: bool,
+    // Code page the target's programs really wrote in
+    text_decoding: TextDecoding,
+    // Whitespace and line endings of saved logs
+    text_options: TextOptions,
+    previous_output: Option<(u32, Vec<String>)>,
+
+    // Lines that scrolled out of the snapshot, spilled to disk past the RAM cap
+    history: Scrollback,
+    history_ram_cap_mb: usize,
+    // Scrolled-off lines kept by the worker, and the part of them on screen
+    worker_history_lines: usize,
+    worker_history: WorkerHistoryView,
+    history_search: String,
+    history_hits: Vec<usize>,
     output_update_timestamp: Option<Instant>,
     lines_to_display: usize,
+    read_full_buffer: bool,
+    read_viewport: bool,
+    keep_trailing: bool,
     refresh_interval_ms: u64,
     auto_scroll: bool,
 
+    // Keep capture out of the way of the monitored workloads
+    low_priority_capture: bool,
+    // Read when the console draws rather than on every refresh interval
+    event_driven_capture: bool,
+    // Stay attached between polls rather than attaching for each one
+    persistent_attach: bool,
+    // Poll interval that speeds up while output changes, within these bounds
+    adaptive_poll: bool,
+    adaptive_min_ms: u64,
+    adaptive_max_ms: u64,
+    // Reattach retries before a poll failure counts as a disconnect
+    reconnect_attempts: u32,
+    poll_budget_enabled: bool,
+    poll_budget_ms: u64,
+    // Cells per ReadConsoleOutputW call for huge buffers
+    read_chunk_cells: usize,
+    // Step timings of the last and the slowest local poll
+    poll_timings: Option<PollTimings>,
+    slowest_poll: Option<PollTimings>,
+    // Latest activity figures from the worker
+    worker_stats: Option<WorkerStats>,
+    // Describe input records instead of writing them, for debugging targets
+    // that ignore input
+    dry_run: bool,
+    dry_run_log: Vec<String>,
+
     // Input state
     command_input: String,
     command_input_top: String,
@@ -29,10 +170,144 @@ pub struct RemoteConApp {
     attached_pid: Option<u32>,
     attach_error: Option<String>,
 
+    // Elevated helper used for admin consoles (launched on demand)
+    helper: Option<HelperClient>,
+    // Helper being launched, and the PID to attach once it connects
+    helper_launch: Option<(u32, Receiver<anyhow::Result<HelperClient>>)>,
+    attached_elevated: bool,
+
+    // Target health from the worker
+    target_alive: Option<bool>,
+    target_last_seen: Option<Instant>,
+    // Last sign of life from the worker thread itself
+    worker_heartbeat: Option<Instant>,
+    // Worker told to stop reading until resumed
+    updates_paused: bool,
+    // Detached target whose last screen is still shown
+    final_screen_of: Option<u32>,
+    // One-shot reads some seconds after a command, and the last one's text
+    capture_after_send: bool,
+    capture_delay_secs: f32,
+    last_capture: Option<(Instant, String)>,
+    // Rectangle of the buffer to sample, and what it held when last read
+    region: SmallRect,
+    last_region: Option<Result<String, String>>,
+    // Pattern the worker filters output with, as typed and as applied
+    output_filter: String,
+    output_filter_context: usize,
+    output_filter_applied: Option<FilterSpec>,
+
+    // Other processes on the target's console, and a pending ownership change
+    console_members: Vec<u32>,
+    console_title: Option<String>,
+    // Selection in the target's own window, which pauses its output
+    target_selection: Option<ConsoleSelection>,
+    console_modes: Option<ConsoleModes>,
+    // Target font and geometry, and how many columns the output area fits
+    console_metrics: Option<ConsoleMetrics>,
+    alternate_screen: bool,
+    // Input and output code page of the target console
+    code_pages: Option<(u32, u32)>,
+    cursor_shape: Option<CursorShape>,
+    viewer_cols: Option<usize>,
+    owner_change: Option<(u32, Vec<u32>)>,
+    follow_new_owner: bool,
+
+    // Keep-alive input for targets that time out when idle
+    #[cfg(feature = "automation")]
+    keep_alive_settings: KeepAliveSettings,
+    #[cfg(feature = "automation")]
+    keep_alive: KeepAlive,
+
+    // Automatic answers to prompts such as "Press any key to continue"
+    #[cfg(feature = "automation")]
+    responder_settings: ResponderSettings,
+    #[cfg(feature = "automation")]
+    responder: Responder,
+
+    // Shell on this machine hosted in our own pseudoconsole
+    local_terminal: Option<LocalTerminal>,
+
+    // Key or numpad code for the Alt+ buttons
+    alt_input: String,
+    // Send clicks on the output to the target instead of selecting lines
+    forward_mouse: bool,
+    // Forward every keystroke typed in the viewer to the target
+    terminal_mode: bool,
+    // Press Enter after the EOF button's Ctrl+Z
+    eof_enter: bool,
+
+    // Multi-line paste sent one line at a time
+    paste_text: String,
+    paste_delay_ms: u64,
+    paste: Option<LinePaste>,
+    // Commands entered while detached, sent in order on the next attach
+    command_queue: Vec<String>,
+    // User variables for {name} placeholders in commands, one name=value per line
+    template_vars: String,
+    // Denylist checked before a typed or pasted command goes out
+    command_guard: CommandGuard,
+    // Command awaiting confirmation: text, rule pattern, whether from the top input
+    guard_pending: Option<(String, String, bool)>,
+    // Pause after each typed key, 0 to type in one burst
+    typing_delay_ms: u64,
+    // End commands with Ctrl+Enter (LF) instead of Enter (CR)
+    enter_sends_lf: bool,
+    send_file_path: String,
+    send_file_wait_prompt: bool,
+
+    // Markdown scratchpad for the attached session
+    notes: Option<SessionNotes>,
+    notes_preview: bool,
+    notes_cache: egui_commonmark::CommonMarkCache,
+
+    // Every command sent, across sessions, with replay
+    #[cfg(feature = "automation")]
+    journal: Journal,
+    #[cfg(feature = "automation")]
+    journal_pid_filter: Option<u32>,
+    #[cfg(feature = "automation")]
+    journal_window_minutes: u32,
+    #[cfg(feature = "automation")]
+    journal_selected: BTreeSet<usize>,
+    #[cfg(feature = "automation")]
+    replay_target: Option<u32>,
+    // Replay waiting for its target to be attached
+    #[cfg(feature = "automation")]
+    pending_replay: Option<(u32, Vec<String>, Instant)>,
+
     // Status bar
     status_message: String,
     last_error: Option<String>,
 
+    // Named settings sets; the storage backend and others are kept per profile
+    profiles: ProfileStore,
+    new_profile_name: String,
+
+    // Where saved logs and traces go
+    storage: StorageConfig,
+    storage_writer: StorageWriter,
+
+    // Auto-reattach after the target restarts
+    auto_reattach: bool,
+    reattach_match: ReattachMatch,
+    reattach_pattern: String,
+    reattach_lost_pid: Option<u32>,
+    /// When the target was lost, in seconds since the Unix epoch; consoles
+    /// that were already running then are not candidates
+    reattach_lost_at: u64,
+    last_reattach_scan: Option<Instant>,
+    reattach_scan: Option<Receiver<anyhow::Result<Vec<CmdProcessInfo>>>>,
+
+    // Remembered window placement, restored once the window exists
+    placements: PlacementStore,
+    placement_restored: bool,
+    last_window_rect: Option<(egui::Rect, bool)>,
+    window_moved_at: Option<Instant>,
+
+    // Attach hooks writing session starts and ends to the audit log
+    session_hooks: Vec<HookId>,
+
     // Context menu state
     show_context_menu: bool,
     context_menu_pid: Option<u32>,
@@ -44,18 +319,133 @@ impl Default for RemoteConApp {
             cmd_processes: Vec::new(),
             selected_pid: None,
             show_refresh_error: None,
-            worker: None,
+            workers: WorkerPool::new(),
             console_output: Vec::new(),
+            console_colors: Vec::new(),
+            console_cursor: None,
+            output_selection: None,
+            jump_to_line: None,
+            colors: ColorSettings::default(),
+            decode_vt: true,
+            text_decoding: TextDecoding::AsShown,
+            text_options: TextOptions::default(),
+            previous_output: None,
+            history: Scrollback::new(DEFAULT_RAM_CAP),
+            history_ram_cap_mb: DEFAULT_RAM_CAP / (1024 * 1024),
+            worker_history_lines: DEFAULT_HISTORY_LINES,
+            worker_history: WorkerHistoryView::default(),
+            history_search: String::new(),
+            history_hits: Vec::new(),
             output_update_timestamp: None,
             lines_to_display: 400,
+            read_full_buffer: false,
+            read_viewport: false,
+            keep_trailing: false,
             refresh_interval_ms: 500,
             auto_scroll: true,
+            low_priority_capture: false,
+            event_driven_capture: true,
+            persistent_attach: false,
+            adaptive_poll: false,
+            adaptive_min_ms: AdaptiveInterval::default().min.as_millis() as u64,
+            adaptive_max_ms: AdaptiveInterval::default().max.as_millis() as u64,
+            reconnect_attempts: WorkerConfig::default().reconnect_attempts,
+            poll_budget_enabled: false,
+            poll_budget_ms: 50,
+            read_chunk_cells: DEFAULT_CHUNK_CELLS,
+            poll_timings: None,
+            worker_stats: None,
+            slowest_poll: None,
+            dry_run: false,
+            dry_run_log: Vec::new(),
             command_input: String::new(),
             command_input_top: String::new(),
             attached_pid: None,
             attach_error: None,
+            helper: None,
+            helper_launch: None,
+            attached_elevated: false,
+            target_alive: None,
+            target_last_seen: None,
+            worker_heartbeat: None,
+            updates_paused: false,
+            final_screen_of: None,
+            capture_after_send: false,
+            capture_delay_secs: 3.0,
+            last_capture: None,
+            region: SmallRect::row(0, 0, 79),
+            last_region: None,
+            output_filter: String::new(),
+            output_filter_context: 2,
+            output_filter_applied: None,
+            console_members: Vec::new(),
+            console_title: None,
+            target_selection: None,
+            console_modes: None,
+            console_metrics: None,
+            alternate_screen: false,
+            code_pages: None,
+            cursor_shape: None,
+            viewer_cols: None,
+            owner_change: None,
+            follow_new_owner: false,
+            #[cfg(feature = "automation")]
+            keep_alive_settings: KeepAliveSettings::default(),
+            #[cfg(feature = "automation")]
+            keep_alive: KeepAlive::new(),
+            #[cfg(feature = "automation")]
+            responder_settings: ResponderSettings::default(),
+            #[cfg(feature = "automation")]
+            responder: Responder::default(),
+            local_terminal: None,
+            alt_input: String::new(),
+            forward_mouse: false,
+            terminal_mode: false,
+            eof_enter: true,
+            paste_text: String::new(),
+            paste_delay_ms: DEFAULT_LINE_DELAY.as_millis() as u64,
+            paste: None,
+            command_queue: Vec::new(),
+            template_vars: String::new(),
+            command_guard: CommandGuard::default(),
+            guard_pending: None,
+            typing_delay_ms: 0,
+            enter_sends_lf: false,
+            send_file_path: String::new(),
+            send_file_wait_prompt: true,
+            notes: None,
+            notes_preview: false,
+            notes_cache: egui_commonmark::CommonMarkCache::default(),
+            #[cfg(feature = "automation")]
+            journal: Journal::default(),
+            #[cfg(feature = "automation")]
+            journal_pid_filter: None,
+            #[cfg(feature = "automation")]
+            journal_window_minutes: 0,
+            #[cfg(feature = "automation")]
+            journal_selected: BTreeSet::new(),
+            #[cfg(feature = "automation")]
+            replay_target: None,
+            #[cfg(feature = "automation")]
+            pending_replay: None,
             status_message: "Not attached".to_string(),
             last_error: None,
+            profiles: ProfileStore::default(),
+            new_profile_name: String::new(),
+            storage: StorageConfig::default(),
+            storage_writer: StorageWriter::new(),
+            auto_reattach: false,
+            reattach_match: ReattachMatch::WindowTitle,
+            reattach_pattern: String::new(),
+            reattach_lost_pid: None,
+            reattach_lost_at: 0,
+            last_reattach_scan: None,
+            reattach_scan: None,
+            placements: PlacementStore::default(),
+            placement_restored: false,
+            last_window_rect: None,
+            window_moved_at: None,
+            session_hooks: audit::watch_sessions(),
             show_context_menu: false,
             context_menu_pid: None,
         }
@@ -64,13 +454,53 @@ impl Default for RemoteConApp {
 
 impl RemoteConApp {
     /// Create a new application instance
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let mut app = Self::default();
+    pub fn new(_cc: &eframe::CreationContext<'_>, intent: StartupIntent) -> Self {
+        let mut app = Self {
+            placements: PlacementStore::load(),
+            profiles: ProfileStore::load(),
+            ..Self::default()
+        };
+        app.load_profile();
+        #[cfg(feature = "automation")]
+        {
+            app.journal = Journal::load();
+        }
         // Initial process enumeration
         app.refresh_process_list();
+        app.apply_startup_intent(intent);
         app
     }
 
+    /// Act on command line flags: attach if asked to
+    fn apply_startup_intent(&mut self, intent: StartupIntent) {
+        if let Some(error) = intent.error {
+            self.attach_error = Some(format!("Command line: {}", error));
+            return;
+        }
+        if let Some(title) = intent.attach_title {
+            // Also arm auto-reattach matching with the same pattern
+            self.reattach_match = ReattachMatch::WindowTitle;
+            self.reattach_pattern = title.clone();
+            let found = self.cmd_processes.iter()
+                .find(|p| p.attachable && wildcard_match(&title, p.window_title.as_deref().unwrap_or("")))
+                .map(|p| p.pid);
+            match found {
+                Some(pid) => self.selected_pid = Some(pid),
+                None => {
+                    self.attach_error = Some(format!("No console window title matches \"{}\"", title));
+                    return;
+                }
+            }
+        }
+        if let Some(pid) = intent.attach_pid {
+            self.selected_pid = Some(pid);
+        }
+
+        if self.selected_pid.is_some() {
+            self.attach_to_console();
+        }
+    }
+
     /// Refresh the list of cmd.exe processes
     fn refresh_process_list(&mut self) {
         match enumerate_cmd_processes() {
@@ -87,230 +517,648 @@ impl RemoteConApp {
     /// Attach to the selected console
     fn attach_to_console(&mut self) {
         if let Some(pid) = self.selected_pid {
+            repro::record(TraceKind::Ui, &format!("attach pid={}", pid));
+            self.prefill_reattach_pattern(pid);
+            self.reattach_lost_pid = None;
+
+            // Swap targets in place when a regular attachment is already live
+            if self.attached_pid.is_some_and(|p| p != pid) && !self.attached_elevated
+                && let Some(worker) = self.worker()
+                && worker.send(UiMessage::SwitchTarget(pid)).is_ok()
+            {
+                self.status_message = format!("Switching to PID {}...", pid);
+                return;
+            }
+
             // Detach from previous if any
             if self.attached_pid.is_some() {
                 self.detach_from_console();
             }
 
             // Create worker for this PID
-            let config = WorkerConfig {
-                interval: Duration::from_millis(self.refresh_interval_ms),
-                lines: self.lines_to_display,
-            };
-
-            self.worker = Some(ConsoleWorker::new(config));
-
-            // Send attach message
-            if let Some(worker) = &self.worker {
-                match worker.send(UiMessage::Attach(pid)) {
-                    Ok(()) => {
-                        self.attached_pid = Some(pid);
-                        self.attach_error = None;
-                        self.status_message = format!("Attaching to PID {}...", pid);
-                    }
-                    Err(e) => {
-                        self.attach_error = Some(format!("Failed to send attach message: {}", e));
-                        self.worker = None;
-                    }
+            let config = self.worker_config();
+
+            match self.workers.open(pid, config, None) {
+                Ok(()) => {
+                    self.start_session(pid);
+                    self.attached_elevated = false;
+                    self.worker_heartbeat = Some(Instant::now());
+                    self.attach_error = None;
+                    self.status_message = format!("Attaching to PID {}...", pid);
+                }
+                Err(e) => {
+                    self.attach_error = Some(format!("Failed to send attach message: {}", e));
                 }
             }
         }
     }
 
-    /// Detach from the current console
-    fn detach_from_console(&mut self) {
-        if let Some(worker) = &self.worker {
-            let _ = worker.send(UiMessage::Detach);
+    /// Attach to the selected elevated console through the elevated helper
+    fn attach_elevated(&mut self) {
+        let Some(pid) = self.selected_pid else {
+            return;
+        };
+        repro::record(TraceKind::Ui, &format!("attach_elevated pid={}", pid));
+
+        if self.attached_pid.is_some() {
+            self.detach_from_console();
         }
-        self.worker = None;
-        self.attached_pid = None;
-        self.console_output.clear();
-        self.status_message = "Not attached".to_string();
-    }
 
-    /// Send a command to the console
-    fn send_command(&mut self) {
-        if self.attached_pid.is_none() {
-            self.last_error = Some("Not attached to any console".to_string());
+        // Launch the helper on first use (triggers a UAC prompt); the attach
+        // goes on in `poll_helper_launch` once the helper connected
+        if self.helper.is_none() {
+            match &mut self.helper_launch {
+                Some((pending, _)) => *pending = pid,
+                None => self.helper_launch = Some((pid, HelperClient::launch_in_background())),
+            }
+            self.status_message = "Waiting for the elevated helper (confirm the UAC prompt)...".to_string();
             return;
         }
+        self.open_elevated(pid);
+    }
 
-        let command = self.command_input.trim();
-        if command.is_empty() {
+    /// Finish attaching through a helper that was being launched
+    fn poll_helper_launch(&mut self) {
+        let Some((pid, launch)) = &self.helper_launch else {
             return;
+        };
+        let pid = *pid;
+        let result = match launch.try_recv() {
+            Ok(result) => result,
+            Err(e) if e.is_empty() => return,
+            Err(_) => Err(anyhow::anyhow!("Helper launch thread ended")),
+        };
+        self.helper_launch = None;
+
+        match result {
+            Ok(helper) => {
+                self.helper = Some(helper);
+                self.open_elevated(pid);
+            }
+            Err(e) => {
+                self.attach_error = Some(format!("Elevated helper unavailable: {}", e));
+            }
         }
+    }
 
-        let pid = self.attached_pid.unwrap();
+    /// Start a session on `pid` through the connected elevated helper
+    fn open_elevated(&mut self, pid: u32) {
+        let Some(helper) = self.helper.clone() else {
+            return;
+        };
 
-        // Attach, send command, detach
-        match attach_to_console(pid) {
+        let config = self.worker_config();
+        match self.workers.open(pid, config, Some(helper)) {
             Ok(()) => {
-                match send_command(command) {
-                    Ok(()) => {
-                        self.command_input.clear();
-                        self.last_error = None;
-                    }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to send command: {}", e));
-                    }
-                }
-                let _ = detach_from_console();
+                self.start_session(pid);
+                self.attached_elevated = true;
+                self.worker_heartbeat = Some(Instant::now());
+                self.attach_error = None;
+                self.status_message = format!("Attaching to PID {} (elevated)...", pid);
             }
             Err(e) => {
-                self.last_error = Some(format!("Failed to attach for command: {}", e));
+                self.attach_error = Some(format!("Failed to send attach message: {}", e));
             }
         }
     }
 
-    /// Send a command from the top input field to the console
-    fn send_command_from_top(&mut self) {
-        if self.attached_pid.is_none() {
-            self.last_error = Some("Not attached to any console".to_string());
+    /// How long the attached session's worker has been silent, once that is
+    /// more than two heartbeats or refresh intervals: a Win32 call it made is hanging
+    fn worker_stalled(&self) -> Option<Duration> {
+        let silent = self.worker_heartbeat?.elapsed();
+        let allowed = HEARTBEAT_INTERVAL.max(Duration::from_millis(self.refresh_interval_ms)) * 2;
+        (silent > allowed).then_some(silent)
+    }
+
+    /// Replace a stalled worker with a fresh one attached to the same target
+    fn restart_worker(&mut self) {
+        let Some(pid) = self.attached_pid else {
             return;
+        };
+        let elevated = self.attached_elevated;
+        repro::record(TraceKind::Ui, &format!("restart_worker pid={}", pid));
+        self.detach_from_console();
+        self.selected_pid = Some(pid);
+        if elevated {
+            self.attach_elevated();
+        } else {
+            self.attach_to_console();
+        }
+    }
+
+    /// Freeze the output for reading, or pick up where the target has got to
+    fn set_updates_paused(&mut self, paused: bool) {
+        repro::record(TraceKind::Ui, if paused { "pause" } else { "resume" });
+        if let Some(worker) = self.worker()
+            && worker.send(if paused { UiMessage::Pause } else { UiMessage::Resume }).is_ok()
+        {
+            self.updates_paused = paused;
+        }
+    }
+
+    /// Ask the worker for one read after the capture delay, paused or not
+    fn schedule_capture(&mut self) {
+        let delay = Duration::from_secs_f32(self.capture_delay_secs);
+        repro::record(TraceKind::Ui, &format!("read_once_after {:?}", delay));
+        self.dispatch_input(UiMessage::ReadOnceAfter(delay));
+    }
+
+    /// Worker settings as chosen in the UI
+    fn worker_config(&self) -> WorkerConfig {
+        WorkerConfig {
+            interval: Duration::from_millis(self.refresh_interval_ms),
+            lines: self.lines_to_display,
+            window: self.read_window(),
+            low_priority: self.low_priority_capture,
+            poll_budget: self.poll_budget(),
+            adaptive: self.adaptive_interval(),
+            event_driven: self.event_driven_capture,
+            reconnect_attempts: self.reconnect_attempts,
+            decode_vt: self.decode_vt,
+            keep_trailing: self.keep_trailing,
+            text_decoding: self.text_decoding,
+            history_lines: self.worker_history_lines,
+            persistent_attach: self.persistent_attach,
+            filter: self.output_filter_applied.clone(),
+            input: InputSettings {
+                typing_delay: Duration::from_millis(self.typing_delay_ms),
+                enter_sends_lf: self.enter_sends_lf,
+                dry_run: self.dry_run,
+            },
+        }
+    }
+
+    /// Hand the attached session's worker the current settings in one go
+    fn reconfigure_worker(&self) {
+        if let Some(worker) = self.worker() {
+            let _ = worker.send(UiMessage::Reconfigure(self.worker_config()));
+        }
+    }
+
+    /// Worker of the attached session
+    fn worker(&self) -> Option<&ConsoleWorker> {
+        self.attached_pid.and_then(|pid| self.workers.get(pid))
+    }
+
+    /// Helper to route sends through when attached elevated
+    fn elevated_helper(&self) -> Option<HelperClient> {
+        if self.attached_elevated {
+            self.helper.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Detach from the current console
+    fn detach_from_console(&mut self) {
+        repro::record(TraceKind::Ui, "detach");
+        let detached = self.attached_pid.take();
+        if let Some(pid) = detached {
+            self.workers.close(pid);
         }
+        self.attached_elevated = false;
+        self.target_alive = None;
+        self.target_last_seen = None;
+        self.worker_heartbeat = None;
+        self.updates_paused = false;
+        self.last_capture = None;
+        self.last_region = None;
+        self.worker_stats = None;
+        self.worker_history = WorkerHistoryView::default();
+        self.console_members.clear();
+        self.console_title = None;
+        self.target_selection = None;
+        self.console_modes = None;
+        self.console_metrics = None;
+        self.alternate_screen = false;
+        self.code_pages = None;
+        self.cursor_shape = None;
+        self.owner_change = None;
+        self.paste = None;
+        // Keep the last screen up; the worker's final read refreshes it
+        self.final_screen_of = detached.or(self.final_screen_of);
+        self.output_selection = None;
+        self.console_cursor = None;
+        self.status_message = "Not attached".to_string();
+    }
+
+    /// Note a new session on `pid`, dropping the last screen of any earlier one
+    fn start_session(&mut self, pid: u32) {
+        self.attached_pid = Some(pid);
+        if self.final_screen_of.take().is_some() {
+            self.console_output.clear();
+            self.console_colors.clear();
+        }
+    }
+
+    /// Send a command to the console
+    fn send_command(&mut self) {
+        self.submit_command(false);
+    }
+
+    /// Send a command from the top input field to the console
+    fn send_command_from_top(&mut self) {
+        self.submit_command(true);
+    }
 
-        let command = self.command_input_top.trim();
+    /// Send the command typed in the bottom or (`top`) the top input box,
+    /// queueing it while no target is there to take it
+    fn submit_command(&mut self, top: bool) {
+        let input = if top { &self.command_input_top } else { &self.command_input };
+        let command = input.trim().to_string();
         if command.is_empty() {
             return;
         }
+        // The guard sees the text that goes out, placeholders filled in; a
+        // queued command's placeholders are checked again once filled
+        let command = if self.should_queue() {
+            command
+        } else {
+            match self.expand_command(&command) {
+                Ok(command) => command,
+                Err(e) => {
+                    self.last_error = Some(e.to_string());
+                    return;
+                }
+            }
+        };
+        if self.guard_allows(&command, top) {
+            self.release_command(command, top);
+        }
+    }
 
-        let pid = self.attached_pid.unwrap();
+    /// Send a command that passed the guard, or queue it while no target is
+    /// there to take it, and clear the input box it came from
+    fn release_command(&mut self, command: String, top: bool) {
+        if self.should_queue() {
+            self.queue_command(command);
+        } else {
+            repro::record(TraceKind::Ui, &format!("send_command text={}", repro::scrub(&command)));
+            self.dispatch_input(UiMessage::SendCommand(command));
+        }
+        if top {
+            self.command_input_top.clear();
+        } else {
+            self.command_input.clear();
+        }
+    }
 
-        // Attach, send command, detach
-        match attach_to_console(pid) {
-            Ok(()) => {
-                match send_command(command) {
-                    Ok(()) => {
-                        self.command_input_top.clear();
-                        self.last_error = None;
-                    }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to send command: {}", e));
+    /// Hand an input operation to the attached session's worker, which
+    /// attaches for it between polls and reports back with `InputSent`.
+    /// False when there is no worker to take it.
+    fn dispatch_input(&mut self, msg: UiMessage) -> bool {
+        // Anything typed on the target keeps it awake as well as a keep-alive would
+        #[cfg(feature = "automation")]
+        let typed = !matches!(
+            msg,
+            UiMessage::TakeSnapshot(_) | UiMessage::ReadRegion(_) | UiMessage::ReadOnceAfter(_)
+                | UiMessage::SetQuickEdit(_) | UiMessage::ClearScreen { .. }
+        );
+        match self.worker() {
+            Some(worker) => match worker.send(msg) {
+                Ok(()) => {
+                    #[cfg(feature = "automation")]
+                    if typed {
+                        self.keep_alive.reset();
                     }
+                    true
                 }
-                let _ = detach_from_console();
-            }
-            Err(e) => {
-                self.last_error = Some(format!("Failed to attach for command: {}", e));
+                Err(e) => {
+                    self.last_error = Some(e.to_string());
+                    false
+                }
+            },
+            None => {
+                self.last_error = Some("Not attached to any console".to_string());
+                false
             }
         }
     }
 
-    /// Send Ctrl+C to the console
-    fn send_ctrl_c(&mut self) {
-        if self.attached_pid.is_none() {
-            self.last_error = Some("Not attached to any console".to_string());
+    /// Whether commands should wait in the queue: nothing is attached, or the
+    /// attached target has gone away
+    fn should_queue(&self) -> bool {
+        self.attached_pid.is_none() || self.target_alive == Some(false)
+    }
+
+    /// Fill in `{pid}`, `{date}`, `{time}`, `{clipboard}`, `{selection}` and
+    /// the user's own variables
+    fn expand_command(&self, command: &str) -> anyhow::Result<String> {
+        let user = parse_variables(&self.template_vars)?;
+        expand(command, |name| match name {
+            "pid" => self.attached_pid.map(|p| p.to_string())
+                .ok_or_else(|| anyhow::anyhow!("{{pid}} needs an attached console")),
+            "date" => Ok(chrono::Local::now().format("%Y-%m-%d").to_string()),
+            "time" => Ok(chrono::Local::now().format("%H:%M:%S").to_string()),
+            "clipboard" => clipboard_text(),
+            "selection" => self.selected_output_rows()
+                .and_then(|rows| self.console_output.get(rows))
+                .map(|lines| lines.iter().map(|l| l.trim()).collect::<Vec<_>>().join(" "))
+                .ok_or_else(|| anyhow::anyhow!("{{selection}} needs selected output lines")),
+            _ => user.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone())
+                .ok_or_else(|| anyhow::anyhow!("Unknown variable {{{}}}", name)),
+        })
+    }
+
+    /// Hold a command until an attachment is established
+    fn queue_command(&mut self, command: String) {
+        repro::record(TraceKind::Ui, &format!("queue_command text={}", repro::scrub(&command)));
+        self.command_queue.push(command);
+        self.last_error = None;
+        self.status_message = format!("{} commands queued until attached", self.command_queue.len());
+    }
+
+    /// Add a sent command to the journal under the target's current window title
+    #[cfg(feature = "automation")]
+    fn journal_command(&mut self, pid: u32, command: &str) {
+        let session = self.cmd_processes.iter()
+            .find(|p| p.pid == pid)
+            .and_then(|p| p.window_title.clone())
+            .unwrap_or_default();
+        if let Err(e) = self.journal.record(pid, &session, command) {
+            self.last_error = Some(format!("Failed to write command journal: {}", e));
+        }
+    }
+
+    /// Send the selected journal commands, oldest first, to the replay
+    /// target, attaching to it first when it is not the current target
+    #[cfg(feature = "automation")]
+    fn replay_journal_selection(&mut self) {
+        let Some(target) = self.replay_target else {
+            return;
+        };
+        let commands: Vec<String> = self.journal_selected.iter()
+            .filter_map(|&i| self.journal.entries().get(i))
+            .map(|e| e.command.clone())
+            .collect();
+        if commands.is_empty() || !self.guard_allows_batch(&commands, "replaying") {
             return;
         }
+        repro::record(TraceKind::Ui, &format!("replay_journal pid={} count={}", target, commands.len()));
+        let _ = audit::log(Some(target), &format!("journal replay of {} commands", commands.len()));
 
-        let pid = self.attached_pid.unwrap();
+        if self.attached_pid != Some(target) {
+            self.selected_pid = Some(target);
+            self.attach_to_console();
+        }
+        self.pending_replay = Some((target, commands, Instant::now()));
+    }
 
-        match attach_to_console(pid) {
-            Ok(()) => {
-                match send_ctrl_c() {
-                    Ok(()) => {
-                        self.last_error = None;
-                    }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to send Ctrl+C: {}", e));
-                    }
-                }
-                let _ = detach_from_console();
-            }
-            Err(e) => {
-                self.last_error = Some(format!("Failed to attach for Ctrl+C: {}", e));
+    /// Hand a pending replay to the worker once its target is attached and
+    /// answering; each command sent is journaled as its `InputSent` arrives
+    #[cfg(feature = "automation")]
+    fn poll_pending_replay(&mut self) {
+        let Some((target, _, since)) = &self.pending_replay else {
+            return;
+        };
+        if self.attached_pid != Some(*target) || self.target_alive != Some(true) {
+            if since.elapsed() >= REPLAY_ATTACH_TIMEOUT {
+                self.last_error = Some(format!("Replay dropped: PID {} could not be attached", target));
+                self.pending_replay = None;
             }
+            return;
+        }
+        let Some((target, commands, _)) = self.pending_replay.take() else {
+            return;
+        };
+        let count = commands.len();
+        let options = BatchOptions { wait_for_prompt: None, stop_on_error: true };
+        if self.dispatch_input(UiMessage::SendCommands { commands, options }) {
+            self.status_message = format!("Replaying {} commands to PID {}", count, target);
         }
     }
 
-    /// Send Ctrl+J (Line Feed - \n, 0x0A) to the console
-    fn send_ctrl_j(&mut self) {
-        if self.attached_pid.is_none() {
-            self.last_error = Some("Not attached to any console".to_string());
+    /// Type the input box's text on the target without pressing Enter, so
+    /// someone at the target can review and confirm it
+    fn type_text(&mut self) {
+        let text = self.command_input.clone();
+        if text.is_empty() {
             return;
         }
+        repro::record(TraceKind::Ui, &format!("type_text text={}", repro::scrub(&text)));
+        if self.dispatch_input(UiMessage::SendText(text)) {
+            self.command_input.clear();
+        }
+    }
 
-        let pid = self.attached_pid.unwrap();
+    /// Send Ctrl+C to the console
+    fn send_ctrl_c(&mut self) {
+        repro::record(TraceKind::Ui, "send_ctrl_c");
+        self.dispatch_input(UiMessage::SendCtrlC);
+    }
 
-        match attach_to_console(pid) {
-            Ok(()) => {
-                // Send Ctrl+J (Line Feed - 0x0A)
-                match send_control_char(0x0A) {
-                    Ok(()) => {
-                        self.last_error = None;
-                    }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to send Ctrl+J: {}", e));
-                    }
+    /// Send Ctrl+<letter> to the console
+    fn send_ctrl_key(&mut self, letter: char) {
+        repro::record(TraceKind::Ui, &format!("send_ctrl_key {}", letter));
+        self.dispatch_input(UiMessage::SendCtrlKey(letter));
+    }
+
+    /// Turn Quick-Edit on the target on or off; with it off, a stray click in
+    /// the target's window can no longer pause its output
+    fn set_target_quick_edit(&mut self, enable: bool) {
+        repro::record(TraceKind::Ui, &format!("set_quick_edit {}", enable));
+        self.dispatch_input(UiMessage::SetQuickEdit(enable));
+    }
+
+    /// Clear the target's screen, with `cls` through its shell or by blanking
+    /// the buffer directly, which also works while a program runs
+    fn clear_target(&mut self, direct: bool) {
+        repro::record(TraceKind::Ui, &format!("clear_target direct={}", direct));
+        self.dispatch_input(UiMessage::ClearScreen { direct });
+    }
+
+    /// Send end-of-input (Ctrl+Z, plus Enter if chosen) to the console
+    fn send_eof(&mut self) {
+        let press_enter = self.eof_enter;
+        repro::record(TraceKind::Ui, &format!("send_eof enter={}", press_enter));
+        self.dispatch_input(UiMessage::SendEof { press_enter });
+    }
+
+    /// Press a navigation or function key on the console
+    fn send_key(&mut self, key: VirtualKey) {
+        repro::record(TraceKind::Ui, &format!("send_key {}", key.label()));
+        self.dispatch_input(UiMessage::SendKey(key));
+    }
+
+    /// In terminal mode, take this frame's keyboard events away from egui
+    /// and type them on the target. Text boxes keep their keys while focused.
+    fn forward_terminal_keys(&mut self, ctx: &egui::Context) {
+        if !self.terminal_mode || self.attached_pid.is_none() || ctx.wants_keyboard_input() {
+            return;
+        }
+        let mut strokes = Vec::new();
+        ctx.input_mut(|i| {
+            i.events.retain(|event| match terminal_strokes(event) {
+                Some(keys) => {
+                    strokes.extend(keys);
+                    false
                 }
-                let _ = detach_from_console();
+                None => true,
+            });
+        });
+
+        #[cfg(feature = "automation")]
+        if !strokes.is_empty() {
+            self.keep_alive.note_typing();
+        }
+
+        // Ctrl+C goes out as the signal, like the Ctrl+C button, so programs
+        // with processed input stop; everything else is typed in order
+        for chunk in strokes.split_inclusive(|k| *k == KeyStroke::Ctrl('C')) {
+            let (keys, ctrl_c) = match chunk.split_last() {
+                Some((KeyStroke::Ctrl('C'), keys)) => (keys, true),
+                _ => (chunk, false),
+            };
+            if !keys.is_empty() {
+                self.send_strokes(keys);
             }
-            Err(e) => {
-                self.last_error = Some(format!("Failed to attach for Ctrl+J: {}", e));
+            if ctrl_c {
+                self.send_ctrl_c();
             }
         }
     }
 
+    /// Type key strokes on the target
+    fn send_strokes(&mut self, keys: &[KeyStroke]) {
+        self.dispatch_input(UiMessage::SendKeys { keys: keys.to_vec(), label: "keys".to_string() });
+    }
+
+    /// Send the Alt+ input as Alt+<key>, or as an Alt+numpad code
+    fn send_alt(&mut self, numpad: bool) {
+        let input = self.alt_input.trim().to_string();
+        let Some(first) = input.chars().next() else {
+            return;
+        };
+        repro::record(TraceKind::Ui, &format!("send_alt numpad={} input={}", numpad, input));
+        self.dispatch_input(if numpad { UiMessage::SendAltCode(input) } else { UiMessage::SendAltKey(first) });
+    }
+
+    /// Whether output clicks can reach the target: the output must mirror its
+    /// visible window and the program must be reading mouse input
+    fn can_forward_mouse(&self) -> bool {
+        (self.read_viewport || self.alternate_screen)
+            && self.console_modes.is_some_and(|m| m.mouse_input())
+    }
+
+    /// Click `button` at a cell of the target's visible window
+    fn send_mouse_click(&mut self, x: usize, y: usize, button: u32) {
+        repro::record(TraceKind::Ui, &format!("send_mouse_click {} {} {}", x, y, button));
+        self.dispatch_input(UiMessage::SendMouseClick { x, y, button });
+    }
+
+    /// Send Ctrl+J (Line Feed - \n, 0x0A) to the console
+    fn send_ctrl_j(&mut self) {
+        repro::record(TraceKind::Ui, "send_ctrl_j");
+        self.dispatch_input(UiMessage::SendControlChars(vec![0x0A]));
+    }
+
     /// Send Ctrl+M (Carriage Return - \r, 0x0D) to the console
     fn send_ctrl_m(&mut self) {
-        if self.attached_pid.is_none() {
-            self.last_error = Some("Not attached to any console".to_string());
-            return;
-        }
+        repro::record(TraceKind::Ui, "send_ctrl_m");
+        self.dispatch_input(UiMessage::SendControlChars(vec![0x0D]));
+    }
 
-        let pid = self.attached_pid.unwrap();
+    /// Send \n\r (Line Feed + Carriage Return) to the console
+    fn send_newline_carriage_return(&mut self) {
+        repro::record(TraceKind::Ui, "send_newline_carriage_return");
+        self.dispatch_input(UiMessage::SendControlChars(vec![0x0A, 0x0D]));
+    }
 
-        match attach_to_console(pid) {
-            Ok(()) => {
-                // Send Ctrl+M (Carriage Return - 0x0D)
-                match send_control_char(0x0D) {
-                    Ok(()) => {
-                        self.last_error = None;
-                    }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to send Ctrl+M: {}", e));
-                    }
-                }
-                let _ = detach_from_console();
+    /// Start a local shell in a pseudoconsole and attach to it like any target
+    fn open_local_terminal(&mut self) {
+        repro::record(TraceKind::Ui, "open_local_terminal");
+        match LocalTerminal::spawn(DEFAULT_SHELL) {
+            Ok(terminal) => {
+                let pid = terminal.pid();
+                let _ = audit::log(Some(pid), &format!("local terminal started {}", DEFAULT_SHELL));
+                self.local_terminal = Some(terminal);
+                self.refresh_process_list();
+                self.selected_pid = Some(pid);
+                self.attach_to_console();
             }
             Err(e) => {
-                self.last_error = Some(format!("Failed to attach for Ctrl+M: {}", e));
+                self.attach_error = Some(format!("Failed to start local terminal: {}", e));
             }
         }
     }
 
-    /// Send \n\r (Line Feed + Carriage Return) to the console
-    fn send_newline_carriage_return(&mut self) {
-        if self.attached_pid.is_none() {
-            self.last_error = Some("Not attached to any console".to_string());
+    /// End the local shell, detaching first if it is the current target
+    fn close_local_terminal(&mut self) {
+        repro::record(TraceKind::Ui, "close_local_terminal");
+        if let Some(terminal) = self.local_terminal.take() {
+            if self.attached_pid == Some(terminal.pid()) {
+                self.detach_from_console();
+            }
+            let _ = audit::log(Some(terminal.pid()), "local terminal closed");
+        }
+    }
+
+    /// Size the local shell's screen to the output view while it is the target,
+    /// so its lines wrap where the view does
+    fn fit_local_terminal(&mut self, cols: usize, rows: usize) {
+        let attached = self.attached_pid;
+        let Some(terminal) = self.local_terminal.as_mut().filter(|t| Some(t.pid()) == attached) else {
             return;
+        };
+        if let Err(e) = terminal.resize(cols, rows) {
+            self.status_message = e.to_string();
         }
+    }
 
-        let pid = self.attached_pid.unwrap();
+    /// Send the keep-alive sequence if its interval elapsed and the user is not typing
+    #[cfg(feature = "automation")]
+    fn poll_keep_alive(&mut self) {
+        let Some(pid) = self.attached_pid else {
+            return;
+        };
+        if !self.keep_alive.due(&self.keep_alive_settings) {
+            return;
+        }
+        // Restart the interval even on failure so errors are not retried every frame
+        self.keep_alive.reset();
 
-        match attach_to_console(pid) {
-            Ok(()) => {
-                // Send Line Feed (0x0A) followed by Carriage Return (0x0D)
-                match send_control_char(0x0A) {
-                    Ok(()) => {
-                        match send_control_char(0x0D) {
-                            Ok(()) => {
-                                self.last_error = None;
-                            }
-                            Err(e) => {
-                                self.last_error = Some(format!("Failed to send \\r: {}", e));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to send \\n: {}", e));
-                    }
-                }
-                let _ = detach_from_console();
+        let sequence = self.keep_alive_settings.sequence.clone();
+        let keys = match parse_sequence(&sequence) {
+            Ok(keys) => keys,
+            Err(e) => {
+                self.last_error = Some(format!("Keep-alive failed: {}", e));
+                return;
             }
+        };
+        // The outcome comes back as `InputSent`, which audits failures
+        if self.dispatch_input(UiMessage::SendKeys { keys, label: format!("keep-alive {}", sequence) })
+            && let Err(e) = audit::log(Some(pid), &format!("keep-alive sent {}", sequence))
+        {
+            self.last_error = Some(format!("Failed to write audit log: {}", e));
+        }
+    }
+
+    /// Answer a watched prompt on the target's prompt line with its keys
+    #[cfg(feature = "automation")]
+    fn poll_responder(&mut self) {
+        let Some(pid) = self.attached_pid else {
+            return;
+        };
+        let Some(line) = self.prompt_line() else {
+            return;
+        };
+        let Some(rule) = self.responder.check(&self.responder_settings, &line).cloned() else {
+            return;
+        };
+
+        let keys = match parse_sequence(&rule.keys) {
+            Ok(keys) => keys,
             Err(e) => {
-                self.last_error = Some(format!("Failed to attach for \\n\\r: {}", e));
+                self.last_error = Some(format!("Auto-respond failed: {}", e));
+                return;
             }
+        };
+        let label = format!("auto-respond \"{}\" {}", rule.pattern, rule.keys);
+        if self.dispatch_input(UiMessage::SendKeys { keys, label }) {
+            if let Err(e) = audit::log(Some(pid), &format!("auto-respond \"{}\" sent {}", rule.pattern, rule.keys)) {
+                self.last_error = Some(format!("Failed to write audit log: {}", e));
+            }
+            self.status_message = format!("Answered \"{}\"", rule.pattern);
         }
     }
 
@@ -321,7 +1169,6 @@ impl RemoteConApp {
             return;
         }
 
-        use std::fs::File;
         use std::io::Write;
         use chrono::{Utc, Datelike, Timelike};
 
@@ -331,82 +1178,1592 @@ impl RemoteConApp {
             now.year(), now.month(), now.day(),
             now.hour(), now.minute(), now.second());
 
-        match File::create(&filename) {
-            Ok(mut file) => {
-                // Write timestamp header
-                let timestamp = if let Some(ts) = self.output_update_timestamp {
-                    format!("# Conversation saved at: {:?}\n\n", ts)
-                } else {
-                    String::from("# Conversation saved\n\n")
-                };
-                let _ = file.write_all(timestamp.as_bytes());
+        let mut data = Vec::new();
 
-                // Write console output
-                for line in &self.console_output {
-                    let _ = file.write_all(line.as_bytes());
-                    let _ = file.write_all(b"\n");
-                }
+        let eol = self.text_options.line_ending.as_str();
 
-                // Write attachment info
-                if let Some(pid) = self.attached_pid {
-                    let _ = file.write_all(format!("\n# Attached to PID: {}", pid).as_bytes());
-                }
+        // Write timestamp header
+        let timestamp = if let Some(ts) = self.output_update_timestamp {
+            format!("# Conversation saved at: {:?}{}{}", ts, eol, eol)
+        } else {
+            format!("# Conversation saved{}{}", eol, eol)
+        };
+        let _ = data.write_all(timestamp.as_bytes());
 
-                self.last_error = None;
-                self.status_message = format!("Saved to {}", filename);
-            }
-            Err(e) => {
-                self.last_error = Some(format!("Failed to save file: {}", e));
+        // Write console output
+        let _ = data.write_all(self.text_options.join(&self.console_output).as_bytes());
+
+        // Write attachment info
+        if let Some(pid) = self.attached_pid {
+            let _ = data.write_all(format!("{}# Attached to PID: {}", eol, pid).as_bytes());
+        }
+
+        self.store(&filename, data, "Conversation");
+    }
+
+    /// Save the target's full screen buffer, with colors and cursor, as JSON
+    fn snapshot_buffer(&mut self) {
+        repro::record(TraceKind::Ui, "snapshot_buffer");
+        self.dispatch_input(UiMessage::TakeSnapshot(SnapshotKind::Json));
+    }
+
+    /// Save a PNG of the target's visible window, drawn with its font and colors
+    fn screenshot_console(&mut self) {
+        repro::record(TraceKind::Ui, "screenshot_console");
+        self.dispatch_input(UiMessage::TakeSnapshot(SnapshotKind::Png));
+    }
+
+    /// Store a snapshot the worker took of the target
+    fn snapshot_taken(&mut self, kind: SnapshotKind, data: Result<Vec<u8>, String>) {
+        let Some(pid) = self.attached_pid else {
+            return;
+        };
+        let (prefix, extension, what) = match kind {
+            SnapshotKind::Json => ("snapshot", "json", "Snapshot"),
+            SnapshotKind::Png => ("screenshot", "png", "Screenshot"),
+        };
+        let filename = format!("{}_{}_{}.{}", prefix, pid, chrono::Local::now().format("%Y%m%d_%H%M%S"), extension);
+        match data {
+            Ok(data) => self.store(&filename, data, what),
+            Err(e) => self.last_error = Some(format!("Failed to take {}: {}", what.to_lowercase(), e)),
+        }
+    }
+
+    /// Hand captured data to the storage writer; `what` names it in the status bar
+    fn store(&mut self, name: &str, data: Vec<u8>, what: &str) {
+        match self.storage_writer.save(&self.storage, name, data, what) {
+            Ok(()) => self.status_message = format!("Saving {}...", what.to_lowercase()),
+            Err(e) => self.last_error = Some(format!("Failed to save {}: {}", what.to_lowercase(), e)),
+        }
+    }
+
+    /// Report saves the storage writer has finished
+    fn poll_saves(&mut self) {
+        while let Some(saved) = self.storage_writer.try_recv() {
+            match saved.result {
+                Ok(location) => {
+                    self.last_error = None;
+                    self.status_message = format!("{} saved to {}", saved.what, location);
+                }
+                Err(e) => {
+                    self.last_error = Some(format!("Failed to save {}: {}", saved.what.to_lowercase(), e));
+                }
             }
         }
     }
 
+    /// Start a repro recording or finish the one in progress
+    fn toggle_repro_recording(&mut self) {
+        if repro::is_recording() {
+            self.finish_repro(true);
+        } else {
+            repro::start(Duration::from_secs(REPRO_WINDOW_SECS));
+            repro::record(TraceKind::Ui, &format!("recording_started attached={:?}", self.attached_pid));
+            self.status_message = format!("Recording repro for {} s...", REPRO_WINDOW_SECS);
+        }
+    }
+
+    /// Write the repro trace out once its window is over
+    fn finish_repro(&mut self, force: bool) {
+        if let Some((name, data)) = repro::finish_if_due(force) {
+            self.store(&name, data, "Repro trace");
+        }
+    }
+
     /// Update the console output from worker messages
     fn update_from_worker(&mut self) {
-        // Take the worker out temporarily to avoid borrow conflicts
-        let mut disconnected = false;
-        if self.worker.is_some() {
-            // Process all available messages
-            loop {
-                let msg = {
-                    // Borrow worker only for the try_recv call
-                    if let Some(ref worker) = self.worker {
-                        worker.try_recv()
-                    } else {
-                        break;
-                    }
-                };
+        // Process all available messages; the pool drops a session that disconnected
+        loop {
+            let msg = self.workers.try_recv().map(|(_, msg)| msg);
 
-                match msg {
-                    Some(WorkerMessage::Output { lines, timestamp }) => {
-                        self.console_output = lines;
-                        self.output_update_timestamp = Some(timestamp);
-                        self.attach_error = None;
-                        self.last_error = None;
-                        if let Some(pid) = self.attached_pid {
-                            self.status_message = format!("Attached to PID {} - Last update: {:?}", pid, timestamp);
+            if let Some(ref m) = msg {
+                repro::record(TraceKind::Worker, &describe_worker_message(m));
+            }
+
+            match msg {
+                Some(WorkerMessage::Output { mut lines, mut colors, changed, timestamp }) => {
+                    // The helper only sends snapshots, so stitch elevated history from those
+                    if self.attached_elevated {
+                        let shift = scrolled_off_count(&self.console_output, &lines);
+                        for line in self.console_output.drain(..shift) {
+                            if let Err(e) = self.history.push(line) {
+                                self.last_error = Some(format!("Scrollback spill failed: {}", e));
+                                break;
+                            }
+                        }
+                    }
+                    if lines.len() == self.console_output.len() && colors.len() == self.console_colors.len() {
+                        // Same shape: only the changed lines need replacing
+                        for i in changed {
+                            self.console_output[i] = std::mem::take(&mut lines[i]);
+                            if let Some(runs) = colors.get_mut(i) {
+                                self.console_colors[i] = std::mem::take(runs);
+                            }
                         }
+                    } else {
+                        self.console_output = lines;
+                        self.console_colors = colors;
+                    }
+                    self.output_received(timestamp);
+                }
+                Some(WorkerMessage::Delta { len, lines, timestamp }) => {
+                    self.console_output.resize(len, String::new());
+                    self.console_colors.resize(len, Vec::new());
+                    for (i, line) in lines.into_iter().filter(|(i, _)| *i < len) {
+                        self.console_output[i] = line.text;
+                        self.console_colors[i] = line.runs;
+                    }
+                    self.output_received(timestamp);
+                }
+                Some(WorkerMessage::Error(e)) => {
+                    self.last_error = Some(e);
+                }
+                Some(WorkerMessage::Event(event)) => self.worker_event(event),
+                Some(WorkerMessage::Snapshot { kind, data }) => self.snapshot_taken(kind, data),
+                Some(WorkerMessage::Crashed(message)) => {
+                    self.last_error = Some(format!("Worker crashed ({}); restarting it", message));
+                }
+                Some(WorkerMessage::InputSent { label, command, error }) => match error {
+                    Some(e) => {
+                        if let Err(e) = audit::log(self.attached_pid, &format!("{} failed: {}", label, e)) {
+                            self.last_error = Some(format!("Failed to write audit log: {}", e));
+                        }
+                        self.last_error = Some(match self.paste.take() {
+                            Some(paste) => {
+                                let (sent, total) = paste.progress();
+                                format!("Paste stopped after {} of {} lines: {}", sent.saturating_sub(1), total, e)
+                            }
+                            None => format!("Failed to send {}: {}", label, e),
+                        });
+                    }
+                    None => {
+                        self.last_error = None;
+                        if command.is_some() && self.capture_after_send {
+                            self.schedule_capture();
+                        }
+                        #[cfg(feature = "automation")]
+                        if let (Some(command), Some(pid)) = (command, self.attached_pid) {
+                            self.journal_command(pid, &command);
+                        }
+                        #[cfg(not(feature = "automation"))]
+                        let _ = command;
+                    }
+                },
+                Some(WorkerMessage::Switched { from, to }) => {
+                    // Keep the old target's output around for reference
+                    if let Some(from) = from {
+                        let old = std::mem::take(&mut self.console_output);
+                        self.console_colors.clear();
+                        self.previous_output = Some((from, old));
+                    }
+                    self.attached_pid = Some(to);
+                    self.owner_change = None;
+                    self.console_title = None;
+                    self.target_selection = None;
+                    self.console_modes = None;
+                    self.console_metrics = None;
+                    self.alternate_screen = false;
+                    self.code_pages = None;
+                    self.cursor_shape = None;
+                    self.status_message = format!("Switched to PID {}", to);
+                }
+                Some(WorkerMessage::Cursor { position }) => {
+                    self.console_cursor = position;
+                }
+                Some(WorkerMessage::Heartbeat) => {
+                    self.worker_heartbeat = Some(Instant::now());
+                }
+                Some(WorkerMessage::Health { alive, last_seen }) => {
+                    self.target_alive = Some(alive);
+                    self.target_last_seen = last_seen;
+                }
+                Some(WorkerMessage::ConsoleMembers { pids }) => {
+                    self.console_members = pids;
+                }
+                Some(WorkerMessage::Selection(selection)) => {
+                    self.target_selection = selection;
+                }
+                Some(WorkerMessage::Modes(modes)) => {
+                    self.console_modes = Some(modes);
+                }
+                Some(WorkerMessage::Metrics(metrics)) => {
+                    self.console_metrics = Some(metrics);
+                }
+                Some(WorkerMessage::AlternateScreen(alternate)) => {
+                    self.alternate_screen = alternate;
+                }
+                Some(WorkerMessage::CodePages { input, output }) => {
+                    self.code_pages = Some((input, output));
+                }
+                Some(WorkerMessage::CursorShape(shape)) => {
+                    self.cursor_shape = Some(shape);
+                }
+                Some(WorkerMessage::Stats(stats)) => {
+                    self.worker_stats = Some(stats);
+                }
+                Some(WorkerMessage::Timings(timings)) => {
+                    if self.slowest_poll.is_none_or(|s| timings.total > s.total) {
+                        self.slowest_poll = Some(timings);
+                    }
+                    self.poll_timings = Some(timings);
+                }
+                Some(WorkerMessage::History { lines, gap }) => {
+                    let marker = gap.then(|| HISTORY_GAP_MARKER.to_string());
+                    for line in marker.into_iter().chain(lines) {
+                        if let Err(e) = self.history.push(line) {
+                            self.last_error = Some(format!("Scrollback spill failed: {}", e));
+                            break;
+                        }
+                    }
+                }
+                Some(WorkerMessage::Captured { lines, timestamp }) => {
+                    self.status_message = format!("Captured {} lines", lines.len());
+                    self.last_capture = Some((timestamp, lines.join("\n")));
+                }
+                Some(WorkerMessage::Region { rect, lines }) => {
+                    if rect == self.region {
+                        self.last_region = Some(lines.map(|lines| lines.join("\n")));
+                    }
+                }
+                Some(WorkerMessage::HistoryRange { from, lines, total }) => {
+                    self.worker_history = WorkerHistoryView {
+                        first: from,
+                        lines,
+                        total,
+                        requested: None,
+                        fetched: Some(Instant::now()),
+                    };
+                }
+                Some(WorkerMessage::OwnerChanged { original, candidates }) => {
+                    match candidates.first() {
+                        Some(&next) if self.follow_new_owner => {
+                            self.selected_pid = Some(next);
+                            self.attach_to_console();
+                            self.status_message = format!("PID {} left its console - following PID {}", original, next);
+                        }
+                        _ => self.owner_change = Some((original, candidates)),
+                    }
+                }
+                Some(WorkerMessage::Final { lines, timestamp }) => {
+                    // Only while nothing else is attached
+                    if self.attached_pid.is_none() {
+                        let (text, colors) = lines.into_iter().map(|l| (l.text, l.runs)).unzip();
+                        self.console_output = text;
+                        self.console_colors = colors;
+                        self.output_update_timestamp = Some(timestamp);
+                    }
+                }
+                Some(WorkerMessage::Disconnected) => {
+                    self.final_screen_of = self.attached_pid;
+                    self.target_alive = None;
+                    if self.auto_reattach && !self.reattach_pattern.is_empty() {
+                        self.reattach_lost_pid = self.attached_pid;
+                        self.reattach_lost_at = chrono::Utc::now().timestamp().max(0) as u64;
+                        self.last_reattach_scan = Some(Instant::now());
+                    }
+                    self.attached_pid = None;
+                    self.status_message = "Disconnected".to_string();
+                    self.last_error = Some("Console disconnected".to_string());
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Note fresh output from the worker in the status line
+    fn output_received(&mut self, timestamp: Instant) {
+        self.output_update_timestamp = Some(timestamp);
+        self.attach_error = None;
+        self.last_error = None;
+        if let Some(pid) = self.attached_pid {
+            self.status_message = format!("Attached to PID {} - Last update: {:?}", pid, timestamp);
+        }
+    }
+
+    /// Seed the reattach pattern from the process being attached
+    fn prefill_reattach_pattern(&mut self, pid: u32) {
+        if !self.reattach_pattern.is_empty() {
+            return;
+        }
+        if let Some(proc) = self.cmd_processes.iter().find(|p| p.pid == pid) {
+            self.reattach_pattern = match self.reattach_match {
+                ReattachMatch::WindowTitle => proc.window_title.clone().unwrap_or_default(),
+                ReattachMatch::CommandLine => proc.command_line.clone(),
+            };
+        }
+    }
+
+    /// While waiting for a restarted target, rescan and attach to the first match
+    fn poll_auto_reattach(&mut self) {
+        let Some(lost_pid) = self.reattach_lost_pid else {
+            self.reattach_scan = None;
+            return;
+        };
+        if !self.auto_reattach {
+            self.reattach_lost_pid = None;
+            self.reattach_scan = None;
+            return;
+        }
+        // Enumerating processes takes long enough to stall a frame, so it runs on its own thread
+        let Some(scan) = &self.reattach_scan else {
+            if self.last_reattach_scan.is_none_or(|t| t.elapsed() >= REATTACH_SCAN_INTERVAL) {
+                self.last_reattach_scan = Some(Instant::now());
+                self.reattach_scan = Some(enumerate_in_background());
+            }
+            return;
+        };
+        let scanned = match scan.try_recv() {
+            Ok(result) => result,
+            Err(e) if e.is_empty() => return,
+            Err(_) => Err(anyhow::anyhow!("Process scan thread ended")),
+        };
+        self.reattach_scan = None;
+        match scanned {
+            Ok(processes) => {
+                self.cmd_processes = processes;
+                self.show_refresh_error = None;
+            }
+            Err(e) => {
+                self.show_refresh_error = Some(format!("Failed to enumerate processes: {}", e));
+                return;
+            }
+        }
+
+        // Only a console started since the loss can be the target coming back
+        let candidate = self.cmd_processes.iter()
+            .filter(|p| p.pid != lost_pid && p.attachable && p.start_time >= self.reattach_lost_at)
+            .find(|p| {
+                let text = match self.reattach_match {
+                    ReattachMatch::WindowTitle => p.window_title.as_deref().unwrap_or(""),
+                    ReattachMatch::CommandLine => p.command_line.as_str(),
+                };
+                wildcard_match(&self.reattach_pattern, text)
+            })
+            .map(|p| p.pid);
+
+        match candidate {
+            Some(pid) => {
+                self.selected_pid = Some(pid);
+                self.attach_to_console();
+                self.status_message = format!("Auto-reattached to PID {} (was {})", pid, lost_pid);
+            }
+            None => {
+                self.status_message = format!("PID {} exited - waiting for a process matching \"{}\"",
+                    lost_pid, self.reattach_pattern);
+            }
+        }
+    }
+
+    /// Show who else shares the target's console and offer to follow a new owner
+    fn render_console_sharing(&mut self, ui: &mut egui::Ui) {
+        if self.attached_pid.is_none() {
+            return;
+        }
+
+        let mut follow = None;
+        let mut dismiss = false;
+        if let Some((original, ref candidates)) = self.owner_change {
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(egui::Color32::from_rgb(200, 120, 0),
+                    format!("PID {} no longer owns the console being read.", original));
+                for &pid in candidates {
+                    if ui.button(format!("Follow PID {}", pid)).clicked() {
+                        follow = Some(pid);
+                    }
+                }
+                if ui.button("Dismiss").clicked() {
+                    dismiss = true;
+                }
+            });
+        }
+        if let Some(pid) = follow {
+            self.owner_change = None;
+            self.selected_pid = Some(pid);
+            self.attach_to_console();
+        } else if dismiss {
+            self.owner_change = None;
+        }
+
+        ui.horizontal(|ui| {
+            let others: Vec<String> = self.console_members.iter()
+                .filter(|p| Some(**p) != self.attached_pid)
+                .map(|p| p.to_string())
+                .collect();
+            if others.is_empty() {
+                ui.label("Console not shared");
+            } else {
+                ui.label(format!("Console shared with PID {}", others.join(", ")));
+            }
+            ui.checkbox(&mut self.follow_new_owner, "Follow new owner automatically");
+        });
+    }
+
+    /// Warn that a selection in the target's window is holding back its output
+    fn render_target_selection(&mut self, ui: &mut egui::Ui) {
+        let Some(selection) = &self.target_selection else {
+            return;
+        };
+        let mut disable_quick_edit = false;
+        if selection.in_progress {
+            let how = if selection.mouse { "mouse selection" } else { "Mark mode" };
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Output is paused by a {} in the target console", how),
+                );
+                if selection.mouse && self.console_modes.is_some_and(|m| m.quick_edit()) {
+                    disable_quick_edit = ui.small_button("Turn off Quick-Edit")
+                        .on_hover_text("Stop clicks in the target's window from pausing it; the current selection still needs Esc or a click there")
+                        .clicked();
+                }
+            });
+        }
+        if !selection.text.is_empty() {
+            let title = format!(
+                "Selected in target: rows {}-{}, columns {}-{}",
+                selection.rows.start, selection.rows.end - 1, selection.cols.start, selection.cols.end - 1
+            );
+            egui::CollapsingHeader::new(title)
+                .id_salt("target_selection")
+                .default_open(false)
+                .show(ui, |ui| {
+                    for line in &selection.text {
+                        ui.monospace(line);
+                    }
+                });
+        }
+        if disable_quick_edit {
+            self.set_target_quick_edit(false);
+        }
+    }
+
+    /// Render the target's console modes as a strip of on/off flags
+    fn render_console_modes(&mut self, ui: &mut egui::Ui) {
+        let Some(modes) = self.console_modes else {
+            return;
+        };
+        let flag = |ui: &mut egui::Ui, (name, on): (&str, bool)| {
+            let color = if on { egui::Color32::LIGHT_GREEN } else { egui::Color32::DARK_GRAY };
+            ui.colored_label(color, name);
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!("Input {:#06x}:", modes.input));
+            for f in modes.input_flags() {
+                flag(ui, f);
+            }
+            ui.separator();
+            ui.label(format!("Output {:#06x}:", modes.output));
+            for f in modes.output_flags() {
+                flag(ui, f);
+            }
+            if let Some((input, output)) = self.code_pages {
+                ui.separator();
+                ui.label(format!("CP in {} / out {}", input, output));
+            }
+            ui.separator();
+            let quick_edit = modes.quick_edit();
+            let label = if quick_edit { "Quick-Edit off" } else { "Quick-Edit on" };
+            if ui.small_button(label)
+                .on_hover_text("Quick-Edit lets a click in the target's window select text, which pauses its output")
+                .clicked()
+            {
+                self.set_target_quick_edit(!quick_edit);
+            }
+        });
+    }
+
+    /// Render the target's font and window size, warning when its rows are
+    /// wider than the output area
+    fn render_console_metrics(&self, ui: &mut egui::Ui) {
+        let Some(m) = &self.console_metrics else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!("Font: {} {}x{}", m.font_face, m.font_size.0, m.font_size.1));
+            ui.separator();
+            ui.label(format!("Buffer {}x{}", m.buffer.0, m.buffer.1));
+            ui.separator();
+            ui.label(format!("Window {}x{} (max {}x{})", m.window.0, m.window.1, m.max_window.0, m.max_window.1));
+            if self.alternate_screen {
+                ui.separator();
+                ui.colored_label(egui::Color32::LIGHT_BLUE, "Alternate screen (full-screen app)")
+                    .on_hover_text("Showing the target's visible screen until the app returns to the main buffer");
+            }
+            if let Some(cols) = self.viewer_cols.filter(|c| *c < m.buffer.0) {
+                ui.separator();
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Buffer is {} columns wide, the viewer fits {}", m.buffer.0, cols),
+                );
+            }
+        });
+    }
+
+    /// Render keep-alive controls
+    #[cfg(feature = "automation")]
+    fn render_keep_alive_settings(&mut self, ui: &mut egui::Ui) {
+        let before = self.keep_alive_settings.clone();
+        egui::CollapsingHeader::new("Keep-alive")
+            .id_salt("keep_alive")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.keep_alive_settings.enabled, "Send keep-alive every");
+                    let mut secs = self.keep_alive_settings.interval.as_secs();
+                    if ui.add(egui::DragValue::new(&mut secs).range(5..=3600).suffix(" s")).changed() {
+                        self.keep_alive_settings.interval = Duration::from_secs(secs);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Keys:");
+                    ui.text_edit_singleline(&mut self.keep_alive_settings.sequence);
+                });
+                if let Err(e) = parse_sequence(&self.keep_alive_settings.sequence) {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                }
+                if self.keep_alive.suspended() {
+                    ui.label("Suspended while you type");
+                } else if let Some(next) = self.keep_alive.next_in(&self.keep_alive_settings) {
+                    ui.label(format!("Next in {}s", next.as_secs()));
+                }
+                ui.label(format!("Entries are logged to {}", audit::audit_path().display()));
+            });
+
+        if self.keep_alive_settings != before {
+            self.keep_alive_settings.save_to(&mut self.profiles);
+            self.save_profiles();
+        }
+    }
+
+    /// Render the prompt watch list for automatic answers
+    #[cfg(feature = "automation")]
+    fn render_responder_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Auto-respond")
+            .id_salt("auto_respond")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.checkbox(&mut self.responder_settings.enabled, "Answer these prompts automatically");
+                let mut remove = None;
+                egui::Grid::new("auto_respond_rules").num_columns(3).show(ui, |ui| {
+                    ui.strong("Prompt contains");
+                    ui.strong("Keys");
+                    ui.end_row();
+                    for (i, rule) in self.responder_settings.rules.iter_mut().enumerate() {
+                        ui.text_edit_singleline(&mut rule.pattern);
+                        ui.add(egui::TextEdit::singleline(&mut rule.keys).desired_width(100.0));
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(i) = remove {
+                    self.responder_settings.rules.remove(i);
+                }
+                if ui.button("Add prompt").clicked() {
+                    self.responder_settings.rules.push(PromptRule::new("", "{ENTER}"));
+                }
+                for rule in &self.responder_settings.rules {
+                    if let Err(e) = parse_sequence(&rule.keys) {
+                        ui.colored_label(egui::Color32::RED, format!("{}: {}", rule.pattern, e));
+                    }
+                }
+                ui.label("Keys use the keep-alive syntax; answers are logged to the audit log");
+            });
+    }
+
+    /// Send the next pasted line once the inter-line delay has passed
+    fn poll_paste(&mut self) {
+        if self.attached_pid.is_none() {
+            self.paste = None;
+            return;
+        }
+        let prompt_seen = if self.at_prompt() { self.output_update_timestamp } else { None };
+        let Some(line) = self.paste.as_ref().and_then(|p| p.due(prompt_seen)).map(str::to_string) else {
+            return;
+        };
+
+        // A failed line comes back as `InputSent` and stops the paste there
+        if !self.dispatch_input(UiMessage::SendCommand(line)) {
+            self.paste = None;
+            return;
+        }
+        let Some(paste) = self.paste.as_mut() else {
+            return;
+        };
+        paste.mark_sent();
+        if paste.is_done() {
+            self.status_message = format!("Pasted {} lines", paste.progress().1);
+            self.paste = None;
+        }
+    }
+
+    /// Hand queued commands to the paste feeder once the target is attached
+    /// and answering, so they go out in order with the paste delay between
+    fn poll_command_queue(&mut self) {
+        if self.command_queue.is_empty() || self.paste.is_some() {
+            return;
+        }
+        if self.attached_pid.is_none() || self.target_alive != Some(true) {
+            return;
+        }
+        // Placeholders such as {pid} are filled in now that there is a target
+        let mut commands = Vec::new();
+        for command in std::mem::take(&mut self.command_queue) {
+            match self.expand_command(&command) {
+                // What the placeholders filled in has not been past the guard yet
+                Ok(expanded) if expanded != command && self.command_guard.check(&expanded).is_some() => {
+                    self.last_error = Some(format!("Dropped queued command {}: its filled-in text matches the command guard", command));
+                    let _ = audit::log(self.attached_pid, &format!("guard blocked {}", expanded));
+                }
+                Ok(expanded) => commands.push(expanded),
+                Err(e) => self.last_error = Some(format!("Dropped queued command {}: {}", command, e)),
+            }
+        }
+        if commands.is_empty() {
+            return;
+        }
+        repro::record(TraceKind::Ui, &format!("flush_queue count={}", commands.len()));
+        self.status_message = format!("Sending {} queued commands", commands.len());
+        self.paste = Some(LinePaste::from_lines(commands, Duration::from_millis(self.paste_delay_ms)));
+    }
+
+    /// Whether the target looks idle at a cmd.exe prompt: its prompt line ends in `>`
+    fn at_prompt(&self) -> bool {
+        self.prompt_line().is_some_and(|l| l.trim_end().ends_with('>'))
+    }
+
+    /// Where the target waits for input: the cursor line up to the cursor,
+    /// or the last non-blank line when the cursor is unknown
+    fn prompt_line(&self) -> Option<String> {
+        match self.console_cursor {
+            Some((row, col)) => self.console_output.get(row)
+                .map(|l| l.chars().take(col).collect::<String>()),
+            None => self.console_output.iter().rev().find(|l| !l.trim().is_empty()).cloned(),
+        }
+    }
+
+    /// Switch dry run for the attached session, starting from an empty capture
+    fn apply_dry_run(&mut self) {
+        let _ = take_dry_run();
+        self.reconfigure_worker();
+    }
+
+    /// Collect the input described by dry run since the last frame
+    fn poll_dry_run(&mut self) {
+        if !self.dry_run {
+            return;
+        }
+        self.dry_run_log.extend(take_dry_run());
+        if let Some(helper) = self.elevated_helper() {
+            match helper.take_dry_run() {
+                Ok(lines) => self.dry_run_log.extend(lines),
+                Err(e) => self.last_error = Some(format!("Failed to read helper dry run: {}", e)),
+            }
+        }
+    }
+
+    /// Start playing a script file into the console
+    fn send_file(&mut self) {
+        let path = std::path::PathBuf::from(self.send_file_path.trim());
+        match LinePaste::from_file(&path, Duration::from_millis(self.paste_delay_ms), self.send_file_wait_prompt) {
+            Ok(paste) => {
+                repro::record(TraceKind::Ui, &format!("send_file lines={}", paste.progress().1));
+                if self.guard_allows_batch(paste.lines(), "pasting") {
+                    self.paste = Some(paste);
+                    self.last_error = None;
+                }
+            }
+            Err(e) => {
+                self.last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Whether the command guard lets `command` go out now. Blocked commands
+    /// set an error; ones needing confirmation open the confirm dialog.
+    fn guard_allows(&mut self, command: &str, top: bool) -> bool {
+        let Some(rule) = self.command_guard.check(command) else {
+            return true;
+        };
+        match rule.action {
+            GuardAction::Block => {
+                self.last_error = Some(format!("Blocked by command guard rule \"{}\"", rule.pattern));
+                let _ = audit::log(self.attached_pid, &format!("guard blocked {}", command));
+            }
+            GuardAction::Confirm => {
+                self.guard_pending = Some((command.to_string(), rule.pattern.clone(), top));
+            }
+        }
+        false
+    }
+
+    /// Whether a paste, script or replay (`what`) may start: there is no one
+    /// to confirm partway through, so any guarded line refuses the whole thing
+    fn guard_allows_batch(&mut self, lines: &[String], what: &str) -> bool {
+        let tripped = lines.iter().enumerate()
+            .find_map(|(i, line)| self.command_guard.check(line).map(|rule| (i + 1, rule.pattern.clone())));
+        let Some((number, pattern)) = tripped else {
+            return true;
+        };
+        self.last_error = Some(format!("Not {}: line {} matches command guard rule \"{}\"", what, number, pattern));
+        let _ = audit::log(self.attached_pid, &format!("guard refused {} at line {}", what, number));
+        false
+    }
+
+    /// Ask before sending a command the guard flagged
+    fn show_guard_dialog(&mut self, ctx: &egui::Context) {
+        let Some((command, pattern, top)) = self.guard_pending.clone() else {
+            return;
+        };
+        let mut close = false;
+        let mut confirmed = false;
+        egui::Window::new("Confirm command")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("This command matches guard rule \"{}\":", pattern));
+                ui.add(egui::Label::new(egui::RichText::new(&command).monospace().strong()).wrap());
+                if let Some(pid) = self.attached_pid {
+                    ui.label(format!("Target: PID {}", pid));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Send anyway").clicked() {
+                        confirmed = true;
+                        close = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if close {
+            self.guard_pending = None;
+        }
+        if confirmed {
+            let _ = audit::log(self.attached_pid, &format!("guard confirmed {}", command));
+            self.release_command(command, top);
+        }
+    }
+
+    /// Render the dangerous-command rules and their exceptions
+    fn render_command_guard(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Command guard")
+            .id_salt("command_guard")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.checkbox(&mut self.command_guard.enabled, "Check commands before sending");
+                let mut remove = None;
+                egui::Grid::new("command_guard_rules").num_columns(3).show(ui, |ui| {
+                    ui.strong("Command");
+                    ui.strong("Action");
+                    ui.end_row();
+                    for (i, rule) in self.command_guard.rules.iter_mut().enumerate() {
+                        ui.text_edit_singleline(&mut rule.pattern);
+                        egui::ComboBox::from_id_salt(("guard_action", i))
+                            .selected_text(rule.action.label())
+                            .show_ui(ui, |ui| {
+                                for action in [GuardAction::Confirm, GuardAction::Block] {
+                                    ui.selectable_value(&mut rule.action, action, action.label());
+                                }
+                            });
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(i) = remove {
+                    self.command_guard.rules.remove(i);
+                }
+                if ui.button("Add rule").clicked() {
+                    self.command_guard.rules.push(GuardRule::new("", GuardAction::Confirm));
+                }
+
+                ui.separator();
+                ui.label("Always allowed:");
+                let mut remove = None;
+                for (i, rule) in self.command_guard.allow.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut rule.pattern);
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    self.command_guard.allow.remove(i);
+                }
+                if ui.button("Add exception").clicked() {
+                    self.command_guard.allow.push(GuardRule::new("", GuardAction::Confirm));
+                }
+                ui.label("A rule is the program then switches that must all appear, e.g. del /s; * and ? are wildcards");
+            });
+    }
+
+    /// Render the user variable editor for command placeholders
+    fn render_template_vars(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Command variables")
+            .id_salt("template_vars")
+            .default_open(false)
+            .show(ui, |ui| {
+                let builtins: Vec<String> = BUILTIN_VARIABLES.iter().map(|v| format!("{{{}}}", v)).collect();
+                ui.label(format!("Commands may use {} and the variables below; {{{{ and }}}} type literal braces.", builtins.join(" ")));
+                ui.add(egui::TextEdit::multiline(&mut self.template_vars)
+                    .hint_text("name=value, one per line")
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY)
+                    .font(egui::TextStyle::Monospace));
+                if let Err(e) = parse_variables(&self.template_vars) {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                }
+            });
+    }
+
+    /// Render the multi-line paste box with its delay and progress
+    fn render_paste(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Paste and scripts")
+            .id_salt("multi_line_paste")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut self.paste_text)
+                    .hint_text("One command per line")
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY)
+                    .font(egui::TextStyle::Monospace));
+                ui.horizontal(|ui| {
+                    ui.label("Delay between lines:");
+                    ui.add(egui::DragValue::new(&mut self.paste_delay_ms).range(0..=10_000).suffix(" ms"));
+
+                    let idle = self.paste.is_none();
+                    ui.add_enabled_ui(idle && self.attached_pid.is_some() && !self.paste_text.is_empty(), |ui| {
+                        if ui.button("Paste lines").clicked() {
+                            let paste = LinePaste::new(&self.paste_text, Duration::from_millis(self.paste_delay_ms));
+                            repro::record(TraceKind::Ui, &format!("paste lines={}", paste.progress().1));
+                            if self.guard_allows_batch(paste.lines(), "pasting") {
+                                self.paste = Some(paste);
+                            }
+                        }
+                    });
+                    if !idle && ui.button("Cancel").clicked() {
+                        self.paste = None;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Typing delay:");
+                    if ui.add(egui::DragValue::new(&mut self.typing_delay_ms).range(0..=500).suffix(" ms per key"))
+                        .on_hover_text("Type keys one at a time for targets that drop input sent in one burst; the window waits while a line is typed")
+                        .changed()
+                    {
+                        self.reconfigure_worker();
+                    }
+                    if ui.checkbox(&mut self.enter_sends_lf, "Enter sends LF")
+                        .on_hover_text("End commands with Ctrl+Enter, which types \\n, for programs that ignore the \\r of a plain Enter")
+                        .changed()
+                    {
+                        self.reconfigure_worker();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Script:");
+                    ui.add(egui::TextEdit::singleline(&mut self.send_file_path)
+                        .hint_text(r"C:\scripts\setup.cmd")
+                        .desired_width(260.0));
+                    ui.checkbox(&mut self.send_file_wait_prompt, "Wait for prompt")
+                        .on_hover_text("Send the next line only after the target shows its prompt again");
+                    let ready = self.paste.is_none() && self.attached_pid.is_some() && !self.send_file_path.trim().is_empty();
+                    ui.add_enabled_ui(ready, |ui| {
+                        if ui.button("Send file…").clicked() {
+                            self.send_file();
+                        }
+                    });
+                });
+                if let Some(paste) = &self.paste {
+                    let (sent, total) = paste.progress();
+                    let waiting = if paste.waits_for_prompt() && sent > 0 && sent < total && !self.at_prompt() {
+                        " (waiting for prompt)"
+                    } else {
+                        ""
+                    };
+                    ui.add(egui::ProgressBar::new(sent as f32 / total.max(1) as f32)
+                        .text(format!("{} / {} lines{}", sent, total, waiting)));
+                }
+            });
+    }
+
+    /// Output rows picked for quoting, in order
+    fn selected_output_rows(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        self.output_selection.map(|(a, b)| a.min(b)..=a.max(b))
+    }
+
+    /// Open the notes for the attached session, saving the previous session's notes
+    fn sync_session_notes(&mut self) {
+        let key = self.attached_pid.map(session_key);
+        if self.notes.as_ref().map(|n| n.key().to_string()) == key {
+            return;
+        }
+        if let Some(mut old) = self.notes.take()
+            && let Err(e) = old.save()
+        {
+            self.last_error = Some(e.to_string());
+        }
+        self.notes = key.as_deref().map(SessionNotes::open);
+    }
+
+    /// Render the session scratchpad with edit / preview modes
+    fn render_notes(&mut self, ui: &mut egui::Ui) {
+        let Some(notes) = self.notes.as_mut() else {
+            return;
+        };
+        egui::CollapsingHeader::new("Notes")
+            .id_salt("session_notes")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.notes_preview, false, "Edit");
+                    ui.selectable_value(&mut self.notes_preview, true, "Preview");
+                    ui.separator();
+                    let selected = self.output_selection.map(|(a, b)| a.min(b)..=a.max(b));
+                    ui.add_enabled_ui(selected.is_some(), |ui| {
+                        if ui.button("Insert selected output as quote").on_hover_text("Click an output line, shift-click to extend").clicked()
+                            && let Some(rows) = selected
+                        {
+                            let lines: Vec<String> = self.console_output.iter()
+                                .skip(*rows.start())
+                                .take(rows.end() - rows.start() + 1)
+                                .cloned()
+                                .collect();
+                            notes.insert_quote(&lines);
+                            self.output_selection = None;
+                        }
+                    });
+                });
+                if self.notes_preview {
+                    egui::ScrollArea::vertical()
+                        .id_salt("notes_preview")
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            egui_commonmark::CommonMarkViewer::new().show(ui, &mut self.notes_cache, &notes.text);
+                        });
+                } else if ui.add(
+                    egui::TextEdit::multiline(&mut notes.text)
+                        .hint_text("Observations, commands, TODOs (markdown)")
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY),
+                ).changed() {
+                    notes.edited();
+                }
+            });
+
+        if let Err(e) = notes.autosave() {
+            self.last_error = Some(e.to_string());
+        }
+    }
+
+    /// Render the cross-session command journal with filters and replay
+    #[cfg(feature = "automation")]
+    fn render_journal(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("Command journal ({})", self.journal.entries().len()))
+            .id_salt("command_journal")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Session:");
+                    let sessions = self.journal.sessions();
+                    let selected = match self.journal_pid_filter {
+                        Some(pid) => format!("PID {}", pid),
+                        None => "All".to_string(),
+                    };
+                    egui::ComboBox::from_id_salt("journal_session")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.journal_pid_filter, None, "All");
+                            for (pid, title) in &sessions {
+                                ui.selectable_value(&mut self.journal_pid_filter, Some(*pid), format!("PID {} {}", pid, title));
+                            }
+                        });
+                    ui.label("Time:");
+                    let window_label = JOURNAL_WINDOWS.iter()
+                        .find(|(m, _)| *m == self.journal_window_minutes)
+                        .map(|(_, l)| *l)
+                        .unwrap_or("All time");
+                    egui::ComboBox::from_id_salt("journal_window")
+                        .selected_text(window_label)
+                        .show_ui(ui, |ui| {
+                            for (minutes, label) in JOURNAL_WINDOWS {
+                                ui.selectable_value(&mut self.journal_window_minutes, minutes, label);
+                            }
+                        });
+                });
+
+                let since = (self.journal_window_minutes > 0)
+                    .then(|| chrono::Local::now() - chrono::Duration::minutes(self.journal_window_minutes as i64));
+                let visible = self.journal.filtered(self.journal_pid_filter, since);
+                let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                egui::ScrollArea::vertical()
+                    .id_salt("journal_rows")
+                    .max_height(200.0)
+                    .show_rows(ui, row_height, visible.len(), |ui, rows| {
+                        for i in rows {
+                            let index = visible[i];
+                            let entry = &self.journal.entries()[index];
+                            let mut checked = self.journal_selected.contains(&index);
+                            let text = format!(
+                                "{}  PID {} {}  {}",
+                                entry.at.format("%m-%d %H:%M:%S"),
+                                entry.pid,
+                                entry.session,
+                                entry.command
+                            );
+                            if ui.checkbox(&mut checked, egui::RichText::new(text).monospace()).changed() {
+                                if checked {
+                                    self.journal_selected.insert(index);
+                                } else {
+                                    self.journal_selected.remove(&index);
+                                }
+                            }
+                        }
+                    });
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} selected", self.journal_selected.len()));
+                    if ui.button("Select shown").clicked() {
+                        self.journal_selected.extend(visible.iter().copied());
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.journal_selected.clear();
+                    }
+                    ui.separator();
+                    ui.label("Replay to:");
+                    let target_label = match self.replay_target {
+                        Some(pid) => format!("PID {}", pid),
+                        None => "choose a console".to_string(),
+                    };
+                    egui::ComboBox::from_id_salt("replay_target")
+                        .selected_text(target_label)
+                        .show_ui(ui, |ui| {
+                            for p in self.cmd_processes.iter().filter(|p| p.attachable) {
+                                let title = p.window_title.as_deref().unwrap_or("");
+                                ui.selectable_value(&mut self.replay_target, Some(p.pid), format!("PID {} {}", p.pid, title));
+                            }
+                        });
+                    let can_replay = self.replay_target.is_some() && !self.journal_selected.is_empty();
+                    ui.add_enabled_ui(can_replay, |ui| {
+                        if ui.button("Replay selection").clicked() {
+                            self.replay_journal_selection();
+                        }
+                    });
+                });
+            });
+    }
+
+    /// Render palette, intensity and contrast controls
+    fn render_color_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Colors")
+            .id_salt("color_settings")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Palette:");
+                    egui::ComboBox::from_id_salt("palette_preset")
+                        .selected_text(self.colors.preset.label())
+                        .show_ui(ui, |ui| {
+                            for preset in PalettePreset::ALL {
+                                ui.selectable_value(&mut self.colors.preset, preset, preset.label());
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Intensity:");
+                    ui.radio_value(&mut self.colors.intensity, IntensityMode::BrightColor, "Bright color");
+                    ui.radio_value(&mut self.colors.intensity, IntensityMode::BoldFont, "Bold");
+                    ui.radio_value(&mut self.colors.intensity, IntensityMode::Both, "Both");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Minimum contrast:");
+                    ui.add(egui::Slider::new(&mut self.colors.min_contrast, 1.0..=7.0).step_by(0.5));
+                });
+                if ui.checkbox(&mut self.decode_vt, "Decode VT escape sequences")
+                    .on_hover_text("Apply colors and cursor moves from ESC sequences that ended up as text in the buffer")
+                    .changed()
+                {
+                    self.reconfigure_worker();
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Text was written as:");
+                    let before = self.text_decoding;
+                    egui::ComboBox::from_id_salt("text_decoding")
+                        .selected_text(self.text_decoding.label())
+                        .show_ui(ui, |ui| {
+                            for decoding in TextDecoding::ALL {
+                                ui.selectable_value(&mut self.text_decoding, decoding, decoding.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text("Repair garbled output from programs that wrote bytes in another code page than the console's");
+                    if self.text_decoding != before {
+                        self.reconfigure_worker();
+                    }
+                });
+            });
+    }
+
+    /// Render the stitched history with search and virtualized rows
+    fn render_history(&mut self, ui: &mut egui::Ui) {
+        let title = format!(
+            "History ({} lines, {:.1} MB in RAM, {} on disk)",
+            self.history.len(),
+            self.history.memory_bytes() as f64 / (1024.0 * 1024.0),
+            self.history.spilled_lines()
+        );
+        egui::CollapsingHeader::new(title)
+            .id_salt("history")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("RAM cap (MB):");
+                    if ui.add(egui::Slider::new(&mut self.history_ram_cap_mb, 1..=1024)).changed()
+                        && let Err(e) = self.history.set_ram_cap(self.history_ram_cap_mb * 1024 * 1024)
+                    {
+                        self.last_error = Some(format!("Scrollback spill failed: {}", e));
+                    }
+                    ui.separator();
+                    ui.label("Search:");
+                    let response = ui.text_edit_singleline(&mut self.history_search);
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        self.history_hits = self.history.search(&self.history_search, 1000);
+                    }
+                    if !self.history_hits.is_empty() {
+                        ui.label(format!("{} matches", self.history_hits.len()));
+                    }
+                });
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                let history = &mut self.history;
+                let hits = &self.history_hits;
+                egui::ScrollArea::vertical()
+                    .id_salt("history_rows")
+                    .max_height(200.0)
+                    .show_rows(ui, row_height, history.len(), |ui, rows| {
+                        for i in rows {
+                            let line = history.get(i).unwrap_or_default();
+                            if hits.binary_search(&i).is_ok() {
+                                ui.label(egui::RichText::new(line).background_color(egui::Color32::from_rgb(90, 80, 0)));
+                            } else {
+                                ui.label(line);
+                            }
+                        }
+                    });
+            });
+    }
+
+    /// Act on a session event from the worker
+    fn worker_event(&mut self, event: WorkerEvent) {
+        match event {
+            WorkerEvent::TitleChanged { title } => self.console_title = Some(title),
+            WorkerEvent::BufferCleared => {
+                if let Err(e) = self.history.push(HISTORY_CLEAR_MARKER.to_string()) {
+                    self.last_error = Some(format!("Scrollback spill failed: {}", e));
+                }
+                self.output_selection = None;
+            }
+            WorkerEvent::Reconnecting { attempt } => {
+                if let Some(pid) = self.attached_pid {
+                    self.status_message = format!("Reconnecting to PID {} (attempt {} of {})...", pid, attempt, self.reconnect_attempts);
+                }
+            }
+            event => self.status_message = event.describe(),
+        }
+    }
+
+    /// Tell the worker to forward only lines matching `spec`, or all of them
+    fn apply_output_filter(&mut self, spec: Option<FilterSpec>) {
+        if let Some(Err(e)) = spec.as_ref().map(|s| regex::Regex::new(&s.pattern)) {
+            self.last_error = Some(format!("Invalid filter: {}", e));
+            return;
+        }
+        repro::record(TraceKind::Ui, &format!("set_filter active={}", spec.is_some()));
+        self.output_filter_applied = spec;
+        self.reconfigure_worker();
+    }
+
+    /// Render the worker-side output filter
+    fn render_output_filter(&mut self, ui: &mut egui::Ui) {
+        let title = match &self.output_filter_applied {
+            Some(spec) => format!("Filter output: /{}/", spec.pattern),
+            None => "Filter output".to_string(),
+        };
+        egui::CollapsingHeader::new(title)
+            .id_salt("output_filter")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Regex:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.output_filter)
+                        .hint_text("error|warning")
+                        .desired_width(200.0));
+                    ui.label("Context:");
+                    ui.add(egui::DragValue::new(&mut self.output_filter_context).range(0..=50).suffix(" lines"));
+                    let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button("Apply").clicked() || submitted) && !self.output_filter.is_empty() {
+                        self.apply_output_filter(Some(FilterSpec {
+                            pattern: self.output_filter.clone(),
+                            context: self.output_filter_context,
+                        }));
+                    }
+                    ui.add_enabled_ui(self.output_filter_applied.is_some(), |ui| {
+                        if ui.button("Clear").clicked() {
+                            self.apply_output_filter(None);
+                        }
+                    });
+                });
+                ui.weak("The worker forwards only matching lines and their context; worker history keeps every line");
+            });
+    }
+
+    /// Render the scheduled capture controls and the text of the last capture
+    fn render_scheduled_capture(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Scheduled capture")
+            .id_salt("scheduled_capture")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Delay:");
+                    ui.add(egui::DragValue::new(&mut self.capture_delay_secs).range(0.0..=600.0).speed(0.1).suffix(" s"));
+                    ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
+                        if ui.button("Capture").on_hover_text("Read the screen once after the delay, even while updates are paused").clicked() {
+                            self.schedule_capture();
+                        }
+                    });
+                    ui.checkbox(&mut self.capture_after_send, "After each sent command");
+                });
+                if let Some((at, text)) = &self.last_capture {
+                    ui.weak(format!("Captured {:.0} s ago", at.elapsed().as_secs_f32()));
+                    egui::ScrollArea::vertical()
+                        .id_salt("scheduled_capture_text")
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            ui.add(egui::TextEdit::multiline(&mut text.as_str())
+                                .font(egui::TextStyle::Monospace)
+                                .desired_width(f32::INFINITY));
+                        });
+                }
+            });
+    }
+
+    /// Render the controls for reading a rectangle of the buffer, such as a
+    /// status line, and the text it held when last read
+    fn render_region_read(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Read region")
+            .id_salt("region_read")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Rows:");
+                    ui.add(egui::DragValue::new(&mut self.region.top).range(0..=i16::MAX));
+                    ui.label("to");
+                    ui.add(egui::DragValue::new(&mut self.region.bottom).range(self.region.top..=i16::MAX));
+                    ui.label("Columns:");
+                    ui.add(egui::DragValue::new(&mut self.region.left).range(0..=i16::MAX));
+                    ui.label("to");
+                    ui.add(egui::DragValue::new(&mut self.region.right).range(self.region.left..=i16::MAX));
+                    ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
+                        if ui.button("Read").on_hover_text("Read just these buffer cells, without a full-buffer read").clicked() {
+                            self.dispatch_input(UiMessage::ReadRegion(self.region));
+                        }
+                    });
+                });
+                match &self.last_region {
+                    Some(Ok(text)) => {
+                        ui.add(egui::TextEdit::multiline(&mut text.as_str())
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY));
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    None => {}
+                }
+            });
+    }
+
+    /// Render the worker's history ring, fetching the rows in view as they
+    /// scroll past
+    fn render_worker_history(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("Worker history ({} lines)", self.worker_history.total))
+            .id_salt("worker_history")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Keep:");
+                    if ui.add(egui::DragValue::new(&mut self.worker_history_lines).range(1000..=1_000_000).speed(1000).suffix(" lines"))
+                        .changed()
+                    {
+                        self.reconfigure_worker();
+                    }
+                });
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                let view = &self.worker_history;
+                let visible = egui::ScrollArea::vertical()
+                    .id_salt("worker_history_rows")
+                    .max_height(200.0)
+                    .stick_to_bottom(true)
+                    .show_rows(ui, row_height, view.total, |ui, rows| {
+                        for i in rows.clone() {
+                            let line = i.checked_sub(view.first).and_then(|k| view.lines.get(k));
+                            match line {
+                                Some(line) => ui.monospace(line),
+                                None => ui.weak("…"),
+                            };
+                        }
+                        rows
+                    })
+                    .inner;
+
+                // Ask for the rows in view and a page either side when they are
+                // missing, and refresh them every second as new lines arrive
+                let view = &self.worker_history;
+                let covered = view.first <= visible.start && visible.end <= view.first + view.lines.len();
+                let stale = view.fetched.is_none_or(|t| t.elapsed() > Duration::from_secs(1));
+                let waiting = view.requested.is_some_and(|t| t.elapsed() < Duration::from_secs(2));
+                if (!covered || stale) && !waiting {
+                    let request = UiMessage::GetHistory {
+                        from: visible.start.saturating_sub(WORKER_HISTORY_PAGE),
+                        to: visible.end.max(visible.start + 1) + WORKER_HISTORY_PAGE,
+                    };
+                    if let Some(worker) = self.worker()
+                        && worker.send(request).is_ok()
+                    {
+                        self.worker_history.requested = Some(Instant::now());
+                    }
+                }
+            });
+    }
+
+    /// Rows the worker should read: the visible screen, the whole buffer or the lines before the cursor
+    fn read_window(&self) -> ReadWindow {
+        if self.read_viewport {
+            ReadWindow::Viewport
+        } else if self.read_full_buffer {
+            ReadWindow::All
+        } else {
+            ReadWindow::ToCursor(self.lines_to_display)
+        }
+    }
+
+    /// Bounds for the output-following poll interval, if enabled
+    fn adaptive_interval(&self) -> Option<AdaptiveInterval> {
+        self.adaptive_poll.then(|| AdaptiveInterval {
+            min: Duration::from_millis(self.adaptive_min_ms),
+            max: Duration::from_millis(self.adaptive_max_ms.max(self.adaptive_min_ms)),
+        })
+    }
+
+    /// CPU budget for a poll, if enabled
+    fn poll_budget(&self) -> Option<Duration> {
+        self.poll_budget_enabled.then(|| Duration::from_millis(self.poll_budget_ms))
+    }
+
+    /// Render the low-priority capture and poll budget controls
+    fn render_throttle_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.low_priority_capture, "Low-priority capture").changed() {
+                self.reconfigure_worker();
+            }
+
+            if ui.checkbox(&mut self.event_driven_capture, "Read on change")
+                .on_hover_text("Read as soon as the console window draws output instead of every refresh interval; consoles hosted in Windows Terminal are still polled")
+                .changed()
+            {
+                self.reconfigure_worker();
+            }
+
+            if ui.checkbox(&mut self.persistent_attach, "Stay attached")
+                .on_hover_text("Keep the console attached between polls instead of attaching for each one; falls back on its own when other sessions or actions keep taking the console. While attached, closing the target's window also closes this app.")
+                .changed()
+            {
+                self.reconfigure_worker();
+            }
+
+            let mut changed = ui.checkbox(&mut self.poll_budget_enabled, "Skip poll after one slower than").changed();
+            ui.add_enabled_ui(self.poll_budget_enabled, |ui| {
+                changed |= ui.add(egui::DragValue::new(&mut self.poll_budget_ms).range(1..=1000).suffix(" ms")).changed();
+            });
+            if changed {
+                self.reconfigure_worker();
+            }
+
+            ui.separator();
+            ui.label("Read chunk:");
+            if ui.add(egui::DragValue::new(&mut self.read_chunk_cells).range(256..=16000).suffix(" cells"))
+                .on_hover_text("Split reads of very wide or tall buffers into calls of at most this many cells")
+                .changed()
+            {
+                set_read_chunk_cells(self.read_chunk_cells);
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut changed = ui.checkbox(&mut self.adaptive_poll, "Adaptive interval")
+                .on_hover_text("Poll at the fastest rate while output changes and slow down to the slowest while it is quiet, instead of the fixed interval")
+                .changed();
+            ui.add_enabled_ui(self.adaptive_poll, |ui| {
+                changed |= ui.add(egui::DragValue::new(&mut self.adaptive_min_ms).range(20..=2000).prefix("fastest ").suffix(" ms")).changed();
+                changed |= ui.add(egui::DragValue::new(&mut self.adaptive_max_ms).range(100..=10_000).prefix("slowest ").suffix(" ms")).changed();
+            });
+            if changed {
+                self.reconfigure_worker();
+            }
+
+            ui.separator();
+            ui.label("Reconnect attempts:");
+            if ui.add(egui::DragValue::new(&mut self.reconnect_attempts).range(0..=8))
+                .on_hover_text("Retries, each waiting twice as long, before a console that cannot be reattached counts as disconnected")
+                .changed()
+            {
+                self.reconfigure_worker();
+            }
+        });
+    }
+
+    /// Severity of an output row, from the colors it was printed in
+    fn line_severity(&self, i: usize) -> Option<Severity> {
+        let runs = self.console_colors.get(i).map(|r| r.as_slice()).unwrap_or(&[]);
+        classify_severity(&self.console_output[i], runs)
+    }
+
+    /// Select and scroll to the next (or previous) error or warning line
+    fn jump_to_flagged(&mut self, forward: bool) {
+        let from = self.output_selection.map(|(a, _)| a);
+        let flagged = |i: &usize| self.line_severity(*i).is_some();
+        let target = if forward {
+            let start = from.map_or(0, |f| f + 1);
+            (start..self.console_output.len()).find(flagged)
+        } else {
+            let end = from.unwrap_or(self.console_output.len());
+            (0..end).rev().find(flagged)
+        };
+        match target {
+            Some(i) => {
+                self.output_selection = Some((i, i));
+                self.jump_to_line = Some(i);
+                self.auto_scroll = false;
+            }
+            None => {
+                self.status_message = "No more error or warning lines".to_string();
+            }
+        }
+    }
+
+    /// Render the worker's rates and counters from its last stats period
+    fn render_worker_stats(&mut self, ui: &mut egui::Ui) {
+        let Some(stats) = self.worker_stats else {
+            return;
+        };
+        egui::CollapsingHeader::new("Diagnostics")
+            .id_salt("worker_stats")
+            .default_open(false)
+            .show(ui, |ui| {
+                egui::Grid::new("worker_stats_grid").num_columns(2).striped(true).show(ui, |ui| {
+                    let rows = [
+                        ("Polls", format!("{:.1}/s", stats.per_second(stats.polls as f64))),
+                        ("Mean poll", millis(stats.mean_poll)),
+                        ("Slowest poll", millis(stats.slowest_poll)),
+                        ("Updates", format!("{:.1}/s", stats.per_second(stats.updates as f64))),
+                        ("Lines", format!("{:.0}/s", stats.per_second(stats.lines as f64))),
+                        ("Text", format!("{:.1} KB/s", stats.per_second(stats.bytes as f64) / 1024.0)),
+                        ("Attach failures", stats.attach_failures.to_string()),
+                    ];
+                    for (name, value) in rows {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
                     }
-                    Some(WorkerMessage::Error(e)) => {
-                        self.last_error = Some(e);
+                });
+                ui.weak(format!("Over the last {:.1} s", stats.period.as_secs_f64()));
+            });
+    }
+
+    /// Render where local polls spend their time
+    fn render_poll_timings(&mut self, ui: &mut egui::Ui) {
+        let Some(last) = self.poll_timings else {
+            return;
+        };
+        egui::CollapsingHeader::new(format!("Poll timing ({})", millis(last.total)))
+            .id_salt("poll_timings")
+            .default_open(false)
+            .show(ui, |ui| {
+                egui::Grid::new("poll_timings_grid").num_columns(3).striped(true).show(ui, |ui| {
+                    ui.label("");
+                    ui.strong("Last");
+                    ui.strong("Slowest");
+                    ui.end_row();
+                    let slowest = self.slowest_poll.unwrap_or(last);
+                    let rows: [TimingRow; 6] = [
+                        ("Attach", |t| millis(t.attach)),
+                        ("Buffer info", |t| millis(t.read.buffer_info)),
+                        ("Read", |t| millis(t.read.read)),
+                        ("Convert", |t| millis(t.read.convert)),
+                        ("Read calls", |t| t.read.read_calls.to_string()),
+                        ("Total", |t| millis(t.total)),
+                    ];
+                    for (name, value) in rows {
+                        ui.label(name);
+                        ui.label(value(&last));
+                        ui.label(value(&slowest));
+                        ui.end_row();
                     }
-                    Some(WorkerMessage::Status(s)) => {
-                        self.status_message = s;
+                });
+                if ui.button("Reset slowest").clicked() {
+                    self.slowest_poll = None;
+                }
+            });
+    }
+
+    /// Render the dry run toggle and the input records it described
+    fn render_dry_run(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Dry run")
+            .id_salt("dry_run")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.dry_run, "Show input records instead of sending")
+                        .on_hover_text("Sends are decoded into the INPUT_RECORDs that would reach CONIN$; nothing is written")
+                        .changed()
+                    {
+                        self.apply_dry_run();
                     }
-                    Some(WorkerMessage::Disconnected) => {
-                        disconnected = true;
-                        self.attached_pid = None;
-                        self.status_message = "Disconnected".to_string();
-                        self.last_error = Some("Console disconnected".to_string());
+                    if ui.button("Clear").clicked() {
+                        self.dry_run_log.clear();
                     }
-                    None => break,
-                }
-            }
+                });
+                egui::ScrollArea::vertical()
+                    .id_salt("dry_run_log")
+                    .max_height(200.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in &self.dry_run_log {
+                            ui.monospace(line);
+                        }
+                    });
+            });
+    }
 
-            if disconnected {
-                self.worker = None;
+    /// Render the auto-reattach settings
+    fn render_reattach_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.auto_reattach, "Auto-reattach by")
+                .on_hover_text("When the attached process exits, attach to the next process matching the pattern");
+            ui.radio_value(&mut self.reattach_match, ReattachMatch::WindowTitle, "title");
+            ui.radio_value(&mut self.reattach_match, ReattachMatch::CommandLine, "command line");
+            ui.add(egui::TextEdit::singleline(&mut self.reattach_pattern)
+                .hint_text("pattern, * and ? allowed")
+                .desired_width(200.0));
+            if self.reattach_lost_pid.is_some() && ui.button("Stop waiting").clicked() {
+                self.reattach_lost_pid = None;
             }
-        }
+        });
     }
 
     /// Render the left panel (process list)
@@ -414,10 +2771,21 @@ impl RemoteConApp {
         egui::SidePanel::left("process_list").show(ctx, |ui| {
             ui.heading("CMD Processes");
 
-            // Refresh button
-            if ui.button("Refresh").clicked() {
-                self.refresh_process_list();
-            }
+            ui.horizontal(|ui| {
+                // Refresh button
+                if ui.button("Refresh").clicked() {
+                    self.refresh_process_list();
+                }
+
+                // Local shell in a pseudoconsole we own
+                if self.local_terminal.is_none() {
+                    if ui.button("New local terminal").clicked() {
+                        self.open_local_terminal();
+                    }
+                } else if ui.button("Close local terminal").clicked() {
+                    self.close_local_terminal();
+                }
+            });
 
             // Show error if any
             if let Some(ref err) = self.show_refresh_error {
@@ -453,6 +2821,10 @@ impl RemoteConApp {
                                         ui.label("Title: (no window)");
                                     }
 
+                                    if !proc.command_line.is_empty() {
+                                        ui.label(egui::RichText::new(&proc.command_line).small().weak());
+                                    }
+
                                     // Session and window info
                                     ui.label(format!("Session: {} | Window: {}",
                                         proc.session_id,
@@ -468,6 +2840,9 @@ impl RemoteConApp {
                                     ui.colored_label(status,
                                         if proc.attachable { "Attachable" } else { "Not attachable" }
                                     );
+                                    if proc.elevated {
+                                        ui.colored_label(egui::Color32::from_rgb(200, 120, 0), "Elevated (admin)");
+                                    }
                                 });
                             });
                         }).response;
@@ -487,10 +2862,10 @@ impl RemoteConApp {
                     }
 
                     // Handle double-click attach outside the loop to avoid borrow conflict
-                    if let Some(pid) = attach_on_double_click {
-                        if self.selected_pid == Some(pid) {
-                            self.attach_to_console();
-                        }
+                    if let Some(pid) = attach_on_double_click
+                        && self.selected_pid == Some(pid)
+                    {
+                        self.attach_to_console();
                     }
                 }
             });
@@ -526,6 +2901,21 @@ impl RemoteConApp {
                 }
             });
 
+            // Elevated targets need the UAC helper
+            let selected_elevated = self.selected_pid.is_some_and(|pid|
+                self.cmd_processes.iter().any(|p| p.pid == pid && p.elevated));
+            if selected_elevated && !is_current_process_elevated()
+                && ui.button("Attach (elevated helper)")
+                    .on_hover_text("Launches an elevated helper via UAC to access admin consoles")
+                    .clicked()
+            {
+                self.attach_elevated();
+            }
+
+            if let Some(ref err) = self.attach_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+
             ui.separator();
 
             // Detach button (always visible)
@@ -537,6 +2927,204 @@ impl RemoteConApp {
         });
     }
 
+    /// Render the profile picker; switching profiles loads that profile's settings
+    fn render_profile_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("Profile: {}", self.profiles.current()))
+            .default_open(false)
+            .show(ui, |ui| {
+                let mut selected = None;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("profile_select")
+                        .selected_text(self.profiles.current())
+                        .show_ui(ui, |ui| {
+                            for name in self.profiles.names() {
+                                if ui.selectable_label(name == self.profiles.current(), &name).clicked() {
+                                    selected = Some(name);
+                                }
+                            }
+                        });
+                    ui.add(egui::TextEdit::singleline(&mut self.new_profile_name)
+                        .hint_text("new profile")
+                        .desired_width(120.0));
+                    let name = self.new_profile_name.trim().to_string();
+                    if ui.add_enabled(!name.is_empty(), egui::Button::new("Create")).clicked() {
+                        selected = Some(name);
+                        self.new_profile_name.clear();
+                    }
+                });
+                ui.label("New profiles start as a copy of the current one. Passwords and secret keys are not saved.");
+                if let Some(name) = selected {
+                    self.switch_profile(&name);
+                }
+            });
+    }
+
+    /// Make another profile current and load its settings
+    fn switch_profile(&mut self, name: &str) {
+        if name == self.profiles.current() {
+            return;
+        }
+        self.profiles.select(name);
+        self.load_profile();
+        self.save_profiles();
+        // Move the window to where this profile had it
+        self.placement_restored = false;
+        self.status_message = format!("Switched to profile {}", name);
+    }
+
+    /// Restore the main window's placement for the current profile once the
+    /// window exists, and save it again after the user moves or resizes it
+    fn poll_window_placement(&mut self, ctx: &egui::Context) {
+        let Some(hwnd) = main_window() else {
+            return;
+        };
+        let profile = self.profiles.current().to_string();
+        if !self.placement_restored {
+            let _ = self.placements.apply(&profile, MAIN_WINDOW_KEY, hwnd);
+            self.placement_restored = true;
+        }
+
+        let rect = ctx.input(|i| {
+            let viewport = i.viewport();
+            viewport.outer_rect.map(|r| (r, viewport.maximized.unwrap_or(false)))
+        });
+        if rect.is_some() && rect != self.last_window_rect {
+            // The first rect seen is where the window opened, not a move
+            if self.last_window_rect.is_some() {
+                self.window_moved_at = Some(Instant::now());
+            }
+            self.last_window_rect = rect;
+        }
+        if let Some(moved) = self.window_moved_at {
+            let wait = PLACEMENT_SAVE_DELAY.saturating_sub(moved.elapsed());
+            if wait.is_zero() {
+                self.window_moved_at = None;
+                let saved = self.placements.remember(&profile, MAIN_WINDOW_KEY, hwnd)
+                    .and_then(|_| self.placements.save());
+                if let Err(e) = saved {
+                    self.last_error = Some(format!("Failed to save window placement: {}", e));
+                }
+            } else {
+                ctx.request_repaint_after(wait);
+            }
+        }
+    }
+
+    /// Apply the current profile's settings
+    fn load_profile(&mut self) {
+        self.storage = StorageConfig::from_settings(self.profiles.settings());
+        #[cfg(feature = "automation")]
+        {
+            self.keep_alive_settings = KeepAliveSettings::from_settings(self.profiles.settings());
+        }
+    }
+
+    /// Write the profile store, reporting failures in the status bar
+    fn save_profiles(&mut self) {
+        if let Err(e) = self.profiles.save() {
+            self.last_error = Some(format!("Failed to save profiles: {}", e));
+        }
+    }
+
+    /// Render the storage backend selector
+    fn render_storage_settings(&mut self, ui: &mut egui::Ui) {
+        let before = self.storage.to_settings();
+        egui::CollapsingHeader::new(format!("Storage: {}", self.storage.kind_label()))
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let local = matches!(self.storage, StorageConfig::LocalFolder { .. });
+                    let share = matches!(self.storage, StorageConfig::NetworkShare { .. });
+                    #[cfg(feature = "web")]
+                    let s3 = matches!(self.storage, StorageConfig::S3 { .. });
+                    if ui.radio(local, "Local folder").clicked() && !local {
+                        self.storage = StorageConfig::default();
+                    }
+                    if ui.radio(share, "Network share").clicked() && !share {
+                        self.storage = StorageConfig::NetworkShare {
+                            unc_path: String::new(),
+                            username: String::new(),
+                            password: String::new(),
+                        };
+                    }
+                    #[cfg(feature = "web")]
+                    if ui.radio(s3, "S3-compatible").clicked() && !s3 {
+                        self.storage = StorageConfig::S3 {
+                            endpoint: String::new(),
+                            region: "us-east-1".to_string(),
+                            bucket: String::new(),
+                            prefix: String::new(),
+                            access_key: String::new(),
+                            secret_key: String::new(),
+                        };
+                    }
+                });
+
+                egui::Grid::new("storage_settings").num_columns(2).show(ui, |ui| {
+                    match &mut self.storage {
+                        StorageConfig::LocalFolder { path } => {
+                            ui.label("Folder:");
+                            ui.text_edit_singleline(path);
+                            ui.end_row();
+                        }
+                        StorageConfig::NetworkShare { unc_path, username, password } => {
+                            ui.label("UNC path:");
+                            ui.add(egui::TextEdit::singleline(unc_path).hint_text(r"\\server\share\logs"));
+                            ui.end_row();
+                            ui.label("Username:");
+                            ui.add(egui::TextEdit::singleline(username).hint_text("(current user)"));
+                            ui.end_row();
+                            ui.label("Password:");
+                            ui.add(egui::TextEdit::singleline(password).password(true).hint_text("(not saved)"));
+                            ui.end_row();
+                        }
+                        #[cfg(feature = "web")]
+                        StorageConfig::S3 { endpoint, region, bucket, prefix, access_key, secret_key } => {
+                            ui.label("Endpoint:");
+                            ui.add(egui::TextEdit::singleline(endpoint).hint_text("https://s3.amazonaws.com"));
+                            ui.end_row();
+                            ui.label("Region:");
+                            ui.text_edit_singleline(region);
+                            ui.end_row();
+                            ui.label("Bucket:");
+                            ui.text_edit_singleline(bucket);
+                            ui.end_row();
+                            ui.label("Prefix:");
+                            ui.text_edit_singleline(prefix);
+                            ui.end_row();
+                            ui.label("Access key:");
+                            ui.text_edit_singleline(access_key);
+                            ui.end_row();
+                            ui.label("Secret key:");
+                            ui.add(egui::TextEdit::singleline(secret_key).password(true).hint_text("(not saved)"));
+                            ui.end_row();
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Saved logs:");
+                    ui.checkbox(&mut self.text_options.trim_trailing, "Trim trailing spaces");
+                    let mut expand = self.text_options.tab_width.is_some();
+                    if ui.checkbox(&mut expand, "Expand tabs to").changed() {
+                        self.text_options.tab_width = expand.then_some(8);
+                    }
+                    if let Some(width) = &mut self.text_options.tab_width {
+                        ui.add(egui::DragValue::new(width).range(1..=16));
+                    }
+                    ui.label("Line endings:");
+                    for ending in LineEnding::ALL {
+                        ui.radio_value(&mut self.text_options.line_ending, ending, ending.label());
+                    }
+                });
+            });
+
+        if self.storage.to_settings() != before {
+            self.storage.save_to(&mut self.profiles);
+            self.save_profiles();
+        }
+    }
+
     /// Show the context menu for attaching to a process
     fn show_context_menu_ui(&mut self, ctx: &egui::Context) {
         if !self.show_context_menu {
@@ -582,10 +3170,10 @@ impl RemoteConApp {
                 if ui.input(|i| i.pointer.any_released()) {
                     // Check if click was outside the menu
                     let menu_rect = ui.min_rect();
-                    if let Some(click_pos) = ui.input(|i| i.pointer.press_origin()) {
-                        if !menu_rect.contains(click_pos) {
-                            self.show_context_menu = false;
-                        }
+                    if let Some(click_pos) = ui.input(|i| i.pointer.press_origin())
+                        && !menu_rect.contains(click_pos)
+                    {
+                        self.show_context_menu = false;
                     }
                 }
             });
@@ -594,7 +3182,10 @@ impl RemoteConApp {
     /// Render the right panel (console viewer)
     fn render_console_viewer(&mut self, ctx: &egui::Context) {
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Console Output");
+            match self.console_title.as_deref().filter(|t| !t.is_empty()) {
+                Some(title) => ui.heading(format!("Console Output - {}", title)),
+                None => ui.heading("Console Output"),
+            };
 
             // Attach/Detach buttons at top
             let can_attach = self.selected_pid.is_some() &&
@@ -616,9 +3207,34 @@ impl RemoteConApp {
                     if ui.button("Detach").clicked() {
                         self.detach_from_console();
                     }
+                    let label = if self.updates_paused { "Resume updates" } else { "Pause updates" };
+                    if ui.button(label)
+                        .on_hover_text("Stop refreshing the output while you read it; the session stays attached and input still goes through")
+                        .clicked()
+                    {
+                        self.set_updates_paused(!self.updates_paused);
+                    }
                 });
+                if self.updates_paused {
+                    ui.colored_label(egui::Color32::YELLOW, "Paused");
+                }
+                if let Some(pid) = self.final_screen_of {
+                    ui.weak(format!("Last screen of PID {}", pid));
+                }
             });
 
+            // Console sharing and ownership changes
+            self.render_console_sharing(ui);
+
+            // Selection made in the target's window
+            self.render_target_selection(ui);
+
+            // Input/output modes of the target console
+            self.render_console_modes(ui);
+
+            // Font and window size of the target console
+            self.render_console_metrics(ui);
+
             ui.separator();
 
             // Quick command input at top
@@ -630,6 +3246,10 @@ impl RemoteConApp {
                         .hint_text("Type quick command here...")
                         .desired_width(f32::INFINITY)
                 );
+                #[cfg(feature = "automation")]
+                if response.changed() {
+                    self.keep_alive.note_typing();
+                }
 
                 // Send on Enter
                 if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
@@ -637,13 +3257,49 @@ impl RemoteConApp {
                 }
 
                 // Send button
-                ui.add_enabled_ui(self.attached_pid.is_some() && !self.command_input_top.trim().is_empty(), |ui| {
-                    if ui.button("Send").clicked() {
+                let label = if self.should_queue() { "Queue" } else { "Send" };
+                ui.add_enabled_ui(!self.command_input_top.trim().is_empty(), |ui| {
+                    if ui.button(label).clicked() {
                         self.send_command_from_top();
                     }
                 });
             });
 
+            // Navigation and function key palette
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Keys:");
+                ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
+                    for key in VirtualKey::PALETTE {
+                        if ui.small_button(key.label()).clicked() {
+                            self.send_key(key);
+                        }
+                    }
+                    ui.separator();
+                    ui.add(egui::TextEdit::singleline(&mut self.alt_input)
+                        .hint_text("F / 130")
+                        .desired_width(48.0));
+                    let input = self.alt_input.trim();
+                    let one_key = input.chars().count() == 1;
+                    let digits = !input.is_empty() && input.chars().all(|c| c.is_ascii_digit());
+                    if ui.add_enabled(one_key, egui::Button::new("Alt+key").small())
+                        .on_hover_text("Press Alt with this key, e.g. to open a menu")
+                        .clicked()
+                    {
+                        self.send_alt(false);
+                    }
+                    if ui.add_enabled(digits, egui::Button::new("Alt+numpad").small())
+                        .on_hover_text("Type the character with this Alt+numpad code")
+                        .clicked()
+                    {
+                        self.send_alt(true);
+                    }
+                    ui.separator();
+                    let can_forward = self.can_forward_mouse();
+                    ui.add_enabled(can_forward, egui::Checkbox::new(&mut self.forward_mouse, "Forward clicks"))
+                        .on_hover_text("Click the output to click the target; needs Visible screen and a program reading mouse input");
+                });
+            });
+
             // Control character buttons
             ui.horizontal(|ui| {
                 ui.label("Send:");
@@ -671,6 +3327,55 @@ impl RemoteConApp {
                     }
                 });
 
+                // Other Ctrl+<letter> keys for interactive programs
+                ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
+                    let mut chosen = None;
+                    egui::ComboBox::from_id_salt("ctrl_key")
+                        .selected_text("Ctrl+…")
+                        .show_ui(ui, |ui| {
+                            for (letter, meaning) in CTRL_KEYS {
+                                if ui.selectable_label(false, format!("Ctrl+{}", letter))
+                                    .on_hover_text(meaning)
+                                    .clicked()
+                                {
+                                    chosen = Some(letter);
+                                }
+                            }
+                        });
+                    if let Some(letter) = chosen {
+                        self.send_ctrl_key(letter);
+                    }
+                });
+
+                // End of input for programs reading stdin until EOF
+                ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
+                    if ui.button("EOF")
+                        .on_hover_text("Ctrl+Z, the Windows end of input (copy con, python)")
+                        .clicked()
+                    {
+                        self.send_eof();
+                    }
+                    ui.checkbox(&mut self.eof_enter, "+Enter")
+                        .on_hover_text("Press Enter after Ctrl+Z; most programs only see it at the end of a line");
+                });
+
+                // Clear the target's screen
+                ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
+                    ui.menu_button("Clear target", |ui| {
+                        if ui.button("Send cls").clicked() {
+                            self.clear_target(false);
+                            ui.close_menu();
+                        }
+                        if ui.button("Erase buffer")
+                            .on_hover_text("Blank the screen buffer directly; works while a program is running")
+                            .clicked()
+                        {
+                            self.clear_target(true);
+                            ui.close_menu();
+                        }
+                    });
+                });
+
                 ui.separator();
 
                 // Save button
@@ -679,6 +3384,34 @@ impl RemoteConApp {
                         self.save_conversation();
                     }
                 });
+
+                // Buffer snapshot button
+                ui.add_enabled_ui(self.attached_pid.is_some() && !self.attached_elevated, |ui| {
+                    if ui.button("Snapshot")
+                        .on_hover_text("Save the whole screen buffer with colors and cursor as JSON")
+                        .clicked()
+                    {
+                        self.snapshot_buffer();
+                    }
+                    if ui.button("Screenshot")
+                        .on_hover_text("Save the target's visible window as a PNG for bug reports")
+                        .clicked()
+                    {
+                        self.screenshot_console();
+                    }
+                });
+
+                // Repro recording button
+                let repro_label = match repro::remaining() {
+                    Some(left) => format!("Stop repro ({} s)", left.as_secs()),
+                    None => "Record repro".to_string(),
+                };
+                if ui.button(repro_label)
+                    .on_hover_text("Capture a scrubbed trace of UI actions, worker messages and Win32 results for a bug report")
+                    .clicked()
+                {
+                    self.toggle_repro_recording();
+                }
             });
 
             ui.separator();
@@ -688,9 +3421,58 @@ impl RemoteConApp {
                 ui.label(&self.status_message);
                 ui.separator();
 
+                // Live title of the target console
+                if let Some(title) = self.console_title.as_deref().filter(|t| !t.is_empty()) {
+                    ui.label(format!("Title: {}", title));
+                    ui.separator();
+                }
+
+                // A visible cursor usually means the target waits at a prompt
+                if let Some(shape) = self.cursor_shape {
+                    if shape.visible {
+                        ui.label(format!("Cursor visible ({}%)", shape.size))
+                            .on_hover_text("The target is probably waiting for input");
+                    } else {
+                        ui.colored_label(egui::Color32::GRAY, "Cursor hidden")
+                            .on_hover_text("A command or full-screen app is probably running");
+                    }
+                    ui.separator();
+                }
+
+                // Target liveness
+                if let Some(alive) = self.target_alive {
+                    let seen = self.target_last_seen
+                        .map(|t| format!("last seen {}s ago", t.elapsed().as_secs()))
+                        .unwrap_or_else(|| "not seen yet".to_string());
+                    if alive {
+                        ui.colored_label(egui::Color32::DARK_GREEN, format!("Target alive / {}", seen));
+                    } else {
+                        ui.colored_label(egui::Color32::RED, format!("Target exited / {}", seen));
+                    }
+                    ui.separator();
+                }
+
                 // Lines to display slider
                 ui.label("Lines:");
-                ui.add(egui::Slider::new(&mut self.lines_to_display, 10..=500));
+                let mut window_changed = false;
+                ui.add_enabled_ui(!self.read_full_buffer && !self.read_viewport, |ui| {
+                    window_changed |= ui.add(egui::Slider::new(&mut self.lines_to_display, 10..=500)).changed();
+                });
+                ui.add_enabled_ui(!self.read_viewport, |ui| {
+                    window_changed |= ui.checkbox(&mut self.read_full_buffer, "Whole buffer").changed();
+                });
+                window_changed |= ui.checkbox(&mut self.read_viewport, "Visible screen")
+                    .on_hover_text("Read only what the target's window shows, e.g. a full-screen TUI")
+                    .changed();
+                if ui.checkbox(&mut self.keep_trailing, "Keep trailing spaces")
+                    .on_hover_text("Keep every cell of a row so column-aligned tables line up")
+                    .changed()
+                {
+                    self.reconfigure_worker();
+                }
+                if window_changed {
+                    self.reconfigure_worker();
+                }
 
                 // Refresh interval slider
                 ui.label("Interval (ms):");
@@ -698,18 +3480,120 @@ impl RemoteConApp {
                 if ui.add(egui::Slider::new(&mut interval, 50..=2000)).changed() {
                     self.refresh_interval_ms = interval as u64;
                     // Update worker interval
-                    if let Some(worker) = &self.worker {
-                        let _ = worker.send(UiMessage::SetInterval(Duration::from_millis(self.refresh_interval_ms)));
-                    }
+                    self.reconfigure_worker();
                 }
             });
 
             // Auto-scroll checkbox
-            ui.checkbox(&mut self.auto_scroll, "Auto-scroll to bottom");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.auto_scroll, "Auto-scroll to bottom");
+                ui.separator();
+                let (errors, warnings) = (0..self.console_output.len())
+                    .filter_map(|i| self.line_severity(i))
+                    .fold((0, 0), |(e, w), s| match s {
+                        Severity::Error => (e + 1, w),
+                        Severity::Warning => (e, w + 1),
+                    });
+                ui.colored_label(egui::Color32::RED, format!("{} errors", errors));
+                ui.colored_label(egui::Color32::YELLOW, format!("{} warnings", warnings));
+                ui.add_enabled_ui(errors + warnings > 0, |ui| {
+                    if ui.button("Prev").on_hover_text("Previous red or yellow line").clicked() {
+                        self.jump_to_flagged(false);
+                    }
+                    if ui.button("Next").on_hover_text("Next red or yellow line").clicked() {
+                        self.jump_to_flagged(true);
+                    }
+                });
+            });
+
+            // Capture throttling
+            self.render_throttle_settings(ui);
+
+            // Where polls spend their time
+            self.render_poll_timings(ui);
+            self.render_worker_stats(ui);
+
+            // Input record inspector
+            self.render_dry_run(ui);
+
+            // Auto-reattach controls
+            self.render_reattach_settings(ui);
+
+            // Scrolled-off history
+            self.render_history(ui);
+
+            // Lines kept by the worker
+            self.render_worker_history(ui);
+
+            // One-shot reads after a delay
+            self.render_scheduled_capture(ui);
+
+            // Reads of part of the buffer
+            self.render_region_read(ui);
+
+            // Lines the worker forwards
+            self.render_output_filter(ui);
+
+            // Multi-line paste
+            self.render_paste(ui);
+
+            // Command placeholders
+            self.render_template_vars(ui);
+
+            // Dangerous-command confirmation
+            self.render_command_guard(ui);
+
+            // Session scratchpad
+            self.render_notes(ui);
+
+            // Commands sent across all sessions
+            #[cfg(feature = "automation")]
+            self.render_journal(ui);
+
+            // Which profile's settings are in use
+            self.render_profile_settings(ui);
+
+            // Palette and intensity rendering
+            self.render_color_settings(ui);
+
+            // Keep-alive input
+            #[cfg(feature = "automation")]
+            self.render_keep_alive_settings(ui);
+
+            // Automatic prompt answers
+            #[cfg(feature = "automation")]
+            self.render_responder_settings(ui);
+
+            // Storage destination for saved logs
+            self.render_storage_settings(ui);
+
+            // Output from the target we switched away from
+            let mut clear_previous = false;
+            if let Some((prev_pid, ref prev_lines)) = self.previous_output {
+                egui::CollapsingHeader::new(format!("Previous output (PID {})", prev_pid))
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if ui.button("Discard").clicked() {
+                            clear_previous = true;
+                        }
+                        egui::ScrollArea::vertical()
+                            .id_salt("previous_output")
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                for line in prev_lines {
+                                    ui.label(line);
+                                }
+                            });
+                    });
+            }
+            if clear_previous {
+                self.previous_output = None;
+            }
 
             ui.separator();
 
             // Console output area
+            let viewer_rows = (ui.available_height() / ui.text_style_height(&egui::TextStyle::Monospace)) as usize;
             egui::ScrollArea::vertical()
                 .show(ui, |ui| {
                     if self.console_output.is_empty() {
@@ -720,12 +3604,74 @@ impl RemoteConApp {
                             ui.label("Select a cmd.exe process and click Attach.");
                         }
                     } else {
-                        egui::Grid::new("console_output").show(ui, |ui| {
-                            for line in &self.console_output {
-                                ui.label(line);
-                                ui.end_row();
-                            }
-                        });
+                        let font = egui::TextStyle::Monospace.resolve(ui.style());
+                        let glyph = ui.fonts(|f| f.glyph_width(&font, 'M'));
+                        if glyph > 0.0 {
+                            let cols = (ui.available_width() / glyph) as usize;
+                            self.viewer_cols = Some(cols);
+                            self.fit_local_terminal(cols, viewer_rows);
+                        }
+                        egui::Frame::new()
+                            .fill(to_color32(self.colors.resolve(0x7, 0x0).bg))
+                            .show(ui, |ui| {
+                                ui.spacing_mut().item_spacing.y = 0.0;
+                                let selected = self.selected_output_rows();
+                                let mut click = None;
+                                for (i, line) in self.console_output.iter().enumerate() {
+                                    let runs = self.console_colors.get(i).map(|r| r.as_slice()).unwrap_or(&[]);
+                                    let response = ui.add(
+                                        egui::Label::new(colored_line(line, runs, &self.colors, &font))
+                                            .selectable(false)
+                                            .sense(egui::Sense::click()),
+                                    );
+                                    if let Some((_, col)) = self.console_cursor.filter(|(row, _)| *row == i) {
+                                        match self.cursor_shape {
+                                            Some(shape) if !shape.visible => {}
+                                            Some(shape) => draw_cursor(ui, response.rect, col, &font, shape.size),
+                                            None => draw_cursor(ui, response.rect, col, &font, DEFAULT_CURSOR_SIZE),
+                                        }
+                                    }
+                                    if let Some(severity) = classify_severity(line, runs) {
+                                        let color = match severity {
+                                            Severity::Error => egui::Color32::RED,
+                                            Severity::Warning => egui::Color32::YELLOW,
+                                        };
+                                        let mut bar = response.rect;
+                                        bar.set_width(2.0);
+                                        ui.painter().rect_filled(bar, 0.0, color);
+                                    }
+                                    if self.jump_to_line == Some(i) {
+                                        response.scroll_to_me(Some(egui::Align::Center));
+                                        self.jump_to_line = None;
+                                    }
+                                    if selected.as_ref().is_some_and(|r| r.contains(&i)) {
+                                        ui.painter().rect_filled(response.rect, 0.0, ui.visuals().selection.bg_fill.gamma_multiply(0.4));
+                                    }
+                                    let forward = self.forward_mouse && self.can_forward_mouse() && glyph > 0.0;
+                                    let button = if response.clicked() {
+                                        Some(MOUSE_LEFT)
+                                    } else if response.secondary_clicked() {
+                                        Some(MOUSE_RIGHT)
+                                    } else if response.middle_clicked() {
+                                        Some(MOUSE_MIDDLE)
+                                    } else {
+                                        None
+                                    };
+                                    if let (true, Some(button), Some(pos)) = (forward, button, response.interact_pointer_pos()) {
+                                        let col = ((pos.x - response.rect.left()) / glyph).max(0.0) as usize;
+                                        click = Some((col, i, button));
+                                    } else if response.clicked() {
+                                        let shift = ui.input(|inp| inp.modifiers.shift);
+                                        self.output_selection = match self.output_selection {
+                                            Some((anchor, _)) if shift => Some((anchor, i)),
+                                            _ => Some((i, i)),
+                                        };
+                                    }
+                                }
+                                if let Some((x, y, button)) = click {
+                                    self.send_mouse_click(x, y, button);
+                                }
+                            });
                     }
 
                     // Scroll to bottom if auto-scroll is enabled
@@ -740,18 +3686,52 @@ impl RemoteConApp {
             if let Some(ref err) = self.last_error {
                 ui.colored_label(egui::Color32::RED, err);
             }
+            if self.dry_run {
+                ui.colored_label(egui::Color32::YELLOW, "Dry run: input is described under \"Dry run\", not sent");
+            }
+            if input_degraded() {
+                ui.colored_label(egui::Color32::YELLOW, "Degraded input: the target refused console input, so keys are typed with SendInput into its window (it is brought to the front)");
+            }
+            if let Some(silent) = self.worker_stalled() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, format!("Worker stalled: no heartbeat for {} s, so output is not being read", silent.as_secs()));
+                    if ui.button("Restart worker")
+                        .on_hover_text("Start a new worker for this target; the stuck one is abandoned")
+                        .clicked()
+                    {
+                        self.restart_worker();
+                    }
+                });
+            }
 
             ui.separator();
 
+            // Commands waiting for an attachment
+            if !self.command_queue.is_empty() {
+                let mut clear = false;
+                ui.horizontal(|ui| {
+                    ui.label(format!("Queued until attached ({}):", self.command_queue.len()));
+                    ui.label(egui::RichText::new(self.command_queue.join(" · ")).monospace());
+                    clear = ui.small_button("Clear").clicked();
+                });
+                if clear {
+                    self.command_queue.clear();
+                }
+            }
+
             // Input area
             ui.horizontal(|ui| {
                 ui.label("Command:");
                 let response = ui.add_sized(
-                    [ui.available_width() - 150.0, 20.0],
+                    [ui.available_width() - 200.0, 20.0],
                     egui::TextEdit::singleline(&mut self.command_input)
                         .hint_text("Type command here...")
                         .desired_width(f32::INFINITY)
                 );
+                #[cfg(feature = "automation")]
+                if response.changed() {
+                    self.keep_alive.note_typing();
+                }
 
                 // Send on Enter
                 if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
@@ -759,17 +3739,33 @@ impl RemoteConApp {
                 }
 
                 // Send button
-                ui.add_enabled_ui(self.attached_pid.is_some() && !self.command_input.trim().is_empty(), |ui| {
-                    if ui.button("Send").clicked() {
+                let label = if self.should_queue() { "Queue" } else { "Send" };
+                ui.add_enabled_ui(!self.command_input.trim().is_empty(), |ui| {
+                    if ui.button(label).clicked() {
                         self.send_command();
                     }
                 });
+                ui.add_enabled_ui(self.attached_pid.is_some() && !self.command_input.trim().is_empty(), |ui| {
+                    if ui.button("Type")
+                        .on_hover_text("Type the text on the target's command line without pressing Enter")
+                        .clicked()
+                    {
+                        self.type_text();
+                    }
+                });
 
                 // Ctrl+C button
                 ui.add_enabled_ui(self.attached_pid.is_some(), |ui| {
                     if ui.button("Ctrl+C").clicked() {
                         self.send_ctrl_c();
                     }
+                    if ui.toggle_value(&mut self.terminal_mode, "Terminal")
+                        .on_hover_text("Forward every key typed in this window to the target; click a text box to type there instead")
+                        .clicked()
+                        && self.terminal_mode
+                    {
+                        ui.memory_mut(|m| m.stop_text_input());
+                    }
                 });
             });
         });
@@ -777,9 +3773,53 @@ impl RemoteConApp {
 }
 
 impl eframe::App for RemoteConApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(notes) = self.notes.as_mut() {
+            let _ = notes.save();
+        }
+        if let Some(helper) = self.helper.take() {
+            helper.shutdown();
+        }
+        for id in self.session_hooks.drain(..) {
+            unsubscribe(id);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Put the window back where it was last time, and remember where it goes
+        self.poll_window_placement(ctx);
+
         // Update from worker messages
         self.update_from_worker();
+        self.poll_helper_launch();
+
+        // Flush a finished repro recording
+        self.finish_repro(false);
+        self.poll_saves();
+
+        // Look for a restarted target
+        self.poll_auto_reattach();
+
+        // Keep idle targets from timing out
+        #[cfg(feature = "automation")]
+        self.poll_keep_alive();
+
+        // Answer "Press any key" style prompts
+        #[cfg(feature = "automation")]
+        self.poll_responder();
+
+        // Flush commands queued while detached, then feed the next line of
+        // a multi-line paste
+        self.poll_command_queue();
+        self.poll_paste();
+        #[cfg(feature = "automation")]
+        self.poll_pending_replay();
+
+        // Follow the attached session with its notes
+        self.sync_session_notes();
+
+        // Type keys on the target before widgets see them
+        self.forward_terminal_keys(ctx);
 
         // Render UI
         self.render_process_list(ctx);
@@ -787,8 +3827,170 @@ impl eframe::App for RemoteConApp {
 
         // Show context menu if active
         self.show_context_menu_ui(ctx);
+        self.show_guard_dialog(ctx);
+
+        // Pick up input described by sends made this frame
+        self.poll_dry_run();
 
         // Request continuous repaint
         ctx.request_repaint();
     }
 }
+
+/// Cursor height in percent of the cell when the target's is unknown (conhost's small cursor)
+const DEFAULT_CURSOR_SIZE: u32 = 25;
+
+/// Default console attribute (light gray on black) for text without color info
+const DEFAULT_ATTR: u16 = 0x07;
+
+/// Key strokes for one egui event in terminal mode: `None` for events that
+/// are not keyboard input, empty for keyboard events with nothing to send
+/// (releases, and letters that arrive again as text)
+fn terminal_strokes(event: &egui::Event) -> Option<Vec<KeyStroke>> {
+    let typed = |text: &str| text.chars().map(|c| KeyStroke::Char(if c == '\n' { '\r' } else { c })).collect();
+    match event {
+        egui::Event::Text(text) | egui::Event::Paste(text) => Some(typed(text)),
+        egui::Event::Copy => Some(vec![KeyStroke::Ctrl('C')]),
+        egui::Event::Cut => Some(vec![KeyStroke::Ctrl('X')]),
+        egui::Event::Key { pressed: false, .. } => Some(Vec::new()),
+        egui::Event::Key { key, modifiers, .. } => {
+            let letter = key.name().chars().next().filter(|c| key.name().len() == 1 && c.is_ascii_alphabetic());
+            let stroke = match (*key, letter) {
+                (_, Some(letter)) if modifiers.ctrl => Some(KeyStroke::Ctrl(letter)),
+                (egui::Key::Enter, _) => Some(KeyStroke::Char('\r')),
+                (egui::Key::Backspace, _) => Some(KeyStroke::VirtualKey(0x08)),
+                _ => terminal_key(*key).map(|k| KeyStroke::VirtualKey(k.code())),
+            };
+            Some(stroke.into_iter().collect())
+        }
+        _ => None,
+    }
+}
+
+/// The palette key an egui key stands for
+fn terminal_key(key: egui::Key) -> Option<VirtualKey> {
+    use egui::Key;
+    let function = [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12];
+    if let Some(n) = function.iter().position(|f| *f == key) {
+        return Some(VirtualKey::F(n as u8 + 1));
+    }
+    Some(match key {
+        Key::Escape => VirtualKey::Escape,
+        Key::Tab => VirtualKey::Tab,
+        Key::ArrowUp => VirtualKey::Up,
+        Key::ArrowDown => VirtualKey::Down,
+        Key::ArrowLeft => VirtualKey::Left,
+        Key::ArrowRight => VirtualKey::Right,
+        Key::Home => VirtualKey::Home,
+        Key::End => VirtualKey::End,
+        Key::PageUp => VirtualKey::PageUp,
+        Key::PageDown => VirtualKey::PageDown,
+        Key::Insert => VirtualKey::Insert,
+        Key::Delete => VirtualKey::Delete,
+        _ => return None,
+    })
+}
+
+/// Format a duration as milliseconds with two decimals
+fn millis(d: Duration) -> String {
+    format!("{:.2} ms", d.as_secs_f64() * 1000.0)
+}
+
+fn to_color32(c: Rgb) -> egui::Color32 {
+    egui::Color32::from_rgb(c.0, c.1, c.2)
+}
+
+/// Lay out one console line with its color runs.
+///
+/// egui has no bold face, so bold cells are brightened instead, the same way
+/// `RichText::strong` emphasizes text.
+fn colored_line(line: &str, runs: &[ColorRun], colors: &ColorSettings, font: &egui::FontId) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let mut append = |range: std::ops::Range<usize>, attr: u16| {
+        let style = colors.resolve_attr(attr);
+        let mut fg = to_color32(style.fg);
+        if style.bold {
+            fg = fg.lerp_to_gamma(egui::Color32::WHITE, 0.3);
+        }
+        job.append(&line[range], 0.0, egui::TextFormat {
+            font_id: font.clone(),
+            color: fg,
+            background: to_color32(style.bg),
+            ..Default::default()
+        });
+    };
+
+    if runs.is_empty() {
+        append(0..line.len(), DEFAULT_ATTR);
+    } else {
+        for run in runs {
+            append(run.range.clone(), run.attr);
+        }
+    }
+    job
+}
+
+/// Draw the target's cursor at `col` of a rendered line, filling `size`
+/// percent of the cell from the bottom like conhost does
+fn draw_cursor(ui: &egui::Ui, line_rect: egui::Rect, col: usize, font: &egui::FontId, size: u32) {
+    let cell = ui.fonts(|f| f.glyph_width(font, 'M'));
+    let left = line_rect.left() + cell * col as f32;
+    let height = (line_rect.height() * size.clamp(1, 100) as f32 / 100.0).max(2.0);
+    let rect = egui::Rect::from_min_size(
+        egui::pos2(left, line_rect.bottom() - height),
+        egui::vec2(cell, height),
+    );
+    ui.painter().rect_filled(rect, 0.0, ui.visuals().strong_text_color());
+}
+
+/// Number of leading lines of `old` that scrolled out of view in `new`.
+///
+/// Finds the smallest shift where the rest of `old` (ignoring its last line,
+/// which may still be being written) lines up with the start of `new`.
+fn scrolled_off_count(old: &[String], new: &[String]) -> usize {
+    if old.len() < 2 || new.is_empty() {
+        return 0;
+    }
+    let stable = &old[..old.len() - 1];
+    for shift in 0..stable.len() {
+        let m = (stable.len() - shift).min(new.len());
+        if stable[shift..shift + m] == new[..m] {
+            return shift;
+        }
+    }
+    0
+}
+
+/// Summarize a worker message for a repro trace without leaking console text
+fn describe_worker_message(msg: &WorkerMessage) -> String {
+    match msg {
+        WorkerMessage::Output { lines, changed, .. } => format!("output lines={} changed={}", lines.len(), changed.len()),
+        WorkerMessage::Delta { len, lines, .. } => format!("delta len={} changed={}", len, lines.len()),
+        WorkerMessage::Error(e) => format!("error {}", e),
+        WorkerMessage::Crashed(message) => format!("crashed {}", message),
+        WorkerMessage::Event(WorkerEvent::TitleChanged { title }) => format!("title {}", repro::scrub(title)),
+        WorkerMessage::Event(event) => format!("event {}", repro::scrub(&event.describe())),
+        WorkerMessage::InputSent { label, error, .. } => format!("input_sent {} ok={}", label, error.is_none()),
+        WorkerMessage::Snapshot { kind, data } => format!("snapshot {:?} ok={}", kind, data.is_ok()),
+        WorkerMessage::Region { lines, .. } => format!("region ok={}", lines.is_ok()),
+        WorkerMessage::Heartbeat => "heartbeat".to_string(),
+        WorkerMessage::Disconnected => "disconnected".to_string(),
+        WorkerMessage::Final { lines, .. } => format!("final lines={}", lines.len()),
+        WorkerMessage::Switched { from, to } => format!("switched from={:?} to={}", from, to),
+        WorkerMessage::Health { alive, .. } => format!("health alive={}", alive),
+        WorkerMessage::Cursor { position } => format!("cursor {:?}", position),
+        WorkerMessage::ConsoleMembers { pids } => format!("console_members {:?}", pids),
+        WorkerMessage::Modes(m) => format!("modes input={:#x} output={:#x}", m.input, m.output),
+        WorkerMessage::CursorShape(c) => format!("cursor_shape visible={} size={}", c.visible, c.size),
+        WorkerMessage::Timings(t) => format!("timings total={:?}", t.total),
+        WorkerMessage::Stats(s) => format!("stats polls={} updates={}", s.polls, s.updates),
+        WorkerMessage::CodePages { input, output } => format!("code_pages input={} output={}", input, output),
+        WorkerMessage::AlternateScreen(on) => format!("alternate_screen {}", on),
+        WorkerMessage::Metrics(m) => format!("metrics buffer={:?} window={:?} font={:?}", m.buffer, m.window, m.font_size),
+        WorkerMessage::Selection(s) => format!("selection {:?}", s.as_ref().map(|s| (s.rows.clone(), s.cols.clone()))),
+        WorkerMessage::History { lines, gap } => format!("history lines={} gap={}", lines.len(), gap),
+        WorkerMessage::Captured { lines, .. } => format!("captured lines={}", lines.len()),
+        WorkerMessage::HistoryRange { from, lines, total } => format!("history_range from={} lines={} total={}", from, lines.len(), total),
+        WorkerMessage::OwnerChanged { original, candidates } => format!("owner_changed original={} candidates={:?}", original, candidates),
+    }
+}
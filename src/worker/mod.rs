@@ -1,19 +1,226 @@
-use crossbeam_channel::{Sender, Receiver, bounded, unbounded, select};
+use crossbeam_channel::{Sender, Receiver, TrySendError, bounded, never, unbounded, select};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use regex::Regex;
 use std::thread;
 use std::time::{Duration, Instant};
-use crate::console::{attach_to_console, detach_from_console, read_console_lines};
+use crate::console::{clear_console, send_alt_code, send_alt_key, send_control_char, send_ctrl_c, send_ctrl_key, send_ctrl_z, send_key, send_keys, send_mouse_click, send_text, set_quick_edit, snapshot_json, ChangeWatch, KeyStroke, VirtualKey, console_code_pages, console_metrics, console_modes, console_process_list, console_selection, console_title, console_window_id, cursor_shape, notify, AttachEvent, CtrlGuard, AttachRoute, ChangeTracker, ColorRun, ConsoleStream, LineEvent, LineTransform, StyledLine, CursorShape, ConsoleMetrics, ConsoleModes, ConsoleSelection, interpret_vt, ReadTimings, ReadWindow, redecode, take_read_timings, TextDecoding, CursorMarker, read_console_region, read_new_lines_since, SmallRect, wait_turn, ConsoleTurn, ConsoleBackend, Win32Console, InputSettings, with_input_settings};
+use crate::console::handles::refresh_conout;
+#[cfg(feature = "automation")]
+use crate::console::{send_commands, BatchOptions, CommandOutcome};
+#[cfg(feature = "gui")]
+use crate::console::render_console_bitmap;
+use crate::helper::HelperClient;
+use crate::process::ProcessWatch;
+
+#[cfg(feature = "async")]
+pub mod stream;
 
 /// Message sent from worker to UI
 #[derive(Debug, Clone)]
 pub enum WorkerMessage {
-    /// New console output lines
-    Output { lines: Vec<String>, timestamp: Instant },
+    /// New console output lines, with per-line color runs when available;
+    /// `changed` indexes the lines that differ from the previous output
+    Output { lines: Vec<String>, colors: Vec<Vec<ColorRun>>, changed: Vec<usize>, timestamp: Instant },
+    /// Output is now `len` lines long, and the indexed lines read differently;
+    /// local reads send these instead of full snapshots
+    Delta { len: usize, lines: Vec<(usize, StyledLine)>, timestamp: Instant },
     /// Error occurred
     Error(String),
-    /// Status update
-    Status(String),
+    /// Something happened to the session
+    Event(WorkerEvent),
+    /// The worker thread is running; sent every [`HEARTBEAT_INTERVAL`]
+    Heartbeat,
     /// Disconnected from console
     Disconnected,
+    /// One last read of a target being let go (on detach, or after it
+    /// exited while its console lived on), for the UI to keep showing
+    Final { lines: Vec<StyledLine>, timestamp: Instant },
+    /// The worker loop panicked with this message; it is started again with
+    /// the same settings and target unless it keeps crashing
+    Crashed(String),
+    /// Rows of a rectangle asked for with `ReadRegion`, or why they could not be read
+    Region { rect: SmallRect, lines: Result<Vec<String>, String> },
+    /// A snapshot asked for with `TakeSnapshot`, or why it could not be taken
+    Snapshot { kind: SnapshotKind, data: Result<Vec<u8>, String> },
+    /// An input operation asked for with a `Send…` message finished; `label`
+    /// names it for messages, and `command` is the text of a sent command
+    InputSent { label: String, command: Option<String>, error: Option<String> },
+    /// Attachment moved to a new PID; the old output is no longer refreshed
+    Switched { from: Option<u32>, to: u32 },
+    /// Cursor as (line index within the last output, column), or None when outside it
+    Cursor { position: Option<(usize, usize)> },
+    /// Liveness of the attached target, sent every poll even when output is silent
+    Health { alive: bool, last_seen: Option<Instant> },
+    /// Processes sharing the console we read changed (excludes ourselves)
+    ConsoleMembers { pids: Vec<u32> },
+    /// The console being read is no longer the one `original` owns; `candidates` share the old console
+    OwnerChanged { original: u32, candidates: Vec<u32> },
+    /// Lines that scrolled above the cursor since the last poll, each sent once;
+    /// `gap` means the previous position was lost and lines may be missing
+    History { lines: Vec<String>, gap: bool },
+    /// The whole read window from a read scheduled with `ReadOnceAfter`
+    Captured { lines: Vec<String>, timestamp: Instant },
+    /// Answer to `GetHistory`: the kept lines of the range starting at line
+    /// `from`, and how many lines the session has scrolled off in all
+    HistoryRange { from: usize, lines: Vec<String>, total: usize },
+    /// Text selected in the target's own window appeared, changed or went away
+    Selection(Option<ConsoleSelection>),
+    /// Input/output modes of the target console changed
+    Modes(ConsoleModes),
+    /// Font or buffer/window size of the target console changed
+    Metrics(ConsoleMetrics),
+    /// A full-screen app switched to (true) or away from (false) an alternate screen buffer
+    AlternateScreen(bool),
+    /// Input/output code pages of the target console changed
+    CodePages { input: u32, output: u32 },
+    /// Where the last local poll spent its time
+    Timings(PollTimings),
+    /// Activity over the last [`STATS_INTERVAL`]
+    Stats(WorkerStats),
+    /// The target's cursor was shown, hidden or resized
+    CursorShape(CursorShape),
+}
+
+/// Session events the UI, logger and automation can branch on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerEvent {
+    /// Attached to `pid`; `route` says how (direct, via another process, elevated)
+    Attached { pid: u32, route: String },
+    /// Detached on request
+    Detached,
+    /// Reattaching for a poll failed; retry `attempt` is scheduled before giving up
+    Reconnecting { attempt: u32 },
+    /// A poll attached again after failed retries
+    Reconnected { pid: u32 },
+    /// The target cleared its screen (cls); sent before the History of what followed
+    BufferCleared,
+    /// The target console's window title changed
+    TitleChanged { title: String },
+    /// Reads stopped on request
+    Paused,
+    /// Reads started again on request
+    Resumed,
+    /// Other console users kept taking the console, so attachments are no
+    /// longer kept between polls
+    StayAttachedOff,
+}
+
+impl WorkerEvent {
+    /// Status line for the event
+    pub fn describe(&self) -> String {
+        match self {
+            WorkerEvent::Attached { pid, route } => format!("Attached to PID {} ({})", pid, route),
+            WorkerEvent::Detached => "Detached".to_string(),
+            WorkerEvent::Reconnecting { attempt } => format!("Reconnecting (attempt {})...", attempt),
+            WorkerEvent::Reconnected { pid } => format!("Reconnected to PID {}", pid),
+            WorkerEvent::BufferCleared => "Screen cleared".to_string(),
+            WorkerEvent::TitleChanged { title } => format!("Title: {}", title),
+            WorkerEvent::Paused => "Updates paused".to_string(),
+            WorkerEvent::Resumed => "Updates resumed".to_string(),
+            WorkerEvent::StayAttachedOff => "Other console users keep taking the console; attaching for every poll again".to_string(),
+        }
+    }
+}
+
+/// Time taken by the steps of one local poll
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PollTimings {
+    /// AttachConsole for this poll
+    pub attach: Duration,
+    /// Time inside the read layer (buffer queries, reads, conversion)
+    pub read: ReadTimings,
+    /// Whole poll, including detaching and messaging
+    pub total: Duration,
+}
+
+/// How often [`WorkerStats`] are sent
+pub const STATS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the worker did over one stats period, for a diagnostics view
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WorkerStats {
+    /// Length of the period the counts cover
+    pub period: Duration,
+    pub polls: u32,
+    pub mean_poll: Duration,
+    pub slowest_poll: Duration,
+    /// Output messages sent to the UI
+    pub updates: u32,
+    /// Lines in those messages: changed lines for local reads, whole snapshots from the helper
+    pub lines: usize,
+    /// Bytes of line text in those messages
+    pub bytes: usize,
+    /// Failed reattaches, retries included, since the worker started
+    pub attach_failures: u32,
+}
+
+impl WorkerStats {
+    /// Rate of `count` over the period
+    pub fn per_second(&self, count: f64) -> f64 {
+        if self.period.is_zero() { 0.0 } else { count / self.period.as_secs_f64() }
+    }
+}
+
+/// Counts toward the next [`WorkerStats`]
+struct StatsCollector {
+    started: Instant,
+    polls: u32,
+    poll_time: Duration,
+    slowest_poll: Duration,
+    updates: u32,
+    lines: usize,
+    bytes: usize,
+    attach_failures: u32,
+}
+
+impl StatsCollector {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            polls: 0,
+            poll_time: Duration::ZERO,
+            slowest_poll: Duration::ZERO,
+            updates: 0,
+            lines: 0,
+            bytes: 0,
+            attach_failures: 0,
+        }
+    }
+
+    fn poll(&mut self, took: Duration) {
+        self.polls += 1;
+        self.poll_time += took;
+        self.slowest_poll = self.slowest_poll.max(took);
+    }
+
+    fn output<'a>(&mut self, lines: impl Iterator<Item = &'a str>) {
+        self.updates += 1;
+        for line in lines {
+            self.lines += 1;
+            self.bytes += line.len();
+        }
+    }
+
+    /// The period's stats once [`STATS_INTERVAL`] has passed, starting the next period
+    fn take_if_due(&mut self) -> Option<WorkerStats> {
+        let period = self.started.elapsed();
+        if period < STATS_INTERVAL {
+            return None;
+        }
+        let stats = WorkerStats {
+            period,
+            polls: self.polls,
+            mean_poll: self.poll_time.checked_div(self.polls).unwrap_or_default(),
+            slowest_poll: self.slowest_poll,
+            updates: self.updates,
+            lines: self.lines,
+            bytes: self.bytes,
+            attach_failures: self.attach_failures,
+        };
+        *self = Self { attach_failures: self.attach_failures, ..Self::new() };
+        Some(stats)
+    }
 }
 
 /// Message sent from UI to worker
@@ -21,21 +228,108 @@ pub enum WorkerMessage {
 pub enum UiMessage {
     /// Attach to a console PID
     Attach(u32),
+    /// Attach to an elevated console PID through the elevated helper
+    AttachElevated(u32, HelperClient),
+    /// Swap the attachment to another PID, keeping the current one if the new attach fails
+    SwitchTarget(u32),
     /// Detach from current console
     Detach,
-    /// Update polling interval
-    SetInterval(Duration),
-    /// Update number of lines to read
-    SetLines(usize),
+    /// Apply a whole new set of tunables at once; only what differs from
+    /// the current settings takes effect, all before the next poll
+    Reconfigure(WorkerConfig),
+    /// Ask for scrolled-off lines `from..to` of the worker's history ring
+    GetHistory { from: usize, to: usize },
+    /// Type a command and press Enter on the target
+    SendCommand(String),
+    /// Type several commands, each with Enter, in one go; each sent one is
+    /// reported with its own `InputSent`
+    #[cfg(feature = "automation")]
+    SendCommands { commands: Vec<String>, options: BatchOptions },
+    /// Interrupt the target with Ctrl+C
+    SendCtrlC,
+    /// Press a navigation or function key on the target
+    SendKey(VirtualKey),
+    /// Type key strokes on the target; `label` names them in `InputSent`
+    SendKeys { keys: Vec<KeyStroke>, label: String },
+    /// Type text on the target without pressing Enter
+    SendText(String),
+    /// Press Ctrl+<letter> on the target
+    SendCtrlKey(char),
+    /// End the target's input with Ctrl+Z, and Enter if asked
+    SendEof { press_enter: bool },
+    /// Press Alt+<key> on the target
+    SendAltKey(char),
+    /// Type a character as an Alt+numpad code on the target
+    SendAltCode(String),
+    /// Click `button` at a cell of the target's visible window
+    SendMouseClick { x: usize, y: usize, button: u32 },
+    /// Turn Quick-Edit on the target on or off
+    SetQuickEdit(bool),
+    /// Clear the target's screen, by blanking the buffer (`direct`) or with `cls`
+    ClearScreen { direct: bool },
+    /// Take a snapshot of the target's buffer, sent back as `Snapshot`
+    TakeSnapshot(SnapshotKind),
+    /// Read just the cells inside a rectangle of the buffer, sent back as `Region`
+    ReadRegion(SmallRect),
+    /// Send control characters such as 0x0A (Ctrl+J), in order
+    SendControlChars(Vec<u16>),
+    /// Stop reading the target, keeping the attachment and the input path
+    Pause,
+    /// Read the target again after `Pause`, starting with an immediate poll
+    Resume,
+    /// Read the target once after this delay, even while paused, and send
+    /// what was read as `Captured`
+    ReadOnceAfter(Duration),
     /// Stop the worker
     Stop,
 }
 
+/// What `TakeSnapshot` captures of the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    /// The whole screen buffer, with colors and cursor, as JSON
+    Json,
+    /// A PNG of the visible window, drawn with the target's font and colors
+    #[cfg(feature = "gui")]
+    Png,
+}
+
 /// Configuration for the console worker
 #[derive(Debug, Clone)]
 pub struct WorkerConfig {
     pub interval: Duration,
     pub lines: usize,
+    /// Rows of the buffer to read on local polls
+    pub window: ReadWindow,
+    /// Run the polling thread at below-normal priority, so polling huge
+    /// buffers never slows the GUI or the target
+    pub low_priority: bool,
+    /// Maximum time a poll may take before the following poll is skipped
+    pub poll_budget: Option<Duration>,
+    /// Poll faster while output changes and back off while it is quiet,
+    /// instead of every `interval`
+    pub adaptive: Option<AdaptiveInterval>,
+    /// Read when the console window reports drawn output, where it can,
+    /// rather than every `interval`
+    pub event_driven: bool,
+    /// Retries, with backoff, of a failed reattach before the session is
+    /// reported disconnected
+    pub reconnect_attempts: u32,
+    /// Interpret VT escape sequences left as text in the buffer
+    pub decode_vt: bool,
+    /// Keep trailing blank cells so columns stay aligned
+    pub keep_trailing: bool,
+    /// Re-read text written in a different code page than the console shows
+    pub text_decoding: TextDecoding,
+    /// Scrolled-off lines kept by the worker for `GetHistory`
+    pub history_lines: usize,
+    /// Stay attached between local polls instead of attaching for each one.
+    /// While attached, closing the target's window also closes this app.
+    pub persistent_attach: bool,
+    /// Forward only the lines this matches, with context
+    pub filter: Option<FilterSpec>,
+    /// Typing delay, Enter key and dry run for the session's input
+    pub input: InputSettings,
 }
 
 impl Default for WorkerConfig {
@@ -43,32 +337,399 @@ impl Default for WorkerConfig {
         Self {
             interval: Duration::from_millis(500),
             lines: 100,
+            window: ReadWindow::ToCursor(100),
+            low_priority: false,
+            poll_budget: None,
+            adaptive: None,
+            event_driven: true,
+            reconnect_attempts: 3,
+            decode_vt: true,
+            keep_trailing: false,
+            text_decoding: TextDecoding::AsShown,
+            history_lines: DEFAULT_HISTORY_LINES,
+            persistent_attach: false,
+            filter: None,
+            input: InputSettings::default(),
+        }
+    }
+}
+
+/// Regular expression for the lines the worker forwards, and how many
+/// lines around each match go with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterSpec {
+    pub pattern: String,
+    pub context: usize,
+}
+
+/// A compiled [`FilterSpec`]. The screen is filtered as a whole on every
+/// change; scrolled-off lines are filtered as they arrive, with context
+/// carried from one batch to the next. The history ring keeps every line.
+struct LineFilter {
+    pattern: Regex,
+    context: usize,
+    /// Last unmatched lines of the stream, for context before the next match
+    recent: VecDeque<String>,
+    /// Stream lines still to forward after the last match
+    after: usize,
+}
+
+impl LineFilter {
+    fn new(spec: &FilterSpec) -> anyhow::Result<Self> {
+        let pattern = Regex::new(&spec.pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid filter: {}", e))?;
+        Ok(Self { pattern, context: spec.context, recent: VecDeque::new(), after: 0 })
+    }
+
+    /// Indices of the screen lines to show: matches and their context
+    fn select(&self, lines: &[&str]) -> Vec<usize> {
+        let mut keep: Vec<usize> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            if self.pattern.is_match(line) {
+                let from = i.saturating_sub(self.context).max(keep.last().map_or(0, |k| k + 1));
+                let to = (i + self.context).min(lines.len() - 1);
+                keep.extend(from..=to);
+            }
+        }
+        keep
+    }
+
+    /// Scrolled-off lines to forward from the next batch
+    fn stream(&mut self, lines: Vec<String>) -> Vec<String> {
+        let mut out = Vec::new();
+        for line in lines {
+            if self.pattern.is_match(&line) {
+                out.extend(self.recent.drain(..));
+                out.push(line);
+                self.after = self.context;
+            } else if self.after > 0 {
+                self.after -= 1;
+                out.push(line);
+            } else {
+                self.recent.push_back(line);
+                if self.recent.len() > self.context {
+                    self.recent.pop_front();
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Bounds of a poll interval that follows the output: polls run every `min`
+/// while lines change, and the gap doubles on each quiet poll up to `max`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveInterval {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Default for AdaptiveInterval {
+    fn default() -> Self {
+        Self {
+            min: Duration::from_millis(100),
+            max: Duration::from_secs(2),
+        }
+    }
+}
+
+impl AdaptiveInterval {
+    /// Gap before the poll after one that took `current`
+    fn next(&self, current: Duration, changed: bool) -> Duration {
+        if changed {
+            self.min
+        } else {
+            (current * 2).clamp(self.min, self.max.max(self.min))
+        }
+    }
+}
+
+/// Longest wait for a change event before polling anyway, for changes the
+/// console raises no event for (title, modes) and events that were missed
+const IDLE_POLL: Duration = Duration::from_secs(2);
+
+/// Time allowed for the rest of a burst of drawing before reading, which
+/// also caps event-driven reads at about 20 per second
+const CHANGE_SETTLE: Duration = Duration::from_millis(50);
+
+/// Scrolled-off lines the worker keeps unless configured otherwise
+pub const DEFAULT_HISTORY_LINES: usize = 50_000;
+
+/// The most recent lines that scrolled off the target, numbered from the
+/// first line of the session so ranges stay valid as old lines drop out
+struct HistoryRing {
+    lines: VecDeque<String>,
+    capacity: usize,
+    /// Lines dropped from the front, i.e. the number of the oldest kept line
+    dropped: usize,
+}
+
+impl HistoryRing {
+    fn new(capacity: usize) -> Self {
+        Self { lines: VecDeque::new(), capacity, dropped: 0 }
+    }
+
+    /// Lines scrolled off in all, kept or not
+    fn total(&self) -> usize {
+        self.dropped + self.lines.len()
+    }
+
+    fn push(&mut self, lines: &[String]) {
+        self.lines.extend(lines.iter().cloned());
+        self.trim();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let excess = self.lines.len().saturating_sub(self.capacity);
+        self.lines.drain(..excess);
+        self.dropped += excess;
+    }
+
+    /// Kept lines of `from..to`, with the number of the first one returned
+    fn range(&self, from: usize, to: usize) -> (usize, Vec<String>) {
+        let start = from.max(self.dropped);
+        let end = to.min(self.total());
+        let lines = if start < end {
+            self.lines.range(start - self.dropped..end - self.dropped).cloned().collect()
+        } else {
+            Vec::new()
+        };
+        (start, lines)
+    }
+
+    fn clear(&mut self) {
+        self.lines.clear();
+        self.dropped = 0;
+    }
+}
+
+/// How often the worker reports that its thread is still running, whether
+/// or not anything is polled
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wait before the first reattach retry; it doubles with each further one
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Times other console users may take the console from a kept attachment
+/// before the worker goes back to attaching for every poll
+const HELD_CONFLICT_LIMIT: u32 = 3;
+
+/// A local attachment kept from one console turn to the next, which saves
+/// AttachConsole and reopening `CONIN$` on every poll. Every other console
+/// user attaches to its own console first, so the attachment is still ours
+/// only if no one else had a turn in between.
+#[derive(Default)]
+struct HeldAttachment {
+    enabled: bool,
+    /// Ticket of the turn that ended with us still attached
+    held: Option<u64>,
+    /// Turns others took while we were attached, since enabled
+    conflicts: u32,
+    /// Conflicts just reached the limit; not reported yet
+    fell_back: bool,
+}
+
+impl HeldAttachment {
+    fn new(enabled: bool) -> Self {
+        Self { enabled, ..Self::default() }
+    }
+
+    fn set_enabled(&mut self, enabled: bool, backend: &mut impl ConsoleBackend) {
+        self.enabled = enabled;
+        self.conflicts = 0;
+        if !enabled {
+            self.release(backend);
+        }
+    }
+
+    /// Whether the attachment kept at the end of our last turn is still in
+    /// place for `turn`; if so, only `CONOUT$` is reopened
+    fn reuse(&mut self, turn: &ConsoleTurn) -> bool {
+        let Some(held) = self.held.take() else {
+            return false;
+        };
+        if turn.ticket() == held + 1 {
+            refresh_conout();
+            return true;
+        }
+        self.conflicts += 1;
+        self.fell_back = self.conflicts == HELD_CONFLICT_LIMIT;
+        false
+    }
+
+    /// Whether to stay attached at the end of `turn` rather than detach
+    fn keep(&mut self, turn: &ConsoleTurn) -> bool {
+        let keep = self.enabled && self.conflicts < HELD_CONFLICT_LIMIT;
+        self.held = keep.then(|| turn.ticket());
+        keep
+    }
+
+    /// Once, after conflicts have turned keeping attachments off
+    fn take_fell_back(&mut self) -> bool {
+        std::mem::take(&mut self.fell_back)
+    }
+
+    /// Stop counting on the kept attachment; another of our own operations
+    /// changed it
+    fn forget(&mut self) {
+        self.held = None;
+    }
+
+    /// Detach from a kept attachment, e.g. once the target has gone
+    fn release(&mut self, backend: &mut impl ConsoleBackend) {
+        if self.held.take().is_some() {
+            let _turn = wait_turn();
+            backend.detach();
+        }
+    }
+}
+
+/// Kinds of poll error that are reported at most once per [`ERROR_COOLDOWN`]
+const READ_ERROR: &str = "Read error";
+const HELPER_READ_ERROR: &str = "Helper read error";
+
+/// Quiet time after reporting an error before the same kind is reported again
+const ERROR_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Repeats of one error kind since it was last reported
+struct ErrorCount {
+    reported: Instant,
+    count: u32,
+}
+
+/// Collapses errors that recur on every poll into one message per kind and
+/// cooldown, counting the repeats in between
+#[derive(Default)]
+struct ErrorThrottle {
+    kinds: HashMap<&'static str, ErrorCount>,
+}
+
+impl ErrorThrottle {
+    /// Message to send for an error of `kind`, or None while it is cooling
+    /// down; after a cooldown the message counts the repeats it covers
+    fn report(&mut self, kind: &'static str, detail: &str) -> Option<String> {
+        let now = Instant::now();
+        let Some(seen) = self.kinds.get_mut(kind) else {
+            self.kinds.insert(kind, ErrorCount { reported: now, count: 0 });
+            return Some(format!("{}: {}", kind, detail));
+        };
+        seen.count += 1;
+        let since = now.duration_since(seen.reported);
+        if since < ERROR_COOLDOWN {
+            return None;
+        }
+        let message = format!("{} ×{} in last {} s: {}", kind, seen.count, since.as_secs(), detail);
+        *seen = ErrorCount { reported: now, count: 0 };
+        Some(message)
+    }
+
+    /// The operation behind `kind` worked again; report its next failure at once
+    fn clear(&mut self, kind: &'static str) {
+        self.kinds.remove(kind);
+    }
+}
+
+/// Messages the channel to the UI holds before the worker stops adding to
+/// it, so a UI that hitches cannot make the queue grow without limit. While
+/// it is full, output is merged into one waiting update (see
+/// [`OutputBacklog`]), periodic reports (heartbeats, health, timings, stats)
+/// are dropped because the next one supersedes them, and other messages
+/// wait for room.
+pub const WORKER_QUEUE: usize = 256;
+
+/// Output waiting for room in a full channel to the UI. Each new update is
+/// merged into it, so the UI gets one message with the same effect as all
+/// of them once it catches up; it may arrive after status messages sent
+/// in the meantime.
+#[derive(Default)]
+struct OutputBacklog {
+    pending: Option<WorkerMessage>,
+}
+
+impl OutputBacklog {
+    /// Send an `Output` or `Delta`, or hold it with what is already waiting
+    fn send(&mut self, tx: &Sender<WorkerMessage>, msg: WorkerMessage) {
+        let msg = match self.pending.take() {
+            Some(older) => coalesce(tx, older, msg),
+            None => msg,
+        };
+        if let Err(TrySendError::Full(msg)) = tx.try_send(msg) {
+            self.pending = Some(msg);
+        }
+    }
+
+    /// Retry the waiting update; called once per loop
+    fn flush(&mut self, tx: &Sender<WorkerMessage>) {
+        if let Some(msg) = self.pending.take()
+            && let Err(TrySendError::Full(msg)) = tx.try_send(msg)
+        {
+            self.pending = Some(msg);
+        }
+    }
+}
+
+/// One output message with the effect of `older` followed by `newer`. The
+/// two kinds only meet when a session changes route; then `older` is sent
+/// as it is, waiting for room, and `newer` is returned.
+fn coalesce(tx: &Sender<WorkerMessage>, older: WorkerMessage, newer: WorkerMessage) -> WorkerMessage {
+    match (older, newer) {
+        (WorkerMessage::Delta { lines: old, .. }, WorkerMessage::Delta { len, lines, timestamp }) => {
+            let mut merged: BTreeMap<usize, StyledLine> = old.into_iter().filter(|(i, _)| *i < len).collect();
+            merged.extend(lines);
+            WorkerMessage::Delta { len, lines: merged.into_iter().collect(), timestamp }
+        }
+        (WorkerMessage::Output { changed: old, .. }, WorkerMessage::Output { lines, colors, mut changed, timestamp }) => {
+            changed.extend(old.into_iter().filter(|i| *i < lines.len()));
+            changed.sort_unstable();
+            changed.dedup();
+            WorkerMessage::Output { lines, colors, changed, timestamp }
+        }
+        (older, newer) => {
+            let _ = tx.send(older);
+            newer
         }
     }
 }
 
+/// Name of the worker thread, as debuggers and profilers show it
+pub const WORKER_THREAD_NAME: &str = "console-poller";
+
 /// Worker that polls console output in the background
 pub struct ConsoleWorker {
     ui_tx: Sender<UiMessage>,
     worker_rx: Receiver<WorkerMessage>,
-    _handle: thread::JoinHandle<()>,
+    handle: thread::JoinHandle<()>,
 }
 
 impl ConsoleWorker {
-    /// Create a new console worker
-    pub fn new(config: WorkerConfig) -> Self {
+    /// Create a new console worker on its own named thread
+    pub fn new(config: WorkerConfig) -> anyhow::Result<Self> {
+        Self::with_backend(config, Win32Console)
+    }
+
+    /// Create a worker that makes its console calls through `backend`,
+    /// e.g. a recorded trace being replayed
+    pub fn with_backend(config: WorkerConfig, backend: impl ConsoleBackend + Send + 'static) -> anyhow::Result<Self> {
         let (ui_tx, ui_rx) = unbounded::<UiMessage>();
-        let (worker_tx, worker_rx) = unbounded::<WorkerMessage>();
+        let (worker_tx, worker_rx) = bounded::<WorkerMessage>(WORKER_QUEUE);
 
-        let handle = thread::spawn(move || {
-            worker_main(config, ui_rx, worker_tx);
-        });
+        let handle = thread::Builder::new()
+            .name(WORKER_THREAD_NAME.to_string())
+            .spawn(move || {
+                supervise(backend, config, ui_rx, worker_tx);
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to start worker thread: {}", e))?;
 
-        Self {
+        Ok(Self {
             ui_tx,
             worker_rx,
-            _handle: handle,
-        }
+            handle,
+        })
     }
 
     /// Send a message to the worker
@@ -88,97 +749,1280 @@ impl ConsoleWorker {
     }
 }
 
-/// Main worker loop
-fn worker_main(
+/// Console workers for several sessions at once, keyed by the PID each one
+/// is attached to. Every session has its own thread, channels and config;
+/// they take turns at the console through the same queue.
+#[derive(Default)]
+pub struct WorkerPool {
+    sessions: HashMap<u32, ConsoleWorker>,
+    /// Sessions told to stop, kept until their thread ends so their
+    /// `Final` snapshot still arrives
+    closing: Vec<(u32, ConsoleWorker)>,
+}
+
+impl WorkerPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a session attached to `pid`, directly or through the elevated
+    /// `helper`, replacing any session `pid` already had
+    pub fn open(&mut self, pid: u32, config: WorkerConfig, helper: Option<HelperClient>) -> anyhow::Result<()> {
+        let worker = ConsoleWorker::new(config)?;
+        worker.send(match helper {
+            Some(helper) => UiMessage::AttachElevated(pid, helper),
+            None => UiMessage::Attach(pid),
+        })?;
+        if let Some(old) = self.sessions.insert(pid, worker) {
+            let _ = old.send(UiMessage::Stop);
+        }
+        Ok(())
+    }
+
+    /// Detach and stop the session for `pid`
+    pub fn close(&mut self, pid: u32) {
+        if let Some(worker) = self.sessions.remove(&pid) {
+            let _ = worker.send(UiMessage::Detach);
+            let _ = worker.send(UiMessage::Stop);
+            self.closing.push((pid, worker));
+        }
+    }
+
+    /// Session attached to `pid`
+    pub fn get(&self, pid: u32) -> Option<&ConsoleWorker> {
+        self.sessions.get(&pid)
+    }
+
+    /// Next message from any session with the PID it was sent for. A
+    /// session that switched targets is filed under its new PID, and one
+    /// that disconnected is stopped. Of sessions that are stopping, only
+    /// the `Final` snapshot is passed on.
+    pub fn try_recv(&mut self) -> Option<(u32, WorkerMessage)> {
+        let Some((pid, msg)) = self.sessions.iter()
+            .find_map(|(&pid, worker)| worker.try_recv().map(|msg| (pid, msg)))
+        else {
+            return self.try_recv_closing();
+        };
+        match msg {
+            WorkerMessage::Switched { to, .. } => {
+                if let Some(worker) = self.sessions.remove(&pid)
+                    && let Some(old) = self.sessions.insert(to, worker)
+                {
+                    let _ = old.send(UiMessage::Stop);
+                }
+            }
+            WorkerMessage::Disconnected => {
+                if let Some(worker) = self.sessions.remove(&pid) {
+                    let _ = worker.send(UiMessage::Stop);
+                    self.closing.push((pid, worker));
+                }
+            }
+            _ => {}
+        }
+        Some((pid, msg))
+    }
+
+    /// Next `Final` snapshot of a stopping session, dropping sessions whose
+    /// thread has ended and been heard out
+    fn try_recv_closing(&mut self) -> Option<(u32, WorkerMessage)> {
+        self.closing.retain(|(_, worker)| !worker.handle.is_finished() || !worker.worker_rx.is_empty());
+        for (pid, worker) in &self.closing {
+            while let Some(msg) = worker.try_recv() {
+                if let WorkerMessage::Final { .. } = msg {
+                    return Some((*pid, msg));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        for worker in self.sessions.values() {
+            let _ = worker.send(UiMessage::Stop);
+        }
+    }
+}
+
+/// Times the worker loop is started again after panicking before the
+/// session is given up
+const MAX_RESPAWNS: u32 = 3;
+
+/// What a restarted worker loop needs to carry on where the last one
+/// panicked: the settings as changed since the start, and the target
+struct Restart {
     config: WorkerConfig,
-    ui_rx: Receiver<UiMessage>,
-    worker_tx: Sender<WorkerMessage>,
+    /// Attached PID, with the elevated helper when attached through it
+    target: Option<(u32, Option<HelperClient>)>,
+}
+
+/// Run the worker loop, catching panics (a misused Win32 call, say) so the
+/// UI hears about them, and starting it again attached to the same target
+fn supervise(mut backend: impl ConsoleBackend, config: WorkerConfig, ui_rx: Receiver<UiMessage>, worker_tx: Sender<WorkerMessage>) {
+    let mut restart = Restart { config, target: None };
+    for _ in 0..=MAX_RESPAWNS {
+        let run = panic::catch_unwind(AssertUnwindSafe(|| worker_main(&mut backend, &mut restart, &ui_rx, &worker_tx)));
+        let Err(payload) = run else {
+            return;
+        };
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        if worker_tx.send(WorkerMessage::Crashed(message)).is_err() {
+            return;
+        }
+    }
+    if let Some((pid, _)) = restart.target {
+        notify(AttachEvent::Detached { pid });
+    }
+    let _ = worker_tx.send(WorkerMessage::Disconnected);
+}
+
+/// Main worker loop, attaching and sending commands through `backend`
+fn worker_main(
+    backend: &mut impl ConsoleBackend,
+    restart: &mut Restart,
+    ui_rx: &Receiver<UiMessage>,
+    worker_tx: &Sender<WorkerMessage>,
 ) {
+    let config = restart.config.clone();
     let mut current_pid: Option<u32> = None;
+    let mut route = AttachRoute::Direct;
+    let mut helper: Option<HelperClient> = None;
     let mut interval = config.interval;
     let mut lines = config.lines;
-    let mut last_output: Option<String> = None;
+    let mut read_window = config.window;
+    let mut last_output = ChangeTracker::new();
+    let mut watch: Option<ProcessWatch> = None;
+    let mut last_seen: Option<Instant> = None;
+    let mut poll_budget = config.poll_budget;
+    let mut skip_next_poll = false;
+    let mut event_driven = config.event_driven;
+    let mut adaptive = config.adaptive;
+    // Gap before the next poll; follows the output when adaptive
+    let mut sleep_for = adaptive.map_or(interval, |a| a.min);
+    // A UI message that ended the wait between polls; after a crash, the
+    // attach to the target the crashed loop had
+    let mut pending_ui: Option<UiMessage> = restart.target.clone().map(|(pid, helper)| match helper {
+        Some(helper) => UiMessage::AttachElevated(pid, helper),
+        None => UiMessage::Attach(pid),
+    });
+    let mut next_poll = Instant::now();
+    // Reads are held off while the UI has paused updates
+    let mut paused = false;
+    // When a read asked for with ReadOnceAfter is due
+    let mut read_once: Option<Instant> = None;
+    let mut errors = ErrorThrottle::default();
+    let mut reconnect_attempts = config.reconnect_attempts;
+    // Reattach retries since the last poll that attached
+    let mut failed_attaches = 0u32;
+    let mut last_heartbeat: Option<Instant> = None;
+    let mut stats = StatsCollector::new();
+    let mut history = HistoryRing::new(config.history_lines);
+    let mut backlog = OutputBacklog::default();
+    let mut filter = config.filter.as_ref().and_then(|spec| LineFilter::new(spec).ok());
+    let mut held = HeldAttachment::new(config.persistent_attach);
+    let mut input = config.input;
+    let mut decode_vt = config.decode_vt;
+    let mut text_decoding = config.text_decoding;
+    let mut stream = ConsoleStream::new();
+    stream.set_keep_trailing(config.keep_trailing);
+    stream.set_transform(line_transform(decode_vt, text_decoding));
+    let mut console: ConsoleIdentity = ConsoleIdentity::default();
+    let mut tail = CursorMarker::default();
+    let mut last_cursor: Option<(usize, usize)> = None;
+    // Held while there is a session, so a Ctrl+C raised in the target's
+    // console cannot kill us; dropped with the session, or on a crash
+    let mut ctrl_guard: Option<CtrlGuard> = None;
+
+    set_thread_low_priority(config.low_priority);
 
     loop {
         // Check for UI messages
-        match ui_rx.try_recv() {
+        let next = match pending_ui.take() {
+            Some(msg) => Ok(msg),
+            None => ui_rx.try_recv(),
+        };
+        // A new target is read right away; it starts a new history once attached
+        if matches!(next, Ok(UiMessage::Attach(_) | UiMessage::AttachElevated(..) | UiMessage::SwitchTarget(_))) {
+            next_poll = Instant::now();
+            failed_attaches = 0;
+            ctrl_guard.get_or_insert_with(CtrlGuard::acquire);
+        }
+        match next {
             Ok(UiMessage::Attach(pid)) => {
+                let turn = wait_turn();
                 // Detach from previous if any
-                if current_pid.is_some() {
-                    let _ = detach_from_console();
-                    current_pid = None;
+                let old = current_pid.take();
+                if old.is_some() {
+                    backend.detach();
+                }
+                if let Some(h) = helper.take() {
+                    let _ = h.detach();
                 }
 
                 // Try to attach to new PID
-                match attach_to_console(pid) {
-                    Ok(()) => {
+                let attached = backend.attach_route(pid);
+                // Hooks may use the console themselves, so they run after our turn
+                drop(turn);
+                if let Some(old) = old {
+                    notify(AttachEvent::Detached { pid: old });
+                }
+                match attached {
+                    Ok(r) => {
                         current_pid = Some(pid);
-                        last_output = None;
-                        let _ = worker_tx.send(WorkerMessage::Status(format!("Attached to PID {}", pid)));
+                        history.clear();
+                        held.forget();
+                        last_output.reset();
+                        console = ConsoleIdentity::default();
+                        stream.reset();
+                        tail = CursorMarker::default();
+                        watch = ProcessWatch::open(pid).ok();
+                        last_seen = Some(Instant::now());
+                        let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Attached { pid, route: r.describe() }));
+                        notify(AttachEvent::Attached { pid });
+                        route = r;
                     }
                     Err(e) => {
+                        notify(AttachEvent::Error { pid: Some(pid), message: e.to_string() });
                         let _ = worker_tx.send(WorkerMessage::Error(format!("Failed to attach: {}", e)));
                     }
                 }
             }
+            Ok(UiMessage::SwitchTarget(pid)) => {
+                // Probe the new target before letting go of the old one
+                if helper.is_some() {
+                    let _ = worker_tx.send(WorkerMessage::Error("Cannot switch targets while attached through the elevated helper".to_string()));
+                } else {
+                    let turn = wait_turn();
+                    let attached = backend.attach_route(pid);
+                    if attached.is_ok() {
+                        backend.detach();
+                    }
+                    drop(turn);
+                    match attached {
+                        Ok(r) => {
+                            route = r;
+                            let from = current_pid.replace(pid);
+                            history.clear();
+                            held.forget();
+                            last_output.reset();
+                            console = ConsoleIdentity::default();
+                            stream.reset();
+                            tail = CursorMarker::default();
+                            watch = ProcessWatch::open(pid).ok();
+                            last_seen = Some(Instant::now());
+                            if let Some(from) = from {
+                                notify(AttachEvent::Detached { pid: from });
+                            }
+                            notify(AttachEvent::Attached { pid });
+                            let _ = worker_tx.send(WorkerMessage::Switched { from, to: pid });
+                        }
+                        Err(e) => {
+                            // The probe let go of any kept attachment; the old target keeps its history
+                            held.forget();
+                            notify(AttachEvent::Error { pid: Some(pid), message: e.to_string() });
+                            let _ = worker_tx.send(WorkerMessage::Error(format!("Failed to switch to PID {}: {}", pid, e)));
+                        }
+                    }
+                }
+            }
+            Ok(UiMessage::AttachElevated(pid, h)) => {
+                if let Some(old) = current_pid.take() {
+                    let turn = wait_turn();
+                    backend.detach();
+                    drop(turn);
+                    notify(AttachEvent::Detached { pid: old });
+                }
+
+                // The helper stays attached on its side between polls
+                match h.attach(pid) {
+                    Ok(()) => {
+                        if let Err(e) = push_input_settings(&h, input) {
+                            let _ = worker_tx.send(WorkerMessage::Error(format!("Failed to set helper input: {}", e)));
+                        }
+                        current_pid = Some(pid);
+                        helper = Some(h);
+                        history.clear();
+                        held.forget();
+                        last_output.reset();
+                        console = ConsoleIdentity::default();
+                        stream.reset();
+                        tail = CursorMarker::default();
+                        watch = ProcessWatch::open(pid).ok();
+                        last_seen = Some(Instant::now());
+                        let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Attached { pid, route: "elevated".to_string() }));
+                        notify(AttachEvent::Attached { pid });
+                    }
+                    Err(e) => {
+                        notify(AttachEvent::Error { pid: Some(pid), message: e.to_string() });
+                        let _ = worker_tx.send(WorkerMessage::Error(format!("Failed to attach via helper: {}", e)));
+                    }
+                }
+            }
             Ok(UiMessage::Detach) => {
-                if current_pid.is_some() {
-                    let _ = detach_from_console();
-                    current_pid = None;
-                    last_output = None;
-                    let _ = worker_tx.send(WorkerMessage::Status("Detached".to_string()));
+                if let Some(pid) = current_pid {
+                    let window = if console.alternate { ReadWindow::Viewport } else { read_window };
+                    if let Some(screen) = final_read(backend, route.attach_pid(pid), &mut held, helper.as_ref(), lines, &mut stream, window) {
+                        let screen = filter_screen(filter.as_ref(), screen);
+                        let _ = worker_tx.send(WorkerMessage::Final { lines: screen, timestamp: Instant::now() });
+                    }
+                }
+                if let Some(h) = helper.take() {
+                    let _ = h.detach();
+                }
+                if let Some(old) = current_pid.take() {
+                    held.forget();
+                    let _turn = wait_turn();
+                    backend.detach();
+                    last_output.reset();
+                    console = ConsoleIdentity::default();
+                    stream.reset();
+                    tail = CursorMarker::default();
+                    watch = None;
+                    last_seen = None;
+                    notify(AttachEvent::Detached { pid: old });
+                    let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Detached));
+                }
+            }
+            Ok(UiMessage::Reconfigure(new)) => {
+                let old = std::mem::replace(&mut restart.config, new.clone());
+                if (new.interval, new.adaptive) != (old.interval, old.adaptive) {
+                    interval = new.interval;
+                    adaptive = new.adaptive;
+                    sleep_for = adaptive.map_or(interval, |a| a.min);
+                    next_poll = next_poll.min(Instant::now() + sleep_for);
+                }
+                if (new.lines, new.window) != (old.lines, old.window) {
+                    lines = new.lines;
+                    read_window = new.window;
+                    last_output.reset();
+                }
+                if new.low_priority != old.low_priority {
+                    set_thread_low_priority(new.low_priority);
+                }
+                if new.poll_budget != old.poll_budget {
+                    poll_budget = new.poll_budget;
+                    skip_next_poll = false;
+                }
+                reconnect_attempts = new.reconnect_attempts;
+                event_driven = new.event_driven;
+                if (new.decode_vt, new.text_decoding) != (old.decode_vt, old.text_decoding) {
+                    decode_vt = new.decode_vt;
+                    text_decoding = new.text_decoding;
+                    stream.set_transform(line_transform(decode_vt, text_decoding));
+                }
+                if new.keep_trailing != old.keep_trailing {
+                    stream.set_keep_trailing(new.keep_trailing);
+                }
+                if new.history_lines != old.history_lines {
+                    history.set_capacity(new.history_lines);
+                }
+                if new.persistent_attach != old.persistent_attach {
+                    held.set_enabled(new.persistent_attach, backend);
+                }
+                if new.input != old.input {
+                    input = new.input;
+                    if let Some(h) = &helper
+                        && let Err(e) = push_input_settings(h, input)
+                    {
+                        let _ = worker_tx.send(WorkerMessage::Error(format!("Failed to set helper input: {}", e)));
+                    }
+                }
+                if new.filter != old.filter {
+                    filter = match new.filter.as_ref().map(LineFilter::new).transpose() {
+                        Ok(filter) => filter,
+                        Err(e) => {
+                            let _ = worker_tx.send(WorkerMessage::Error(e.to_string()));
+                            None
+                        }
+                    };
+                    // Send the screen again, filtered or whole
+                    last_output.reset();
+                    stream.reset();
+                    next_poll = Instant::now();
+                }
+            }
+            Ok(UiMessage::GetHistory { from, to }) => {
+                let (from, lines) = history.range(from, to);
+                let _ = worker_tx.send(WorkerMessage::HistoryRange { from, lines, total: history.total() });
+            }
+            Ok(UiMessage::SendCommand(text)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_command(&text), |b| b.send_command(&text));
+                let _ = worker_tx.send(input_sent("command", Some(text), result));
+            }
+            #[cfg(feature = "automation")]
+            Ok(UiMessage::SendCommands { commands, options }) => {
+                let outcomes = run_input(
+                    backend,
+                    current_pid,
+                    &route,
+                    &mut held,
+                    helper.as_ref(),
+                    input,
+                    |h| {
+                        let mut failed = false;
+                        Ok(commands.iter().map(|c| {
+                            let result = (!failed || !options.stop_on_error).then(|| h.send_command(c));
+                            failed |= result.as_ref().is_some_and(Result::is_err);
+                            CommandOutcome { command: c.clone(), result }
+                        }).collect::<Vec<_>>())
+                    },
+                    |_| Ok(send_commands(&commands, options)),
+                );
+                match outcomes {
+                    Ok(outcomes) => {
+                        // Skipped commands are not reported
+                        for outcome in outcomes {
+                            if let Some(result) = outcome.result {
+                                let _ = worker_tx.send(input_sent("command", Some(outcome.command), result));
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ = worker_tx.send(input_sent("commands", None, Err(e)));
+                    }
                 }
             }
-            Ok(UiMessage::SetInterval(d)) => {
-                interval = d;
+            Ok(UiMessage::SendCtrlC) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_ctrl_c(), |_| send_ctrl_c());
+                let _ = worker_tx.send(input_sent("Ctrl+C", None, result));
+            }
+            Ok(UiMessage::SendKey(key)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_key(key), |_| send_key(key));
+                let _ = worker_tx.send(input_sent(key.label(), None, result));
+            }
+            Ok(UiMessage::SendKeys { keys, label }) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_keys(&keys), |_| send_keys(&keys));
+                let _ = worker_tx.send(input_sent(label, None, result));
+            }
+            Ok(UiMessage::SendControlChars(codes)) => {
+                let result = run_input(
+                    backend,
+                    current_pid,
+                    &route,
+                    &mut held,
+                    helper.as_ref(),
+                    input,
+                    |h| codes.iter().try_for_each(|&c| h.send_control_char(c)),
+                    |_| codes.iter().try_for_each(|&c| send_control_char(c)),
+                );
+                let label = codes.iter()
+                    .map(|&c| format!("Ctrl+{}", char::from(0x40 + (c & 0x1F) as u8)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let _ = worker_tx.send(input_sent(label, None, result));
+            }
+            Ok(UiMessage::SendText(text)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_text(&text), |_| send_text(&text));
+                let _ = worker_tx.send(input_sent("text", None, result));
+            }
+            Ok(UiMessage::SendCtrlKey(letter)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_ctrl_key(letter), |_| send_ctrl_key(letter));
+                let _ = worker_tx.send(input_sent(format!("Ctrl+{}", letter), None, result));
+            }
+            Ok(UiMessage::SendEof { press_enter }) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_ctrl_z(press_enter), |_| send_ctrl_z(press_enter));
+                let _ = worker_tx.send(input_sent("EOF", None, result));
+            }
+            Ok(UiMessage::SendAltKey(key)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_alt_key(key), |_| send_alt_key(key));
+                let _ = worker_tx.send(input_sent(format!("Alt+{}", key), None, result));
+            }
+            Ok(UiMessage::SendAltCode(digits)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.send_alt_code(&digits), |_| send_alt_code(&digits));
+                let _ = worker_tx.send(input_sent(format!("Alt+{}", digits), None, result));
+            }
+            Ok(UiMessage::SendMouseClick { x, y, button }) => {
+                let result = run_input(
+                    backend,
+                    current_pid,
+                    &route,
+                    &mut held,
+                    helper.as_ref(),
+                    input,
+                    |h| h.send_mouse_click(x, y, button),
+                    |_| send_mouse_click(x, y, button),
+                );
+                let _ = worker_tx.send(input_sent("click", None, result));
+            }
+            Ok(UiMessage::SetQuickEdit(enable)) => {
+                let result = run_input(backend, current_pid, &route, &mut held, helper.as_ref(), input, |h| h.set_quick_edit(enable), |_| set_quick_edit(enable));
+                let _ = worker_tx.send(input_sent("Quick-Edit change", None, result));
+            }
+            Ok(UiMessage::ClearScreen { direct }) => {
+                let result = run_input(
+                    backend,
+                    current_pid,
+                    &route,
+                    &mut held,
+                    helper.as_ref(),
+                    input,
+                    |h| if direct { h.clear_console() } else { h.send_command("cls") },
+                    |b| if direct { clear_console() } else { b.send_command("cls") },
+                );
+                let _ = worker_tx.send(input_sent("clear", None, result));
             }
-            Ok(UiMessage::SetLines(n)) => {
-                lines = n;
+            Ok(UiMessage::TakeSnapshot(kind)) => {
+                let data = run_input(
+                    backend,
+                    current_pid,
+                    &route,
+                    &mut held,
+                    helper.as_ref(),
+                    input,
+                    |_| Err(anyhow::anyhow!("Snapshots are not available through the elevated helper")),
+                    |_| match kind {
+                        SnapshotKind::Json => snapshot_json().map(String::into_bytes),
+                        #[cfg(feature = "gui")]
+                        SnapshotKind::Png => render_console_bitmap(),
+                    },
+                );
+                let _ = worker_tx.send(WorkerMessage::Snapshot { kind, data: data.map_err(|e| e.to_string()) });
+            }
+            Ok(UiMessage::ReadRegion(rect)) => {
+                let lines = run_input(
+                    backend,
+                    current_pid,
+                    &route,
+                    &mut held,
+                    helper.as_ref(),
+                    input,
+                    |_| Err(anyhow::anyhow!("Region reads are not available through the elevated helper")),
+                    |_| read_console_region(rect),
+                );
+                let lines = lines.map(|lines| lines.into_iter().map(|l| l.text).collect()).map_err(|e| e.to_string());
+                let _ = worker_tx.send(WorkerMessage::Region { rect, lines });
+            }
+            Ok(UiMessage::Pause) => {
+                paused = true;
+                let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Paused));
+            }
+            Ok(UiMessage::Resume) => {
+                paused = false;
+                next_poll = Instant::now();
+                let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Resumed));
+            }
+            Ok(UiMessage::ReadOnceAfter(delay)) => {
+                read_once = Some(Instant::now() + delay);
             }
             Ok(UiMessage::Stop) => {
-                if current_pid.is_some() {
-                    let _ = detach_from_console();
+                if let Some(h) = helper.take() {
+                    let _ = h.detach();
+                }
+                if let Some(old) = current_pid {
+                    let _turn = wait_turn();
+                    backend.detach();
+                    notify(AttachEvent::Detached { pid: old });
                 }
                 break;
             }
             Err(_) => {}
         }
 
-        // Poll console if attached
-        if let Some(pid) = current_pid {
-            // Reattach for this operation
-            if let Err(e) = attach_to_console(pid) {
+        // Without a target (detached, or the attach failed) the session is over
+        if current_pid.is_none() {
+            ctrl_guard = None;
+        }
+
+        // Remember the target in case the loop has to be started again
+        if restart.target.as_ref().map(|(pid, _)| *pid) != current_pid {
+            restart.target = current_pid.map(|pid| (pid, helper.clone()));
+        }
+
+        backlog.flush(worker_tx);
+
+        // Tell the UI this thread is not stuck, even while nothing is polled
+        if last_heartbeat.is_none_or(|t| t.elapsed() >= HEARTBEAT_INTERVAL) {
+            let _ = worker_tx.try_send(WorkerMessage::Heartbeat);
+            last_heartbeat = Some(Instant::now());
+        }
+        if let Some(period) = stats.take_if_due() {
+            let _ = worker_tx.try_send(WorkerMessage::Stats(period));
+        }
+
+        // Liveness check: the process handle is signaled once the target exits
+        if let Some(pid) = current_pid.filter(|_| watch.as_ref().is_some_and(|w| !w.is_alive())) {
+            // Others may keep the console open, with the target's last words on it
+            let window = if console.alternate { ReadWindow::Viewport } else { read_window };
+            let last = final_read(backend, route.attach_pid(pid), &mut held, helper.as_ref(), lines, &mut stream, window)
+                .map(|screen| filter_screen(filter.as_ref(), screen));
+            if let Some(h) = helper.take() {
+                let _ = h.detach();
+            }
+            held.release(backend);
+            notify(AttachEvent::Detached { pid });
+            let _ = worker_tx.send(WorkerMessage::Health { alive: false, last_seen });
+            let _ = worker_tx.send(WorkerMessage::Disconnected);
+            if let Some(screen) = last {
+                let _ = worker_tx.send(WorkerMessage::Final { lines: screen, timestamp: Instant::now() });
+            }
+            current_pid = None;
+            ctrl_guard = None;
+            last_output.reset();
+            console = ConsoleIdentity::default();
+            stream.reset();
+            tail = CursorMarker::default();
+            watch = None;
+            last_seen = None;
+            continue;
+        }
+
+        // Until the next poll is due, handle UI messages as they arrive and,
+        // with a change watch, poll as soon as the console draws something;
+        // while paused only a scheduled read is due and drawing is ignored
+        let due = if paused { read_once } else { Some(read_once.map_or(next_poll, |t| t.min(next_poll))) };
+        let wait = due.map_or(HEARTBEAT_INTERVAL, |t| t.saturating_duration_since(Instant::now()));
+        if !wait.is_zero() {
+            let none = never();
+            let changes = console.changes.as_ref().filter(|_| !paused).map_or(&none, |w| w.changes());
+            select! {
+                // A UI that went away without saying Stop still stops us
+                recv(ui_rx) -> msg => pending_ui = Some(msg.unwrap_or(UiMessage::Stop)),
+                recv(changes) -> _ => {
+                    if let Some(watch) = &console.changes {
+                        watch.settle(CHANGE_SETTLE);
+                    }
+                    next_poll = Instant::now();
+                }
+                default(wait.min(HEARTBEAT_INTERVAL)) => {}
+            }
+            continue;
+        }
+
+        // A scheduled read runs whatever the budget says
+        let capture = read_once.is_some_and(|t| t <= Instant::now());
+        if capture {
+            read_once = None;
+        }
+        let mut captured: Option<Vec<String>> = None;
+
+        // Back off for one interval after a poll that exceeded the CPU budget
+        if skip_next_poll && !capture {
+            skip_next_poll = false;
+            next_poll = Instant::now() + interval;
+            continue;
+        }
+        let poll_started = Instant::now();
+        let mut output_changed = false;
+
+        // Poll through the elevated helper if that is how we attached
+        if let (Some(_), Some(h)) = (current_pid, &helper) {
+            match h.read_lines(lines) {
+                Ok(output_lines) => {
+                    last_seen = Some(Instant::now());
+                    errors.clear(HELPER_READ_ERROR);
+                    if capture {
+                        captured = Some(output_lines.clone());
+                    }
+                    let output_lines = match &filter {
+                        Some(f) => {
+                            let texts: Vec<&str> = output_lines.iter().map(String::as_str).collect();
+                            f.select(&texts).into_iter().map(|i| output_lines[i].clone()).collect()
+                        }
+                        None => output_lines,
+                    };
+                    if let Some(changed) = last_output.compare(&output_lines) {
+                        output_changed = true;
+                        stats.output(output_lines.iter().map(String::as_str));
+                        backlog.send(worker_tx, WorkerMessage::Output {
+                            lines: output_lines,
+                            colors: Vec::new(),
+                            changed,
+                            timestamp: Instant::now(),
+                        });
+                    }
+                }
+                Err(e) => {
+                    notify(AttachEvent::Error { pid: current_pid, message: e.to_string() });
+                    if let Some(message) = errors.report(HELPER_READ_ERROR, &e.to_string()) {
+                        let _ = worker_tx.send(WorkerMessage::Error(message));
+                    }
+                }
+            }
+        } else if let Some(pid) = current_pid {
+            // Hold the console until the read is done and we have detached
+            let turn = wait_turn();
+
+            // Reattach for this operation, through whichever route worked,
+            // unless we stayed attached since the last one
+            take_read_timings();
+            let attach_started = Instant::now();
+            let attached = if held.reuse(&turn) { Ok(()) } else { backend.attach(route.attach_pid(pid)) };
+            let attach = attach_started.elapsed();
+            if held.take_fell_back() {
+                let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::StayAttachedOff));
+            }
+            if let Err(e) = attached {
+                notify(AttachEvent::Error { pid: Some(pid), message: e.to_string() });
+                stats.attach_failures += 1;
+                // A console that is only briefly unreachable gets a few more tries
+                if failed_attaches < reconnect_attempts {
+                    failed_attaches += 1;
+                    let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Reconnecting { attempt: failed_attaches }));
+                    next_poll = Instant::now() + RECONNECT_BACKOFF * 2u32.pow(failed_attaches - 1);
+                    // A scheduled read is retried with the reattach
+                    if capture {
+                        read_once = Some(next_poll);
+                    }
+                    continue;
+                }
+                failed_attaches = 0;
+                notify(AttachEvent::Detached { pid });
                 let _ = worker_tx.send(WorkerMessage::Disconnected);
                 current_pid = None;
-                last_output = None;
+                ctrl_guard = None;
+                last_output.reset();
+                console = ConsoleIdentity::default();
+                stream.reset();
+                tail = CursorMarker::default();
+                watch = None;
+                last_seen = None;
                 continue;
             }
+            if failed_attaches > 0 {
+                failed_attaches = 0;
+                let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::Reconnected { pid }));
+            }
 
-            // Read console output
-            match read_console_lines(lines) {
-                Ok(output_lines) => {
-                    let output = output_lines.join("\n");
+            // Watch who shares the console we are reading
+            for msg in console.observe(pid) {
+                let _ = worker_tx.send(msg);
+            }
+            console.watch_changes(event_driven);
 
-                    // Only send if output changed
-                    if last_output.as_ref() != Some(&output) {
-                        last_output = Some(output.clone());
-                        let _ = worker_tx.send(WorkerMessage::Output {
-                            lines: output_lines,
+            // Hand every line that scrolled past the cursor to the history;
+            // a full-screen app's alternate buffer has none
+            if let Some((stitched, next)) = (!console.alternate).then(|| read_new_lines_since(&tail)).and_then(Result::ok) {
+                tail = next;
+                if stitched.cleared {
+                    let _ = worker_tx.send(WorkerMessage::Event(WorkerEvent::BufferCleared));
+                }
+                history.push(&stitched.lines);
+                let lines = match filter.as_mut() {
+                    Some(f) => f.stream(stitched.lines),
+                    None => stitched.lines,
+                };
+                if !lines.is_empty() || stitched.gap {
+                    let _ = worker_tx.send(WorkerMessage::History { lines, gap: stitched.gap });
+                }
+            }
+
+            // Read console output; only the screen matters while a full-screen app runs
+            let window = if console.alternate { ReadWindow::Viewport } else { read_window };
+            let polled = backend.poll(&mut stream, window);
+            let read_failed = polled.is_err();
+            if polled.is_ok() && stream.cursor() != last_cursor {
+                last_cursor = stream.cursor();
+                let _ = worker_tx.send(WorkerMessage::Cursor { position: last_cursor });
+            }
+            match polled {
+                Ok(events) => {
+                    // A successful buffer query means the console is still there
+                    last_seen = Some(Instant::now());
+                    errors.clear(READ_ERROR);
+                    if capture {
+                        captured = Some(stream.lines().iter().map(|l| l.text.clone()).collect());
+                    }
+
+                    // Only send if a line changed; a filtered screen goes as a
+                    // whole, since its lines move as matches come and go
+                    if let (Some(f), false) = (&filter, events.is_empty()) {
+                        output_changed = true;
+                        let screen = stream.lines();
+                        let texts: Vec<&str> = screen.iter().map(|l| l.text.as_str()).collect();
+                        let keep = f.select(&texts);
+                        let lines: Vec<String> = keep.iter().map(|&i| screen[i].text.clone()).collect();
+                        if let Some(changed) = last_output.compare(&lines) {
+                            stats.output(lines.iter().map(String::as_str));
+                            backlog.send(worker_tx, WorkerMessage::Output {
+                                colors: keep.iter().map(|&i| screen[i].runs.clone()).collect(),
+                                lines,
+                                changed,
+                                timestamp: Instant::now(),
+                            });
+                        }
+                    } else if !events.is_empty() {
+                        output_changed = true;
+                        let lines = events.into_iter()
+                            .filter_map(|e| match e {
+                                LineEvent::Added { index, line } | LineEvent::Changed { index, line } => Some((index, line)),
+                                LineEvent::Cleared { .. } => None,
+                            })
+                            .collect::<Vec<_>>();
+                        stats.output(lines.iter().map(|(_, l)| l.text.as_str()));
+                        backlog.send(worker_tx, WorkerMessage::Delta {
+                            len: stream.lines().len(),
+                            lines,
                             timestamp: Instant::now(),
                         });
                     }
                 }
                 Err(e) => {
+                    notify(AttachEvent::Error { pid: Some(pid), message: e.to_string() });
                     // Don't spam errors - only send if we haven't sent one recently
-                    let _ = worker_tx.send(WorkerMessage::Error(format!("Read error: {}", e)));
+                    if let Some(message) = errors.report(READ_ERROR, &e.to_string()) {
+                        let _ = worker_tx.send(WorkerMessage::Error(message));
+                    }
                 }
             }
 
-            // Detach after reading
-            let _ = detach_from_console();
+            // Detach after reading, or stay attached for the next poll
+            if read_failed || !held.keep(&turn) {
+                backend.detach();
+            }
+            let _ = worker_tx.try_send(WorkerMessage::Timings(PollTimings {
+                attach,
+                read: take_read_timings(),
+                total: poll_started.elapsed(),
+            }));
+        }
+
+        if capture {
+            let _ = worker_tx.send(match captured {
+                Some(lines) => WorkerMessage::Captured { lines, timestamp: Instant::now() },
+                None if current_pid.is_none() => WorkerMessage::Error("Scheduled read skipped: not attached".to_string()),
+                None => WorkerMessage::Error("Scheduled read failed".to_string()),
+            });
+        }
+
+        if current_pid.is_some() {
+            stats.poll(poll_started.elapsed());
+        }
+
+        if let Some(budget) = poll_budget {
+            skip_next_poll = current_pid.is_some() && poll_started.elapsed() > budget;
         }
 
-        // Sleep for the configured interval
-        thread::sleep(interval);
+        if current_pid.is_some() {
+            let alive = watch.as_ref().is_none_or(|w| w.is_alive());
+            let _ = worker_tx.try_send(WorkerMessage::Health { alive, last_seen });
+        }
+
+        sleep_for = match adaptive {
+            Some(bounds) => bounds.next(sleep_for, output_changed),
+            None => interval,
+        };
+
+        // Poll again after the interval; with a change watch, once the console
+        // draws something or the quiet gap passes: the adaptive one when set,
+        // otherwise IDLE_POLL
+        let gap = match adaptive {
+            None if console.changes.is_some() => interval.max(IDLE_POLL),
+            _ => sleep_for,
+        };
+        next_poll = Instant::now() + gap;
+    }
+}
+
+/// Perform one input operation on the session's target: through the elevated
+/// helper when attached that way, otherwise attached on this thread for just
+/// the operation, between polls, or on the attachment kept since the last one.
+/// Local writes follow the session's `input` settings; the helper was given
+/// them with [`push_input_settings`].
+// Each argument is a separate local of the worker loop, which other arms change
+#[allow(clippy::too_many_arguments)]
+fn run_input<B: ConsoleBackend, T>(
+    backend: &mut B,
+    pid: Option<u32>,
+    route: &AttachRoute,
+    held: &mut HeldAttachment,
+    helper: Option<&HelperClient>,
+    input: InputSettings,
+    via_helper: impl FnOnce(&HelperClient) -> anyhow::Result<T>,
+    local: impl FnOnce(&mut B) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let Some(pid) = pid else {
+        return Err(anyhow::anyhow!("Not attached to any console"));
+    };
+    if let Some(h) = helper {
+        return via_helper(h);
+    }
+    let turn = wait_turn();
+    if !held.reuse(&turn) {
+        backend.attach(route.attach_pid(pid))
+            .map_err(|e| anyhow::anyhow!("Failed to attach: {}", e))?;
+    }
+    let result = with_input_settings(input, || local(backend));
+    if !held.keep(&turn) {
+        backend.detach();
+    }
+    result
+}
+
+/// Give the elevated helper the session's input settings; it writes with
+/// them until told otherwise
+fn push_input_settings(helper: &HelperClient, input: InputSettings) -> anyhow::Result<()> {
+    helper.set_typing_delay(input.typing_delay)?;
+    helper.set_enter_sends_lf(input.enter_sends_lf)?;
+    helper.set_dry_run(input.dry_run)
+}
+
+/// Report the outcome of an input operation to the UI
+fn input_sent(label: impl Into<String>, command: Option<String>, result: anyhow::Result<()>) -> WorkerMessage {
+    WorkerMessage::InputSent { label: label.into(), command, error: result.err().map(|e| e.to_string()) }
+}
+
+/// One last read of the target before letting it go: through the helper
+/// when attached that way, otherwise attached on this thread. None when
+/// the console cannot be reached any more.
+fn final_read(
+    backend: &mut impl ConsoleBackend,
+    attach_pid: u32,
+    held: &mut HeldAttachment,
+    helper: Option<&HelperClient>,
+    lines: usize,
+    stream: &mut ConsoleStream,
+    window: ReadWindow,
+) -> Option<Vec<StyledLine>> {
+    Some(match helper {
+        Some(h) => h.read_lines(lines).ok()?
+            .into_iter()
+            .map(|text| StyledLine { text, runs: Vec::new() })
+            .collect(),
+        None => {
+            let turn = wait_turn();
+            if !held.reuse(&turn) && backend.attach(attach_pid).is_err() {
+                return None;
+            }
+            let read = backend.poll(stream, window).map(|_| stream.lines().to_vec());
+            held.forget();
+            backend.detach();
+            read.ok()?
+        }
+    })
+}
+
+/// Screen lines filtered like the output was
+fn filter_screen(filter: Option<&LineFilter>, screen: Vec<StyledLine>) -> Vec<StyledLine> {
+    match filter {
+        Some(f) => {
+            let texts: Vec<&str> = screen.iter().map(|l| l.text.as_str()).collect();
+            f.select(&texts).into_iter().map(|i| screen[i].clone()).collect()
+        }
+        None => screen,
+    }
+}
+
+/// Code page repair and VT decoding applied to each poll's lines, in that order
+fn line_transform(decode_vt: bool, text_decoding: TextDecoding) -> Option<LineTransform> {
+    if !decode_vt && text_decoding == TextDecoding::AsShown {
+        return None;
+    }
+    Some(Box::new(move |mut lines| {
+        if let TextDecoding::CodePage(actual) = text_decoding {
+            lines = redecode(&lines, console_code_pages().1, actual);
+        }
+        if decode_vt {
+            lines = interpret_vt(&lines);
+        }
+        lines
+    }))
+}
+
+/// What we know about the console being read, used to spot ownership changes
+#[derive(Default)]
+struct ConsoleIdentity {
+    window: Option<isize>,
+    members: Vec<u32>,
+    owner_lost: bool,
+    title: Option<String>,
+    selection: Option<ConsoleSelection>,
+    modes: Option<ConsoleModes>,
+    metrics: Option<ConsoleMetrics>,
+    code_pages: Option<(u32, u32)>,
+    cursor_shape: Option<CursorShape>,
+    /// Height of the last buffer seen with scrollback, i.e. the main screen buffer
+    main_height: Option<usize>,
+    /// The active buffer is an alternate screen without scrollback
+    alternate: bool,
+    /// Wake-ups for output drawn in `window`, when it raises them
+    changes: Option<ChangeWatch>,
+    /// Window a change watch was last tried for, so a failure is not retried every poll
+    change_window: Option<isize>,
+}
+
+impl ConsoleIdentity {
+    /// Compare the attached console against the last poll and report changes.
+    ///
+    /// The target "loses" the console when the console window changes under
+    /// the same PID (it moved to a new console) or when it no longer appears
+    /// in GetConsoleProcessList (it detached while others kept the console).
+    fn observe(&mut self, target: u32) -> Vec<WorkerMessage> {
+        let mut out = Vec::new();
+        let our_pid = std::process::id();
+        let window = console_window_id();
+        let members: Vec<u32> = console_process_list().into_iter().filter(|p| *p != our_pid).collect();
+
+        let moved = self.window.is_some_and(|w| w != window);
+        let lost = moved || !members.contains(&target);
+        if lost && !self.owner_lost {
+            let sharing = if moved { &self.members } else { &members };
+            out.push(WorkerMessage::OwnerChanged {
+                original: target,
+                candidates: sharing.iter().copied().filter(|p| *p != target).collect(),
+            });
+        }
+        self.owner_lost = lost && !moved;
+
+        if members != self.members {
+            out.push(WorkerMessage::ConsoleMembers { pids: members.clone() });
+        }
+        self.window = Some(window);
+        self.members = members;
+
+        let title = console_title();
+        if self.title.as_ref() != Some(&title) {
+            out.push(WorkerMessage::Event(WorkerEvent::TitleChanged { title: title.clone() }));
+            self.title = Some(title);
+        }
+
+        let selection = console_selection().unwrap_or(None);
+        if selection != self.selection {
+            out.push(WorkerMessage::Selection(selection.clone()));
+            self.selection = selection;
+        }
+
+        if let Ok(modes) = console_modes()
+            && self.modes != Some(modes)
+        {
+            out.push(WorkerMessage::Modes(modes));
+            self.modes = Some(modes);
+        }
+
+        if let Ok(metrics) = console_metrics() {
+            // CONOUT$ always opens the active buffer, so a switch shows up as the
+            // scrollback disappearing; a buffer that never had any (ConPTY) is not one
+            let alternate = if metrics.buffer.1 > metrics.window.1 {
+                self.main_height = Some(metrics.buffer.1);
+                false
+            } else {
+                self.main_height.is_some()
+            };
+            if alternate != self.alternate {
+                out.push(WorkerMessage::AlternateScreen(alternate));
+                self.alternate = alternate;
+            }
+            if self.metrics.as_ref() != Some(&metrics) {
+                out.push(WorkerMessage::Metrics(metrics.clone()));
+                self.metrics = Some(metrics);
+            }
+        }
+
+        let code_pages = console_code_pages();
+        if self.code_pages != Some(code_pages) {
+            out.push(WorkerMessage::CodePages { input: code_pages.0, output: code_pages.1 });
+            self.code_pages = Some(code_pages);
+        }
+
+        if let Ok(shape) = cursor_shape()
+            && self.cursor_shape != Some(shape)
+        {
+            out.push(WorkerMessage::CursorShape(shape));
+            self.cursor_shape = Some(shape);
+        }
+        out
+    }
+}
+
+impl ConsoleIdentity {
+    /// Start or stop the change watch once the console window is known
+    fn watch_changes(&mut self, enabled: bool) {
+        if !enabled {
+            self.changes = None;
+            self.change_window = None;
+        } else if self.window.is_some() && self.change_window != self.window {
+            self.change_window = self.window;
+            self.changes = self.window.and_then(|w| ChangeWatch::start(w).ok());
+        }
+    }
+}
+
+/// Switch the calling thread between normal and below-normal priority
+fn set_thread_low_priority(low: bool) {
+    use windows::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_NORMAL,
+    };
+
+    let priority = if low { THREAD_PRIORITY_BELOW_NORMAL } else { THREAD_PRIORITY_NORMAL };
+    unsafe {
+        let _ = SetThreadPriority(GetCurrentThread(), priority);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> StyledLine {
+        StyledLine { text: text.to_string(), runs: Vec::new() }
+    }
+
+    fn delta(len: usize, lines: &[(usize, &str)]) -> WorkerMessage {
+        let lines = lines.iter().map(|&(i, t)| (i, line(t))).collect();
+        WorkerMessage::Delta { len, lines, timestamp: Instant::now() }
+    }
+
+    fn output(lines: &[&str], changed: &[usize]) -> WorkerMessage {
+        WorkerMessage::Output {
+            lines: lines.iter().map(|l| l.to_string()).collect(),
+            colors: vec![Vec::new(); lines.len()],
+            changed: changed.to_vec(),
+            timestamp: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn backlog_merges_deltas_and_drops_lines_past_len() {
+        let (tx, rx) = bounded(1);
+        tx.send(output(&["blocker"], &[])).unwrap();
+        let mut backlog = OutputBacklog::default();
+        backlog.send(&tx, delta(5, &[(1, "a"), (4, "gone")]));
+        backlog.send(&tx, delta(3, &[(1, "b"), (2, "c")]));
+
+        rx.recv().unwrap();
+        backlog.flush(&tx);
+        match rx.try_recv().unwrap() {
+            WorkerMessage::Delta { len, lines, .. } => {
+                assert_eq!(len, 3);
+                assert_eq!(lines, vec![(1, line("b")), (2, line("c"))]);
+            }
+            _ => panic!("expected a delta"),
+        }
+        assert!(backlog.pending.is_none());
+    }
+
+    #[test]
+    fn backlog_merges_outputs_with_union_of_changed() {
+        let (tx, rx) = bounded(1);
+        tx.send(delta(0, &[])).unwrap();
+        let mut backlog = OutputBacklog::default();
+        backlog.send(&tx, output(&["a", "b", "c", "d"], &[3, 0]));
+        backlog.send(&tx, output(&["a", "b", "c"], &[1, 0]));
+
+        rx.recv().unwrap();
+        backlog.flush(&tx);
+        match rx.try_recv().unwrap() {
+            WorkerMessage::Output { lines, changed, .. } => {
+                assert_eq!(lines, vec!["a", "b", "c"]);
+                assert_eq!(changed, vec![0, 1]);
+            }
+            _ => panic!("expected output"),
+        }
+    }
+
+    #[test]
+    fn replays_a_trace_through_run_input() {
+        let path = std::env::temp_dir().join(format!("remote_con_replay_{}.log", std::process::id()));
+        std::fs::write(&path, "# remote_con repro trace v1\n\
+            0\tui\tattach pid=42\n\
+            5\twin32\tattach_to_console ok\n\
+            6\twin32\tsend_command ok\n\
+            7\twin32\tattach_to_console ok\n\
+            7\twin32\tread_console_lines ok\n\
+            7\tline\tX:\\> xxx\n\
+            10\twin32\tattach_to_console ok\n\
+            11\twin32\tsend_command err Xxx xxxxxx xx xxxxxxx\n\
+            20\twin32\tattach_to_console err Xxxxxx xx xxxxxx\n").unwrap();
+        let mut mock = crate::repro::MockConsole::from_file(&path).unwrap();
+        assert_eq!(mock.target(), Some(42));
+        let _ = std::fs::remove_file(&path);
+
+        let mut held = HeldAttachment::new(false);
+        let send = |mock: &mut crate::repro::MockConsole, held: &mut HeldAttachment| run_input(
+            mock,
+            Some(42),
+            &AttachRoute::Direct,
+            held,
+            None,
+            InputSettings::default(),
+            |h| h.send_command("dir"),
+            |b| b.send_command("dir"),
+        );
+        assert!(send(&mut mock, &mut held).is_ok());
+        let mut stream = ConsoleStream::new();
+        let screen = final_read(&mut mock, 42, &mut held, None, 1, &mut stream, ReadWindow::ToCursor(1)).unwrap();
+        assert_eq!(screen.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(), vec!["X:\\> xxx"]);
+        assert_eq!(send(&mut mock, &mut held).unwrap_err().to_string(), "Xxx xxxxxx xx xxxxxxx");
+        assert!(send(&mut mock, &mut held).unwrap_err().to_string().starts_with("Failed to attach"));
+        assert_eq!(mock.remaining(), 0);
+    }
+
+    #[test]
+    fn adaptive_interval_backs_off_and_resets() {
+        let bounds = AdaptiveInterval { min: Duration::from_millis(100), max: Duration::from_millis(700) };
+        let mut gap = bounds.min;
+        let mut gaps = Vec::new();
+        for _ in 0..4 {
+            gap = bounds.next(gap, false);
+            gaps.push(gap.as_millis());
+        }
+        assert_eq!(gaps, vec![200, 400, 700, 700]);
+        assert_eq!(bounds.next(gap, true), bounds.min);
+
+        // A maximum below the minimum never makes the gap shorter than it
+        let inverted = AdaptiveInterval { min: Duration::from_millis(300), max: Duration::from_millis(100) };
+        assert_eq!(inverted.next(Duration::from_millis(300), false), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn history_ring_numbers_lines_across_drops() {
+        let text = |n: usize| (0..n).map(|i| format!("line {}", i)).collect::<Vec<_>>();
+        let mut ring = HistoryRing::new(3);
+        ring.push(&text(5));
+        assert_eq!(ring.total(), 5);
+        assert_eq!(ring.range(0, 10), (2, vec!["line 2".to_string(), "line 3".to_string(), "line 4".to_string()]));
+        assert_eq!(ring.range(3, 4), (3, vec!["line 3".to_string()]));
+        assert_eq!(ring.range(7, 9), (7, Vec::new()));
+
+        ring.set_capacity(1);
+        assert_eq!(ring.range(0, 10), (4, vec!["line 4".to_string()]));
+        ring.clear();
+        assert_eq!(ring.total(), 0);
+    }
+
+    #[test]
+    fn line_filter_keeps_matches_with_context() {
+        let filter = LineFilter::new(&FilterSpec { pattern: "err".to_string(), context: 1 }).unwrap();
+        assert_eq!(filter.select(&["a", "b", "err 1", "c", "d", "e", "err 2"]), vec![1, 2, 3, 5, 6]);
+        assert_eq!(filter.select(&["err", "err", "x"]), vec![0, 1, 2]);
+        assert!(LineFilter::new(&FilterSpec { pattern: "(".to_string(), context: 0 }).is_err());
+
+        // Context before a match is carried from one batch to the next
+        let mut filter = LineFilter::new(&FilterSpec { pattern: "err".to_string(), context: 1 }).unwrap();
+        let batch = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert!(filter.stream(batch(&["a", "b"])).is_empty());
+        assert_eq!(filter.stream(batch(&["err", "c", "d"])), batch(&["b", "err", "c"]));
+    }
+
+    #[test]
+    fn error_throttle_reports_a_kind_once_until_cleared() {
+        let mut errors = ErrorThrottle::default();
+        assert_eq!(errors.report(READ_ERROR, "gone").as_deref(), Some(format!("{}: gone", READ_ERROR).as_str()));
+        assert_eq!(errors.report(READ_ERROR, "gone"), None);
+        assert!(errors.report(HELPER_READ_ERROR, "gone").is_some());
+        errors.clear(READ_ERROR);
+        assert!(errors.report(READ_ERROR, "gone").is_some());
+    }
+
+    #[test]
+    fn coalesce_sends_older_kind_when_kinds_differ() {
+        let (tx, rx) = bounded(1);
+        tx.send(delta(0, &[])).unwrap();
+        let reader = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let first = rx.recv().unwrap();
+            let second = rx.recv().unwrap();
+            (first, second)
+        });
+
+        // Blocks until the reader makes room, then hands back the newer message
+        let newer = coalesce(&tx, output(&["old"], &[0]), delta(2, &[(1, "new")]));
+        assert!(matches!(newer, WorkerMessage::Delta { len: 2, .. }));
+
+        let (first, second) = reader.join().unwrap();
+        assert!(matches!(first, WorkerMessage::Delta { len: 0, .. }));
+        assert!(matches!(second, WorkerMessage::Output { ref lines, .. } if lines == &["old"]));
     }
 }
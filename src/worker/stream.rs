@@ -0,0 +1,94 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use crossbeam_channel::Sender;
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use super::{ConsoleWorker, UiMessage, WorkerConfig, WorkerMessage, WORKER_QUEUE, WORKER_THREAD_NAME};
+
+/// [`ConsoleWorker`] for async code: messages arrive as a [`Stream`]. The
+/// worker still polls on its own thread; a second thread moves its messages
+/// onto a tokio channel of the same size, so the worker's backpressure
+/// policy carries over.
+///
+/// It is one session outside any [`WorkerPool`](super::WorkerPool); like a
+/// pooled worker it restarts itself after a panic, and the `Crashed`
+/// message arrives on the stream.
+// Nothing in the app runs a tokio runtime yet; the facade is for servers built on the crate
+#[cfg_attr(not(test), allow(dead_code))]
+pub struct AsyncWorker {
+    ui_tx: Sender<UiMessage>,
+    messages: mpsc::Receiver<WorkerMessage>,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl AsyncWorker {
+    /// Start a worker and the thread that forwards its messages
+    pub fn new(config: WorkerConfig) -> anyhow::Result<Self> {
+        let ConsoleWorker { ui_tx, worker_rx, .. } = ConsoleWorker::new(config)?;
+        let (tx, messages) = mpsc::channel(WORKER_QUEUE);
+        thread::Builder::new()
+            .name(format!("{}-bridge", WORKER_THREAD_NAME))
+            .spawn(move || {
+                // Ends when the worker stops or the receiving side is dropped
+                while let Ok(msg) = worker_rx.recv() {
+                    if tx.blocking_send(msg).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to start worker bridge thread: {}", e))?;
+        Ok(Self { ui_tx, messages })
+    }
+
+    /// Send a message to the worker; the channel to it is unbounded, so
+    /// this never waits and needs no `.await`
+    pub fn send(&self, msg: UiMessage) -> anyhow::Result<()> {
+        self.ui_tx.send(msg)
+            .map_err(|e| anyhow::anyhow!("Failed to send message to worker: {}", e))
+    }
+
+    /// Next message from the worker; None once it has stopped
+    pub async fn recv(&mut self) -> Option<WorkerMessage> {
+        self.messages.recv().await
+    }
+}
+
+impl Stream for AsyncWorker {
+    type Item = WorkerMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<WorkerMessage>> {
+        self.messages.poll_recv(cx)
+    }
+}
+
+impl Drop for AsyncWorker {
+    fn drop(&mut self) {
+        let _ = self.ui_tx.send(UiMessage::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_on_a_tokio_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let mut worker = AsyncWorker::new(WorkerConfig::default()).unwrap();
+            // Without a target the worker answers without touching a console
+            worker.send(UiMessage::SendCommand("dir".to_string())).unwrap();
+            loop {
+                match worker.recv().await {
+                    Some(WorkerMessage::InputSent { error, .. }) => {
+                        assert_eq!(error.as_deref(), Some("Not attached to any console"));
+                        break;
+                    }
+                    Some(_) => continue,
+                    None => panic!("worker stopped before answering"),
+                }
+            }
+        });
+    }
+}